@@ -1,5 +1,15 @@
-use std::fs::{File, create_dir_all};
-use std::io::{Read, Write, Result};
+// The shared modules import from alloc, which only the crate root can declare
+extern crate alloc;
+
+use std::fs::create_dir_all;
+use std::io::{Error, ErrorKind, Result};
+
+// Build scripts can't link the package's own library, so compile it in
+#[path = "src/lib.rs"]
+#[allow(dead_code)]
+mod sha_hasher;
+
+use sha_hasher::input_gen::{write_atomic, write_input_bin};
 
 fn main() -> Result<()> {
     // Ensure the Bin and build directories exist
@@ -7,23 +17,26 @@ fn main() -> Result<()> {
     create_dir_all("build")?;
 
     let n: u64 = 5; // public input
-    let secret: [u8; 32] = [255; 32]; // private input
-
-    let mut pub_file = File::create("Bin/public.bin")?;
-    pub_file.write_all(&n.to_le_bytes())?;
+    let secret: &[u8] = &[255; 32]; // private input, up to secret::MAX_SECRET_LEN bytes
 
-    let mut priv_file = File::create("Bin/private.bin")?;
-    priv_file.write_all(&secret)?;
+    let mut public_bin = Vec::new();
+    sha_hasher::codec::write_u64_le(&mut public_bin, n);
+    write_atomic("Bin/public.bin", &public_bin)?;
+    write_atomic("Bin/private.bin", secret)?;
 
-    // Concatenate Bin/public.bin and Bin/private.bin into build/input.bin
-    let mut public = Vec::new();
-    let mut private = Vec::new();
-    File::open("Bin/public.bin")?.read_to_end(&mut public)?;
-    File::open("Bin/private.bin")?.read_to_end(&mut private)?;
+    let public = sha_hasher::Public {
+        n,
+        algorithm: sha_hasher::hasher::Algorithm::Sha256, // or Keccak256
+        checkpoint_interval: 0, // e.g. 1 to print every round's hash
+        secret_padding: sha_hasher::secret::Padding::None, // or Fill(0), Pkcs7 for a shorter secret
+    };
+    let secret = sha_hasher::secret::pad(secret, public.secret_padding);
 
-    let mut input_file = File::create("build/input.bin")?;
-    input_file.write_all(&public)?;
-    input_file.write_all(&private)?;
+    // build/input.bin uses the shared layout (public fields and length-prefixed
+    // secret, framed by input_gen with the header and CRC)
+    let payload = sha_hasher::serialize_payload(&public, &secret)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    write_input_bin("build/input.bin", &payload)?;
 
     Ok(())
 }
@@ -1,36 +1,1234 @@
-use reqwest;
-use serde_json::Value;
-use std::fs::{self, File};
-use std::io::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Fetch ETH data from API
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd&include_24hr_change=true")
-        .send()
-        .await?;
-    
-    let data: Value = response.json().await?;
-    
-    // Extract values
-    let eth_price = (data["ethereum"]["usd"].as_f64().unwrap() * 100.0) as u64;
-    let price_change_24h = (data["ethereum"]["usd_24h_change"].as_f64().unwrap() * 100.0) as i64;
-    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    
-    // Write to input.bin
+// The included modules are written for the no_std library, against alloc
+extern crate alloc;
+
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+#[cfg(feature = "network")]
+use std::time::Duration;
+
+use clock::{Clock, FixedClock, SystemClock};
+use codec::hex;
+use config::Config;
+use input_gen::write_atomic;
+
+#[path = "src/clock.rs"]
+mod clock;
+#[path = "src/config.rs"]
+mod config;
+#[path = "../../src/codec.rs"]
+#[allow(dead_code)]
+mod codec;
+#[path = "../../src/crc32.rs"]
+#[allow(dead_code)]
+mod crc32;
+#[path = "../../src/fixed.rs"]
+#[allow(dead_code)]
+mod fixed;
+#[path = "../../src/header.rs"]
+#[allow(dead_code)]
+mod header;
+#[path = "../../src/input_gen.rs"]
+#[allow(dead_code)]
+mod input_gen;
+#[path = "src/market.rs"]
+#[allow(dead_code)]
+mod market;
+#[path = "src/percent.rs"]
+mod percent;
+#[path = "src/output_layout.rs"]
+#[allow(dead_code)]
+mod output_layout;
+#[path = "src/indicators.rs"]
+#[allow(dead_code)]
+mod indicators;
+#[path = "src/strategy.rs"]
+#[allow(dead_code)]
+mod strategy;
+
+// CoinGecko ids, in the order their records are written to input.bin,
+// unless ZKMD_COINS (or config.toml's `coins`) lists others,
+// comma-separated. Three fit any input publishing up to 11 runs
+// (output_layout::Layout::max_records); more refuse to build.
+const DEFAULT_COINS: &str = "bitcoin,ethereum,solana";
+
+// Retries after the first attempt, unless ZKMD_FETCH_RETRIES or
+// config.toml says otherwise
+#[cfg(feature = "network")]
+const DEFAULT_FETCH_RETRIES: u32 = 3;
+#[cfg(feature = "network")]
+const RETRY_BASE_DELAY_MS: u64 = 200;
+
+// Per-request timeout unless ZKMD_HTTP_TIMEOUT (seconds) or config.toml says
+// otherwise
+#[cfg(feature = "network")]
+const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 10;
+
+// How much of an unexpected response body to quote in an error
+const BODY_SNIPPET_LEN: usize = 200;
+
+// Last successful fetch, reused when the network is unavailable
+#[cfg(feature = "network")]
+const CACHE_PATH: &str = "build/last_fetch.json";
+
+// The published inputs, read back by `cargo run --bin verify`
+const PUBLIC_PATH: &str = "build/public.json";
+
+// The raw bodies behind the latest input.bin, kept for auditing
+const RESPONSE_PATH: &str = "build/market_response.json";
+
+// CoinGecko vs_currency, unless ZKMD_CURRENCY or config.toml names another
+const DEFAULT_CURRENCY: &str = "usd";
+
+// Hysteresis margin when ZKMD_PREV_SIGNALS is set without ZKMD_HYSTERESIS_BPS
+const DEFAULT_HYSTERESIS_BPS: u64 = 100;
+
+// Every variable this script reads, directly or through config.rs, so cargo
+// reruns it when one changes instead of keeping a stale input.bin. Add a
+// variable here along with the env::var or config.var that reads it.
+const ENV_VARS: &[&str] = &[
+    "ZKMD_ACCOUNT_BALANCE",
+    "ZKMD_BUY_THRESHOLD",
+    "ZKMD_BUY_THRESHOLD_PCT",
+    "ZKMD_COINS",
+    "ZKMD_COMPRESS",
+    "ZKMD_CONFIG",
+    "ZKMD_CURRENCY",
+    "ZKMD_DIRECTION",
+    "ZKMD_EMA_PERIOD",
+    "ZKMD_FETCH_RETRIES",
+    "ZKMD_FIXED_TIME",
+    "ZKMD_FIXTURE",
+    "ZKMD_HISTORY_DAYS",
+    "ZKMD_HTTP_TIMEOUT",
+    "ZKMD_HYSTERESIS_BPS",
+    "ZKMD_LAST_TRADE_TIME",
+    "ZKMD_MAX_AGE_SECS",
+    "ZKMD_MAX_CHANGE_BPS",
+    "ZKMD_MIN_HOLD_SECS",
+    "ZKMD_MIN_VOLUME",
+    "ZKMD_NEUTRAL_BAND_BPS",
+    "ZKMD_NONCE_FILE",
+    "ZKMD_OFFLINE",
+    "ZKMD_OUTPUT_MODE",
+    "ZKMD_PREV_SIGNALS",
+    "ZKMD_PRICE_BASIS",
+    "ZKMD_PRICE_DECIMALS",
+    "ZKMD_RECORD_SECRETS",
+    "ZKMD_RISK_BANDS",
+    "ZKMD_RISK_FRACTION",
+    "ZKMD_SELL_THRESHOLD",
+    "ZKMD_SELL_THRESHOLD_PCT",
+    "ZKMD_SIGNAL_MODE",
+    "ZKMD_SQUEEZE_BPS",
+    "ZKMD_STOP_BPS",
+    "ZKMD_TARGET_BPS",
+    "ZKMD_WEIGHTS",
+    "SOURCE_DATE_EPOCH",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "NO_PROXY",
+    "no_proxy",
+];
+
+// Binance base assets used when CoinGecko is unavailable
+#[cfg(feature = "network")]
+const BINANCE_ASSETS: [(&str, &str); 3] = [
+    ("bitcoin", "BTC"),
+    ("ethereum", "ETH"),
+    ("solana", "SOL"),
+];
+
+// One coin's market data in the requested currency, normalized across sources
+struct Quote {
+    coin: String,
+    price: f64,
+    // None when the source omitted it
+    change_24h_pct: Option<f64>,
+    volume: f64,
+}
+
+// Quotes with the time they were fetched and the source they came from;
+// every source but the cache records its responses
+struct Fetched {
+    quotes: Vec<Quote>,
+    fetched_at: u64,
+    source: market::DataSource,
+}
+
+// The hysteresis margin and each record's previous signal code
+struct Hysteresis {
+    margin_bps: u64,
+    prev_signals: Vec<u32>,
+}
+
+// A response body exactly as received, and where it came from
+struct RawResponse {
+    url: String,
+    body: String,
+}
+
+// Live market data, only with the `network` feature (on by default).
+// Without it reqwest and tokio aren't compiled in at all, so the build
+// can't reach the network and ZKMD_FIXTURE is the only data source.
+#[cfg(feature = "network")]
+struct Network {
+    client: Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+// The HTTP client, and how many times get_json retries a request on it.
+#[cfg(feature = "network")]
+struct Client {
+    http: reqwest::Client,
+    retries: u32,
+}
+
+#[cfg(feature = "network")]
+impl Network {
+    fn new(config: &Config) -> Result<Network, Box<dyn std::error::Error>> {
+        let retries = match config.var("ZKMD_FETCH_RETRIES") {
+            Some(v) => v.parse()?,
+            None => DEFAULT_FETCH_RETRIES,
+        };
+        let client = Client { http: http_client(config)?, retries };
+        Ok(Network { client, runtime: tokio::runtime::Runtime::new()? })
+    }
+
+    // Live quotes, or the cache with ZKMD_OFFLINE=1 or when every source
+    // fails (which records no responses).
+    fn quotes(
+        &self,
+        coins: &[String],
+        currency: &str,
+        clock: &dyn Clock,
+        responses: &mut Vec<RawResponse>,
+    ) -> Result<Fetched, Box<dyn std::error::Error>> {
+        if env::var("ZKMD_OFFLINE").is_ok_and(|v| v == "1") {
+            println!("cargo:warning=ZKMD_OFFLINE=1, skipping the network");
+            let (quotes, fetched_at) = load_cache(coins, currency)?;
+            return Ok(Fetched { quotes, fetched_at, source: market::DataSource::Cache });
+        }
+        match self.runtime.block_on(fetch_live(&self.client, coins, currency, responses)) {
+            Ok((source, quotes)) => {
+                let now = clock.now_secs()?;
+                save_cache(source, currency, &quotes, now)?;
+                Ok(Fetched { quotes, fetched_at: now, source })
+            }
+            Err(live_err) => {
+                let (quotes, fetched_at) = load_cache(coins, currency)
+                    .map_err(|cache_err| format!("{}; and no usable cache: {}", live_err, cache_err))?;
+                Ok(Fetched { quotes, fetched_at, source: market::DataSource::Cache })
+            }
+        }
+    }
+
+    fn daily_closes(
+        &self,
+        coin: &str,
+        currency: &str,
+        decimals: u32,
+        days: u32,
+        responses: &mut Vec<RawResponse>,
+    ) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+        self.runtime.block_on(fetch_daily_closes(&self.client, coin, currency, decimals, days, responses))
+    }
+}
+
+#[cfg(not(feature = "network"))]
+struct Network;
+
+#[cfg(not(feature = "network"))]
+const NO_NETWORK: &str = "built without the `network` feature: set ZKMD_FIXTURE to a simple/price fixture";
+
+#[cfg(not(feature = "network"))]
+impl Network {
+    fn new(_config: &Config) -> Result<Network, Box<dyn std::error::Error>> {
+        Ok(Network)
+    }
+
+    fn quotes(
+        &self,
+        _coins: &[String],
+        _currency: &str,
+        _clock: &dyn Clock,
+        _responses: &mut Vec<RawResponse>,
+    ) -> Result<Fetched, Box<dyn std::error::Error>> {
+        Err(NO_NETWORK.into())
+    }
+
+    fn daily_closes(
+        &self,
+        _coin: &str,
+        _currency: &str,
+        _decimals: u32,
+        _days: u32,
+        _responses: &mut Vec<RawResponse>,
+    ) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+        Err(NO_NETWORK.into())
+    }
+}
+
+// Any rerun-if directive replaces cargo's default of rerunning on every
+// package change; the script still reruns whenever it (or a module it
+// includes) is recompiled. The files it reads are watched as well. The nonce
+// file isn't, since this script rewrites it on every run, and a config.toml
+// only once it exists: cargo reruns a script on every build while a watched
+// file is missing.
+fn emit_rerun_directives() {
+    for name in ENV_VARS {
+        println!("cargo:rerun-if-env-changed={}", name);
+    }
+    let config_path = env::var("ZKMD_CONFIG").unwrap_or_else(|_| config::DEFAULT_CONFIG_PATH.to_string());
+    let inputs = [Some(config_path), env::var("ZKMD_FIXTURE").ok(), env::var("ZKMD_RECORD_SECRETS").ok()];
+    for path in inputs.into_iter().flatten().filter(|path| fs::metadata(path).is_ok()) {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    emit_rerun_directives();
     fs::create_dir_all("build")?;
-    let mut file = File::create("build/input.bin")?;
-    
-    file.write_all(&eth_price.to_le_bytes())?;
-    file.write_all(&price_change_24h.to_le_bytes())?;
-    file.write_all(&timestamp.to_le_bytes())?;
-    
-    println!("Updated: ETH ${:.2} ({:+.2}%)", 
-             eth_price as f64 / 100.0, 
-             price_change_24h as f64 / 100.0);
-    
+    let config = Config::load()?;
+    let network = Network::new(&config)?;
+    let clock = clock_from_env()?;
+    let coins: Vec<String> = config
+        .var("ZKMD_COINS")
+        .unwrap_or_else(|| DEFAULT_COINS.to_string())
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    let currency = config
+        .var("ZKMD_CURRENCY")
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string())
+        .to_ascii_lowercase();
+    let code = currency.to_ascii_uppercase().into_bytes();
+    if !market::is_currency(&code) {
+        return Err(format!("ZKMD_CURRENCY must be a 3-letter code, got {:?}", currency).into());
+    }
+    let currency_code = [code[0], code[1], code[2]];
+    let price_decimals = price_decimals_from_env()?;
+
+    // Live data when possible, otherwise the cached snapshot (and its
+    // timestamp)
+    // Responses are only recorded when they produced this input.bin; a build
+    // from the cache leaves the file from the fetch that wrote the cache.
+    let mut responses = Vec::new();
+    let (Fetched { quotes, fetched_at: timestamp, source: data_source }, live) =
+        if let Ok(path) = env::var("ZKMD_FIXTURE") {
+            println!("cargo:warning=ZKMD_FIXTURE={}, skipping the network", path);
+            let quotes = load_fixture(&path, &coins, &currency, &mut responses)?;
+            (Fetched { quotes, fetched_at: clock.now_secs()?, source: market::DataSource::Fixture }, false)
+        } else {
+            let fetched = network.quotes(&coins, &currency, clock.as_ref(), &mut responses)?;
+            let live = fetched.source != market::DataSource::Cache;
+            (fetched, live)
+        };
+
+    // Extract values
+    let mut records = Vec::new();
+    for quote in &quotes {
+        let price = fixed::to_fixed(quote.price, price_decimals)?;
+        let change_24h = match quote.change_24h_pct {
+            Some(pct) => market::ReturnBps::from_percent(pct)?,
+            None => market::CHANGE_UNAVAILABLE,
+        };
+        let volume = fixed::to_fixed(quote.volume, market::VOLUME_DECIMALS)?;
+        let change = match quote.change_24h_pct {
+            Some(_) => format!("{:+.2}%", change_24h.to_percent()),
+            None => "no 24h change".to_string(),
+        };
+        println!("Updated: {} {:.2} {} ({}, 24h volume {})",
+                 quote.coin,
+                 fixed::from_fixed(price, price_decimals),
+                 currency.to_ascii_uppercase(),
+                 change,
+                 volume);
+        records.push(market::Record { price, change_24h, timestamp, volume });
+    }
+
+    // The input.bin payload; input_gen adds the header and CRC
+    let mut input = Vec::new();
+    market::write_records(&mut input, &records)?;
+    if price_decimals != market::PRICE_DECIMALS {
+        println!("Price decimals: {}", price_decimals);
+        market::write_price_decimals(&mut input, price_decimals)?;
+    }
+    let thresholds = config.thresholds()?;
+    if let Some(thresholds) = &thresholds {
+        println!("Thresholds: BUY <= {} bps, SELL >= {} bps", thresholds.buy.bps(), thresholds.sell.bps());
+        market::write_thresholds(&mut input, thresholds)?;
+    }
+    let mut history = None;
+    match env::var("ZKMD_HISTORY_DAYS") {
+        Ok(_) if !live => {
+            println!("cargo:warning=no live data, skipping price history (signal falls back to thresholds)");
+        }
+        Ok(days) => {
+            let mut series = Vec::new();
+            for quote in &quotes {
+                let closes = network.daily_closes(&quote.coin, &currency, price_decimals, days.parse()?, &mut responses)?;
+                println!("History: {} {} daily closes", quote.coin, closes.len());
+                series.push(closes);
+            }
+            market::write_price_history(&mut input, &series)?;
+            history = Some(series);
+        }
+        Err(_) => {}
+    }
+    println!("Data source: {}", data_source.name());
+    market::write_data_source(&mut input, data_source)?;
+    let source_digest = if data_source == market::DataSource::Cache {
+        None
+    } else {
+        let digest = market::source_digest(responses.iter().map(|r| r.body.as_bytes()));
+        save_responses(data_source, timestamp, &responses, &digest)?;
+        println!("Source digest: {}", hex(&digest));
+        market::write_source_digest(&mut input, &digest)?;
+        Some(digest)
+    };
+    // Always written, so the guest publishes every record's age even
+    // without the freshness gate
+    let current_time = clock.now_secs()?;
+    market::write_current_time(&mut input, current_time)?;
+    let freshness = match env::var("ZKMD_MAX_AGE_SECS") {
+        Ok(v) => Some(market::Freshness {
+            current_time,
+            max_age_secs: v.parse()?,
+        }),
+        Err(_) => None,
+    };
+    if let Some(freshness) = &freshness {
+        println!("Freshness: records older than {}s are HOLD", freshness.max_age_secs);
+        market::write_freshness(&mut input, freshness)?;
+    }
+    let min_volume = env::var("ZKMD_MIN_VOLUME").ok().map(|v| v.parse()).transpose()?;
+    if let Some(min_volume) = min_volume {
+        println!("Liquidity: 24h volume below {} {} is HOLD", min_volume, currency.to_ascii_uppercase());
+        market::write_min_volume(&mut input, min_volume)?;
+    }
+    let squeeze_bps = env::var("ZKMD_SQUEEZE_BPS").ok().map(|v| v.parse()).transpose()?;
+    if let Some(squeeze_bps) = squeeze_bps {
+        println!("Squeeze: Bollinger band width below {} bps", squeeze_bps);
+        market::write_squeeze(&mut input, squeeze_bps)?;
+    }
+    let direction = match env::var("ZKMD_DIRECTION") {
+        Ok(name) => market::Direction::from_name(&name).ok_or_else(|| format!("unknown ZKMD_DIRECTION {:?}", name))?,
+        Err(_) => market::Direction::Long,
+    };
+    if direction != market::Direction::Long {
+        println!("Direction: {}", direction.name());
+        market::write_direction(&mut input, direction)?;
+    }
+    let levels = levels_from_env()?;
+    if let Some(levels) = &levels {
+        println!("Exit levels: stop {} bps per risk level, target +{} bps", levels.stop_bps, levels.target_bps);
+        market::write_levels(&mut input, levels)?;
+    }
+    let position = position_from_env(price_decimals)?;
+    if let Some(position) = &position {
+        println!("Position sizing: {:.2} {} balance, {:.2}% risked per trade",
+                 fixed::from_fixed(position.balance, price_decimals),
+                 currency.to_ascii_uppercase(),
+                 fixed::from_fixed(position.risk_fraction, market::RISK_FRACTION_DECIMALS) * 100.0);
+        market::write_position(&mut input, position)?;
+    }
+    if currency_code != market::DEFAULT_CURRENCY {
+        market::write_currency(&mut input, currency_code)?;
+    }
+    let mode = signal_mode_from_env()?;
+    if let Some(mode) = mode {
+        println!("Signal mode: {}", mode.name());
+        market::write_signal_mode(&mut input, mode)?;
+    }
+    let hysteresis = hysteresis_from_env(&quotes)?;
+    if let Some(h) = &hysteresis {
+        println!("Hysteresis: reversing a previous signal takes {} bps beyond the threshold", h.margin_bps);
+        market::write_hysteresis(&mut input, h.margin_bps, &h.prev_signals)?;
+    }
+    let (price_basis, ema_period) = price_basis_from_env()?;
+    if price_basis != market::PriceBasis::Raw {
+        println!("Price basis: {} over {} closes", price_basis.name(), ema_period);
+        if history.is_none() {
+            println!(
+                "cargo:warning=ZKMD_PRICE_BASIS={} without a price history; threshold signals will HOLD",
+                price_basis.name()
+            );
+        }
+        market::write_price_basis(&mut input, price_basis, ema_period)?;
+    }
+    let neutral_band_bps = env::var("ZKMD_NEUTRAL_BAND_BPS").ok().map(|v| v.parse()).transpose()?;
+    if let Some(band) = neutral_band_bps {
+        println!("Neutral band: HOLD while |24h change| < {} bps (replaces the thresholds)", band);
+        if thresholds.is_some() {
+            println!("cargo:warning=ZKMD_NEUTRAL_BAND_BPS replaces ZKMD_BUY_THRESHOLD and ZKMD_SELL_THRESHOLD");
+        }
+        market::write_neutral_band(&mut input, Some(band))?;
+    }
+    let max_change_bps = env::var("ZKMD_MAX_CHANGE_BPS").ok().map(|v| v.parse()).transpose()?;
+    if let Some(bound) = max_change_bps {
+        println!("Sanity bound: HOLD on a 24h change beyond ±{} bps", bound);
+        market::write_max_change(&mut input, bound)?;
+    }
+    let min_hold = min_hold_from_env()?;
+    if let Some(m) = &min_hold {
+        match m.last_trade_time {
+            market::NO_LAST_TRADE => println!("Min hold: {}s between trades (no trade yet)", m.min_hold_secs),
+            last => println!("Min hold: {}s between trades, last trade at {}", m.min_hold_secs, last),
+        }
+        market::write_min_hold(&mut input, m)?;
+    }
+    let risk_bands = risk_bands_from_env()?;
+    if let Some(bands) = &risk_bands {
+        println!("Risk bands: {} over {:?} bps", bands.count(), bands.thresholds());
+        market::write_risk_bands(&mut input, bands)?;
+    }
+    let nonce = match env::var("ZKMD_NONCE_FILE") {
+        Ok(path) => Some((next_nonce(&path)?, path)),
+        Err(_) => None,
+    };
+    if let Some((nonce, path)) = &nonce {
+        println!("Nonce: {} (state in {})", nonce, path);
+        market::write_nonce(&mut input, *nonce)?;
+    }
+    let output_mode = match env::var("ZKMD_OUTPUT_MODE") {
+        Ok(name) => {
+            market::OutputMode::from_name(&name).ok_or_else(|| format!("unknown ZKMD_OUTPUT_MODE {:?}", name))?
+        }
+        Err(_) => market::OutputMode::Standard,
+    };
+    if output_mode != market::OutputMode::Standard {
+        println!("Output mode: {} (price, 24h change and timestamp republished)", output_mode.name());
+        market::write_output_mode(&mut input, output_mode)?;
+    }
+    let weights = weights_from_env(&quotes)?;
+    if let Some(weights) = &weights {
+        let summary: Vec<String> = quotes.iter().zip(weights).map(|(q, w)| format!("{}={}", q.coin, w)).collect();
+        println!("Portfolio weights: {}", summary.join(", "));
+        market::write_weights(&mut input, weights)?;
+    }
+    let secrets = secrets_from_env(&quotes)?;
+    if let Some(secrets) = &secrets {
+        // Only the count: the secrets never leave input.bin
+        println!("Record secrets: {}, one per asset, paired with its Merkle leaf", secrets.len());
+        market::write_secrets(&mut input, secrets)?;
+    }
+    // The guest refuses an input whose published runs leave no room for
+    // all its records (RecordError::NoRoom), so refuse to write one
+    market::read_market_input(&input)?;
+    input_gen::write_input_bin("build/input.bin", &input)?;
+    // The guest reads the raw file, so the compressed one is a copy for
+    // archiving and for the host tools, which read either
+    if env::var("ZKMD_COMPRESS").is_ok_and(|v| v == "1") {
+        input_gen::write_input_bin_gz(&format!("build/input.bin{}", input_gen::GZIP_SUFFIX), &input)?;
+    }
+    // Only once input.bin carries it, so a failed build doesn't use one up
+    if let Some((nonce, path)) = &nonce {
+        write_atomic(path, format!("{}\n", nonce).as_bytes())?;
+    }
+
+    let rules = market::Rules {
+        thresholds: thresholds.unwrap_or(market::DEFAULT_THRESHOLDS),
+        mode: mode.unwrap_or(market::SignalMode::default_for(history.is_some())),
+        freshness,
+        min_volume,
+        squeeze_bps: squeeze_bps.unwrap_or(market::DEFAULT_SQUEEZE_BPS),
+        position,
+        levels,
+        direction,
+        hysteresis_bps: hysteresis.as_ref().map(|h| h.margin_bps),
+        price_basis,
+        ema_period,
+        neutral_band_bps,
+        current_time: Some(current_time),
+        max_change_bps,
+        min_hold,
+        risk_bands,
+    };
+    print_likely_signals(&quotes, &records, history.as_deref(), hysteresis.as_ref(), &rules, &currency);
+    let public = market::MarketInput {
+        records,
+        history,
+        rules,
+        currency: currency_code,
+        price_decimals,
+        source_digest,
+        prev_signals: hysteresis.map(|h| h.prev_signals),
+        secrets,
+        weights,
+        nonce: nonce.map(|(n, _)| n),
+        output_mode,
+        data_source,
+    };
+    save_public(&quotes, &public)?;
+
     Ok(())
-}
\ No newline at end of file
+}
+
+// What the guest should decide for each record, by the same strategy::decide
+// it runs, so a build shows at once whether the thresholds behave. Only a
+// preview: the proof is what counts.
+fn print_likely_signals(
+    quotes: &[Quote],
+    records: &[market::Record],
+    history: Option<&[Vec<u64>]>,
+    hysteresis: Option<&Hysteresis>,
+    rules: &market::Rules,
+    currency: &str,
+) {
+    for (i, (quote, record)) in quotes.iter().zip(records).enumerate() {
+        let closes = history.and_then(|h| h.get(i)).map(Vec::as_slice);
+        let prev = hysteresis.and_then(|h| strategy::Signal::try_from(h.prev_signals[i]).ok());
+        let decision = strategy::decide(record, rules, closes, prev);
+        let change = match record.change_24h {
+            market::CHANGE_UNAVAILABLE => "no 24h change".to_string(),
+            change => format!("{:+.2}%", change.to_percent()),
+        };
+        let reason = decision.hold_reason.map_or(String::new(), |r| format!(" ({:?})", r));
+        println!(
+            "Likely: {} {:.2} {} ({}) -> {}{}",
+            quote.coin,
+            quote.price,
+            currency.to_ascii_uppercase(),
+            change,
+            decision.signal.label(),
+            reason
+        );
+    }
+}
+
+// A timed-out request counts as a connection failure: get_json retries it,
+// then the caller falls back to the next source or the cache.
+#[cfg(feature = "network")]
+fn http_client(config: &Config) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+    let secs = match config.var("ZKMD_HTTP_TIMEOUT") {
+        Some(v) => v.parse().map_err(|e| format!("ZKMD_HTTP_TIMEOUT must be whole seconds: {}", e))?,
+        None => DEFAULT_HTTP_TIMEOUT_SECS,
+    };
+    if secs == 0 {
+        return Err("ZKMD_HTTP_TIMEOUT must be at least 1 second".into());
+    }
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(secs));
+    let mut proxied = false;
+    // Setting a proxy explicitly turns off reqwest's own detection, so both
+    // schemes are configured here, each honoring NO_PROXY.
+    for (scheme, names) in [("https", ["HTTPS_PROXY", "https_proxy"]), ("http", ["HTTP_PROXY", "http_proxy"])] {
+        let Some(url) = names.iter().find_map(|name| env::var(name).ok().filter(|v| !v.is_empty())) else {
+            continue;
+        };
+        let proxy = match scheme {
+            "https" => reqwest::Proxy::https(&url),
+            _ => reqwest::Proxy::http(&url),
+        }
+        .map_err(|e| format!("bad {} proxy {}: {}", scheme, redact_proxy(&url), e))?;
+        builder = builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_env()));
+        println!("cargo:warning={} proxy: {}", scheme, redact_proxy(&url));
+        proxied = true;
+    }
+    if let Some(no_proxy) = ["NO_PROXY", "no_proxy"].iter().find_map(|name| env::var(name).ok()).filter(|_| proxied) {
+        println!("cargo:warning=bypassing the proxy for: {}", no_proxy);
+    }
+    Ok(builder.build()?)
+}
+
+// A proxy URL with any user:password replaced, for logging.
+#[cfg(feature = "network")]
+fn redact_proxy(url: &str) -> String {
+    let rest_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    match url[rest_start..].rfind('@') {
+        Some(at) => format!("{}***@{}", &url[..rest_start], &url[rest_start + at + 1..]),
+        None => url.to_string(),
+    }
+}
+
+// GET `url` as JSON. Connection errors, timeouts, 5xx and 429 are retried
+// with exponential backoff (200ms, 400ms, 800ms, ...); other 4xx fail at once.
+// Successful bodies are appended to `responses`.
+#[cfg(feature = "network")]
+async fn get_json(
+    client: &Client,
+    url: &str,
+    responses: &mut Vec<RawResponse>,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let retries = client.retries;
+    let mut attempt = 0;
+    loop {
+        let failure = match client.http.get(url).send().await {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    let body = response.text().await?;
+                    let data = serde_json::from_str(&body).map_err(|e| {
+                        format!("response from {} is not JSON ({}): {}", url, e, snippet(&body))
+                    })?;
+                    responses.push(RawResponse { url: url.to_string(), body });
+                    return Ok(data);
+                }
+                if !status.is_server_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(format!("HTTP {} from {}", status, url).into());
+                }
+                format!("HTTP {}", status)
+            }
+            Err(e) if e.is_timeout() => format!("timed out ({})", e),
+            Err(e) if e.is_connect() => e.to_string(),
+            Err(e) => return Err(e.into()),
+        };
+        if attempt >= retries {
+            return Err(format!("{} from {} after {} retries", failure, url, retries).into());
+        }
+        let delay = Duration::from_millis(RETRY_BASE_DELAY_MS << attempt.min(16));
+        attempt += 1;
+        println!("cargo:warning=retry {}/{} in {:?}: {} ({})", attempt, retries, delay, url, failure);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// Try CoinGecko first and only fail if every source fails. Only the
+// responses of the source that was used end up in `responses`.
+#[cfg(feature = "network")]
+async fn fetch_live(
+    client: &Client,
+    coins: &[String],
+    currency: &str,
+    responses: &mut Vec<RawResponse>,
+) -> Result<(market::DataSource, Vec<Quote>), Box<dyn std::error::Error>> {
+    match fetch_coingecko(client, coins, currency, responses).await {
+        Ok(quotes) => {
+            println!("cargo:warning=market data source: CoinGecko");
+            Ok((market::DataSource::CoinGecko, quotes))
+        }
+        Err(gecko_err) => {
+            println!("cargo:warning=CoinGecko failed ({}), falling back to Binance", gecko_err);
+            responses.clear();
+            let quotes = fetch_binance(client, coins, currency, responses).await.map_err(|binance_err| {
+                format!("all market data sources failed: CoinGecko: {}; Binance: {}", gecko_err, binance_err)
+            })?;
+            println!("cargo:warning=market data source: Binance");
+            Ok((market::DataSource::Binance, quotes))
+        }
+    }
+}
+
+// The cache stores quotes in CoinGecko's simple/price shape, whichever
+// source produced them.
+#[cfg(feature = "network")]
+fn save_cache(
+    source: market::DataSource,
+    currency: &str,
+    quotes: &[Quote],
+    fetched_at: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = serde_json::Map::new();
+    for quote in quotes {
+        let mut entry = serde_json::Map::new();
+        entry.insert(currency.to_string(), json!(quote.price));
+        entry.insert(format!("{}_24h_change", currency), json!(quote.change_24h_pct));
+        entry.insert(format!("{}_24h_vol", currency), json!(quote.volume));
+        data.insert(quote.coin.clone(), Value::Object(entry));
+    }
+    let cache = json!({ "fetched_at": fetched_at, "source": source.name(), "currency": currency, "data": data });
+    write_atomic(CACHE_PATH, serde_json::to_string_pretty(&cache)?.as_bytes())?;
+    Ok(())
+}
+
+// Each body is stored as a string, byte for byte, since re-serializing the
+// parsed JSON would reorder keys and reformat numbers.
+fn save_responses(
+    source: market::DataSource,
+    fetched_at: u64,
+    responses: &[RawResponse],
+    digest: &[u8; 32],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let responses: Vec<Value> = responses
+        .iter()
+        .map(|r| json!({ "url": r.url, "body": r.body }))
+        .collect();
+    let document =
+        json!({ "fetched_at": fetched_at, "source": source.name(), "sha256": hex(digest), "responses": responses });
+    write_atomic(RESPONSE_PATH, serde_json::to_string_pretty(&document)?.as_bytes())?;
+    Ok(())
+}
+
+// Everything the guest decides on, in the shape `verify` expects.
+fn save_public(quotes: &[Quote], input: &market::MarketInput) -> Result<(), Box<dyn std::error::Error>> {
+    let rules = &input.rules;
+    let records: Vec<Value> = quotes
+        .iter()
+        .zip(&input.records)
+        .map(|(quote, r)| {
+            json!({
+                "coin": quote.coin,
+                "price": r.price,
+                "change_24h": r.change_24h.bps(),
+                "timestamp": r.timestamp,
+                "volume": r.volume,
+            })
+        })
+        .collect();
+    let public = json!({
+        "records": records,
+        "thresholds": { "buy": rules.thresholds.buy.bps(), "sell": rules.thresholds.sell.bps() },
+        "mode": rules.mode.name(),
+        "freshness": rules.freshness.map(|f| json!({ "current_time": f.current_time, "max_age_secs": f.max_age_secs })),
+        "min_volume": rules.min_volume,
+        "squeeze_bps": rules.squeeze_bps,
+        "direction": rules.direction.name(),
+        "levels": rules.levels.map(|l| json!({ "stop_bps": l.stop_bps, "target_bps": l.target_bps })),
+        "position": rules.position.map(|p| json!({ "balance": p.balance, "risk_fraction": p.risk_fraction })),
+        "currency": String::from_utf8_lossy(&input.currency),
+        "price_decimals": input.price_decimals,
+        "history": input.history,
+        "hysteresis_bps": rules.hysteresis_bps,
+        "prev_signals": input.prev_signals,
+        "weights": input.weights,
+        "nonce": input.nonce,
+        "output_mode": input.output_mode.name(),
+        "data_source": input.data_source.name(),
+        "price_basis": rules.price_basis.name(),
+        "ema_period": rules.ema_period,
+        "neutral_band_bps": rules.neutral_band_bps,
+        "current_time": rules.current_time,
+        "max_change_bps": rules.max_change_bps,
+        "min_hold": rules
+            .min_hold
+            .map(|m| json!({ "last_trade_time": m.last_trade_time, "min_hold_secs": m.min_hold_secs })),
+        "risk_bands": rules.risk_bands.map(|b| b.thresholds().to_vec()),
+        "source_digest": input.source_digest.map(|d| hex(&d)),
+        "record_secrets": input.secrets.is_some(),
+    });
+    write_atomic(PUBLIC_PATH, serde_json::to_string_pretty(&public)?.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(feature = "network")]
+fn load_cache(coins: &[String], currency: &str) -> Result<(Vec<Quote>, u64), Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(CACHE_PATH)
+        .map_err(|e| format!("cannot read {}: {}", CACHE_PATH, e))?;
+    let cache: Value = serde_json::from_str(&contents)?;
+    let fetched_at = cache["fetched_at"].as_u64().ok_or("cache is missing fetched_at")?;
+    // Caches from before ZKMD_CURRENCY are always USD
+    let cached_currency = cache["currency"].as_str().unwrap_or(DEFAULT_CURRENCY);
+    if cached_currency != currency {
+        return Err(format!("cache is in {}, not {}", cached_currency, currency).into());
+    }
+    let quotes = quotes_from_coingecko(&cache["data"], coins, currency, CACHE_PATH)?;
+    println!("cargo:warning=using cached data from {} (unix time)", fetched_at);
+    Ok((quotes, fetched_at))
+}
+
+// A checked-in simple/price body, parsed exactly like a live response. The
+// records are stamped with the build time, so pin it for a hermetic build.
+fn load_fixture(
+    path: &str,
+    coins: &[String],
+    currency: &str,
+    responses: &mut Vec<RawResponse>,
+) -> Result<Vec<Quote>, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("cannot read fixture {}: {}", path, e))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("fixture {} is not JSON ({}): {}", path, e, snippet(&contents)))?;
+    let quotes = quotes_from_coingecko(&data, coins, currency, path)?;
+    responses.push(RawResponse { url: path.to_string(), body: contents });
+    Ok(quotes)
+}
+
+// Every coin from CoinGecko's simple/price endpoint in a single call.
+#[cfg(feature = "network")]
+async fn fetch_coingecko(
+    client: &Client,
+    coins: &[String],
+    currency: &str,
+    responses: &mut Vec<RawResponse>,
+) -> Result<Vec<Quote>, Box<dyn std::error::Error>> {
+    let url = format!(
+        "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}&include_24hr_change=true&include_24hr_vol=true",
+        coins.join(","),
+        currency
+    );
+    let data = get_json(client, &url, responses).await?;
+    quotes_from_coingecko(&data, coins, currency, "API response")
+}
+
+// Quotes for `coins` in order, from a simple/price body (`source` names it
+// in errors). Coins missing from the body are skipped with a warning, and
+// it's an error only if none are left; a coin that is present but lacks a
+// field means the shape has changed, so that is an error too.
+fn quotes_from_coingecko(
+    data: &Value,
+    coins: &[String],
+    currency: &str,
+    source: &str,
+) -> Result<Vec<Quote>, Box<dyn std::error::Error>> {
+    if !data.is_object() {
+        return Err(format!("expected a JSON object in {}: {}", source, snippet(&data.to_string())).into());
+    }
+    // Rate limits and bad requests come back as
+    // {"status": {"error_message": ...}}
+    if let Some(message) = data["status"]["error_message"].as_str() {
+        return Err(format!("CoinGecko error in {}: {}", source, message).into());
+    }
+    let mut quotes = Vec::new();
+    for coin in coins {
+        let entry = &data[coin.as_str()];
+        if entry.is_null() {
+            println!("cargo:warning=no price data for {}, skipping it", coin);
+            continue;
+        }
+        // None when absent or null; an error when present but not a number
+        let field = |suffix: &str| -> Result<Option<f64>, Box<dyn std::error::Error>> {
+            let key = format!("{}{}", currency, suffix);
+            match entry.get(&key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(value) => {
+                    let value = value.as_f64().ok_or_else(|| {
+                        format!("non-numeric field {}.{} in {}: {}", coin, key, source, snippet(&data.to_string()))
+                    })?;
+                    finite(value, &format!("{}.{}", coin, key), source).map(Some)
+                }
+            }
+        };
+        let required = |suffix: &str| -> Result<f64, Box<dyn std::error::Error>> {
+            field(suffix)?.ok_or_else(|| {
+                format!("missing field {}.{}{} in {}: {}", coin, currency, suffix, source, snippet(&data.to_string()))
+                    .into()
+            })
+        };
+        // CoinGecko leaves the change out for some thinly traded coins
+        let change_24h_pct = field("_24h_change")?;
+        if change_24h_pct.is_none() {
+            println!("cargo:warning=no 24h change for {}; threshold signals will HOLD", coin);
+        }
+        quotes.push(Quote {
+            coin: coin.clone(),
+            price: required("")?,
+            change_24h_pct,
+            volume: required("_24h_vol")?,
+        });
+    }
+    if quotes.is_empty() {
+        return Err(format!("none of the requested coins are in {}: {}", source, snippet(&data.to_string())).into());
+    }
+    Ok(quotes)
+}
+
+// `value` unless it is NaN or infinite, which no API should send: Binance
+// sends numbers as strings, and "NaN" and "inf" parse. Refused here, where
+// the error can name the field (`what`) and its source, so that a live fetch
+// falls back to the next source rather than casting garbage into a record.
+fn finite(value: f64, what: &str, source: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    if !value.is_finite() {
+        return Err(format!("non-finite {} in {}: {}", what, source, value).into());
+    }
+    Ok(value)
+}
+
+// The first BODY_SNIPPET_LEN characters of `body`, for error messages.
+fn snippet(body: &str) -> String {
+    match body.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+// Every coin from Binance's ticker/24hr endpoint, one call per symbol.
+// USD prices come from USDT pairs, which we treat as USD; other currencies
+// use the pair quoted in that currency (e.g. BTCEUR).
+#[cfg(feature = "network")]
+async fn fetch_binance(
+    client: &Client,
+    coins: &[String],
+    currency: &str,
+    responses: &mut Vec<RawResponse>,
+) -> Result<Vec<Quote>, Box<dyn std::error::Error>> {
+    let quote_asset = match currency {
+        "usd" => "USDT".to_string(),
+        other => other.to_ascii_uppercase(),
+    };
+    let mut quotes = Vec::new();
+    for coin in coins {
+        let Some(asset) = BINANCE_ASSETS
+            .iter()
+            .find(|(id, _)| id == coin)
+            .map(|(_, asset)| *asset)
+        else {
+            println!("cargo:warning=no Binance symbol for {}, skipping it", coin);
+            continue;
+        };
+        let url = format!("https://api.binance.com/api/v3/ticker/24hr?symbol={}{}", asset, quote_asset);
+        let data = get_json(client, &url, responses).await?;
+        // Binance returns numbers as strings
+        let field = |name: &str| -> Result<f64, Box<dyn std::error::Error>> {
+            let text = data[name].as_str().ok_or_else(|| {
+                format!("missing field {} in Binance response: {}", name, snippet(&data.to_string()))
+            })?;
+            let value = text.parse().map_err(|e| format!("field {} is not a number ({}): {:?}", name, e, text))?;
+            finite(value, name, "Binance response")
+        };
+        quotes.push(Quote {
+            coin: coin.clone(),
+            price: field("lastPrice")?,
+            change_24h_pct: Some(field("priceChangePercent")?),
+            volume: field("quoteVolume")?,
+        });
+    }
+    if quotes.is_empty() {
+        return Err("none of the requested coins are listed on Binance".into());
+    }
+    Ok(quotes)
+}
+
+// Daily closes of `currency`, with `decimals` places, for the last `days`
+// days, oldest first.
+#[cfg(feature = "network")]
+async fn fetch_daily_closes(
+    client: &Client,
+    coin: &str,
+    currency: &str,
+    decimals: u32,
+    days: u32,
+    responses: &mut Vec<RawResponse>,
+) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let url = format!(
+        "https://api.coingecko.com/api/v3/coins/{}/market_chart?vs_currency={}&days={}&interval=daily",
+        coin, currency, days
+    );
+    let data = get_json(client, &url, responses).await?;
+    let prices = data["prices"].as_array().ok_or_else(|| {
+        format!("missing field prices in market_chart response: {}", snippet(&data.to_string()))
+    })?;
+    let closes = prices
+        .iter()
+        .filter_map(|p| p.as_array()?.get(1)?.as_f64())
+        .map(|p| Ok(fixed::to_fixed(finite(p, &format!("{} close", coin), "market_chart response")?, decimals)?))
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+    Ok(closes)
+}
+
+// Where the build time comes from: ZKMD_FIXED_TIME, else SOURCE_DATE_EPOCH,
+// else the wall clock. Pinning it (with ZKMD_OFFLINE=1, so the quotes come
+// from the cache) makes input.bin byte-identical across builds.
+fn clock_from_env() -> Result<Box<dyn Clock>, Box<dyn std::error::Error>> {
+    for name in ["ZKMD_FIXED_TIME", "SOURCE_DATE_EPOCH"] {
+        if let Ok(v) = env::var(name) {
+            let secs = v.parse().map_err(|e| format!("{} must be Unix seconds: {}", name, e))?;
+            return Ok(Box::new(FixedClock(secs)));
+        }
+    }
+    Ok(Box::new(SystemClock))
+}
+
+// ZKMD_STOP_BPS and/or ZKMD_TARGET_BPS; an unset one keeps its default.
+fn levels_from_env() -> Result<Option<market::Levels>, Box<dyn std::error::Error>> {
+    let stop = env::var("ZKMD_STOP_BPS").ok();
+    let target = env::var("ZKMD_TARGET_BPS").ok();
+    if stop.is_none() && target.is_none() {
+        return Ok(None);
+    }
+    let defaults = market::DEFAULT_LEVELS;
+    Ok(Some(market::Levels {
+        stop_bps: stop.map(|v| v.parse()).transpose()?.unwrap_or(defaults.stop_bps),
+        target_bps: target.map(|v| v.parse()).transpose()?.unwrap_or(defaults.target_bps),
+    }))
+}
+
+// ZKMD_PREV_SIGNALS lists last run's signals as coin=LABEL pairs (e.g.
+// bitcoin=BUY,ethereum=HOLD) and turns on hysteresis with a margin of
+// ZKMD_HYSTERESIS_BPS. Coins it doesn't mention count as HOLD, which never
+// holds back a signal. One previous code per quote, in order.
+fn hysteresis_from_env(quotes: &[Quote]) -> Result<Option<Hysteresis>, Box<dyn std::error::Error>> {
+    let Ok(list) = env::var("ZKMD_PREV_SIGNALS") else {
+        if env::var("ZKMD_HYSTERESIS_BPS").is_ok() {
+            return Err("ZKMD_HYSTERESIS_BPS needs ZKMD_PREV_SIGNALS".into());
+        }
+        return Ok(None);
+    };
+    let margin_bps = match env::var("ZKMD_HYSTERESIS_BPS") {
+        Ok(v) => v.parse()?,
+        Err(_) => DEFAULT_HYSTERESIS_BPS,
+    };
+    let mut prev_signals = vec![strategy::Signal::Hold as u32; quotes.len()];
+    for pair in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (coin, label) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("ZKMD_PREV_SIGNALS entry {:?} is not coin=SIGNAL", pair))?;
+        let signal = strategy::Signal::from_label(label)
+            .ok_or_else(|| format!("unknown signal {:?} in ZKMD_PREV_SIGNALS", label))?;
+        match quotes.iter().position(|q| q.coin == coin) {
+            Some(i) => prev_signals[i] = signal as u32,
+            None => println!("cargo:warning=ZKMD_PREV_SIGNALS names {}, which has no record; ignoring it", coin),
+        }
+    }
+    Ok(Some(Hysteresis { margin_bps, prev_signals }))
+}
+
+// The nonce for this build: one more than the last one written to the state
+// file at `path`, or 1 on the first run, before the file exists (0 is
+// market::NO_NONCE).
+fn next_nonce(path: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let last: u64 = match fs::read_to_string(path) {
+        Ok(text) => text.trim().parse().map_err(|e| format!("nonce state {} is not a u64: {}", path, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("cargo:warning=no nonce state at {} yet; starting from nonce 1", path);
+            return Ok(market::NO_NONCE + 1);
+        }
+        Err(e) => return Err(format!("cannot read nonce state {}: {}", path, e).into()),
+    };
+    Ok(last.checked_add(1).ok_or_else(|| format!("nonce state {} is at u64::MAX", path))?)
+}
+
+// ZKMD_WEIGHTS sets each asset's vote in the portfolio aggregation as
+// coin=WEIGHT pairs (e.g. bitcoin=3,ethereum=1). Coins it doesn't mention
+// weigh 1, as every asset does without it. One weight per quote, in order.
+fn weights_from_env(quotes: &[Quote]) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+    let Ok(list) = env::var("ZKMD_WEIGHTS") else {
+        return Ok(None);
+    };
+    let mut weights = vec![1; quotes.len()];
+    for pair in list.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (coin, weight) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("ZKMD_WEIGHTS entry {:?} is not coin=WEIGHT", pair))?;
+        let weight: u32 = weight.trim().parse().map_err(|e| format!("ZKMD_WEIGHTS weight for {}: {}", coin, e))?;
+        if weight > output_layout::MAX_WEIGHT {
+            let max = output_layout::MAX_WEIGHT;
+            return Err(format!("ZKMD_WEIGHTS weight for {} is {}, maximum is {}", coin, weight, max).into());
+        }
+        match quotes.iter().position(|q| q.coin == coin) {
+            Some(i) => weights[i] = weight,
+            None => println!("cargo:warning=ZKMD_WEIGHTS names {}, which has no record; ignoring it", coin),
+        }
+    }
+    Ok(Some(weights))
+}
+
+// ZKMD_RECORD_SECRETS names a file of coin=HEX lines, one private secret
+// per asset (blank lines and # comments are skipped). Every asset needs
+// exactly one, so a missing or extra coin fails the build rather than
+// shifting a secret onto the wrong record. One secret per quote, in order.
+fn secrets_from_env(quotes: &[Quote]) -> Result<Option<Vec<Vec<u8>>>, Box<dyn std::error::Error>> {
+    let Ok(path) = env::var("ZKMD_RECORD_SECRETS") else {
+        return Ok(None);
+    };
+    let contents = fs::read_to_string(&path).map_err(|e| format!("cannot read ZKMD_RECORD_SECRETS {}: {}", path, e))?;
+    let mut secrets = vec![None; quotes.len()];
+    let mut count = 0;
+    for (n, line) in contents.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (coin, secret) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{} line {}: expected coin=HEX", path, n))?;
+        let secret = secret.trim();
+        let secret = codec::unhex(secret)
+            .ok_or_else(|| format!("{} line {}: {:?} is not an even number of hex digits", path, n, secret))?;
+        count += 1;
+        match quotes.iter().position(|q| q.coin == coin.trim()) {
+            Some(i) if secrets[i].is_none() => secrets[i] = Some(secret),
+            Some(_) => return Err(format!("{} line {}: second secret for {}", path, n, coin.trim()).into()),
+            None => {}
+        }
+    }
+    let secrets: Option<Vec<Vec<u8>>> = secrets.into_iter().collect();
+    match secrets {
+        Some(secrets) if count == quotes.len() => Ok(Some(secrets)),
+        _ => Err(format!("{} has {} secrets for {} assets; it needs exactly one per asset", path, count, quotes.len()).into()),
+    }
+}
+
+// ZKMD_MIN_HOLD_SECS: the cooldown after a trade, and ZKMD_LAST_TRADE_TIME
+// the Unix seconds of the last one (NO_LAST_TRADE if unset: no trade yet).
+fn min_hold_from_env() -> Result<Option<market::MinHold>, Box<dyn std::error::Error>> {
+    let last_trade_time = env::var("ZKMD_LAST_TRADE_TIME").ok().map(|v| v.parse()).transpose()?;
+    let Ok(secs) = env::var("ZKMD_MIN_HOLD_SECS") else {
+        if last_trade_time.is_some() {
+            return Err("ZKMD_LAST_TRADE_TIME needs ZKMD_MIN_HOLD_SECS".into());
+        }
+        return Ok(None);
+    };
+    Ok(Some(market::MinHold {
+        last_trade_time: last_trade_time.unwrap_or(market::NO_LAST_TRADE),
+        min_hold_secs: secs.parse()?,
+    }))
+}
+
+// ZKMD_RISK_BANDS, comma-separated ascending thresholds in basis points
+// (e.g. "200,500,1000" for four bands).
+fn risk_bands_from_env() -> Result<Option<market::RiskBands>, Box<dyn std::error::Error>> {
+    let Ok(list) = env::var("ZKMD_RISK_BANDS") else {
+        return Ok(None);
+    };
+    let thresholds = list.split(',').map(|t| t.trim().parse()).collect::<Result<Vec<u64>, _>>()?;
+    let bands = market::RiskBands::new(&thresholds).ok_or_else(|| {
+        format!(
+            "ZKMD_RISK_BANDS={:?}: need 1 to {} strictly ascending thresholds",
+            list,
+            output_layout::MAX_RISK_BANDS - 1
+        )
+    })?;
+    Ok(Some(bands))
+}
+
+// ZKMD_PRICE_BASIS (raw, ema or wma) picks what Threshold mode compares, and
+// ZKMD_EMA_PERIOD the EMA's or WMA's period in closes (DEFAULT_EMA_PERIOD if
+// unset).
+fn price_basis_from_env() -> Result<(market::PriceBasis, u32), Box<dyn std::error::Error>> {
+    let basis = match env::var("ZKMD_PRICE_BASIS") {
+        Ok(name) => market::PriceBasis::from_name(&name).ok_or_else(|| format!("unknown ZKMD_PRICE_BASIS {:?}", name))?,
+        Err(_) => market::PriceBasis::Raw,
+    };
+    let period = match env::var("ZKMD_EMA_PERIOD") {
+        Ok(_) if basis == market::PriceBasis::Raw => {
+            return Err("ZKMD_EMA_PERIOD needs ZKMD_PRICE_BASIS=ema or wma".into())
+        }
+        Ok(v) => v.parse()?,
+        Err(_) => market::DEFAULT_EMA_PERIOD,
+    };
+    if period == 0 {
+        return Err("ZKMD_EMA_PERIOD must be at least 1".into());
+    }
+    Ok((basis, period))
+}
+
+// ZKMD_PRICE_DECIMALS: places kept in every price (e.g. 8 for a token
+// worth a fraction of a cent). Unset means market::PRICE_DECIMALS (cents).
+fn price_decimals_from_env() -> Result<u32, Box<dyn std::error::Error>> {
+    let decimals = match env::var("ZKMD_PRICE_DECIMALS") {
+        Ok(v) => v.parse()?,
+        Err(_) => return Ok(market::PRICE_DECIMALS),
+    };
+    if decimals > market::MAX_PRICE_DECIMALS {
+        return Err(format!("ZKMD_PRICE_DECIMALS is {}, maximum is {}", decimals, market::MAX_PRICE_DECIMALS).into());
+    }
+    Ok(decimals)
+}
+
+// ZKMD_ACCOUNT_BALANCE (in currency units, e.g. 10000.50) and
+// ZKMD_RISK_FRACTION (e.g. 0.02 for 2%) together enable position sizing.
+fn position_from_env(price_decimals: u32) -> Result<Option<market::Position>, Box<dyn std::error::Error>> {
+    let balance = env::var("ZKMD_ACCOUNT_BALANCE").ok();
+    let fraction = env::var("ZKMD_RISK_FRACTION").ok();
+    match (balance, fraction) {
+        (None, None) => Ok(None),
+        (Some(balance), Some(fraction)) => Ok(Some(market::Position {
+            balance: fixed::to_fixed(balance.parse()?, price_decimals)?,
+            risk_fraction: fixed::to_fixed(fraction.parse()?, market::RISK_FRACTION_DECIMALS)?,
+        })),
+        _ => Err("set both ZKMD_ACCOUNT_BALANCE and ZKMD_RISK_FRACTION, or neither".into()),
+    }
+}
+
+// ZKMD_SIGNAL_MODE: any SignalMode::name (threshold, rsi, ma_crossover,
+// bollinger, macd). Unset leaves the field
+// out and the guest picks RSI with a price history, thresholds without.
+fn signal_mode_from_env() -> Result<Option<market::SignalMode>, Box<dyn std::error::Error>> {
+    match env::var("ZKMD_SIGNAL_MODE") {
+        Ok(name) => Ok(Some(
+            market::SignalMode::from_name(&name).ok_or_else(|| format!("unknown ZKMD_SIGNAL_MODE {:?}", name))?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,109 @@
+// Host-side backtest: runs strategy::decide, the guest's own decision code,
+// over a CSV of historical prices and writes what it decided for each row.
+//
+// cargo run --bin backtest -- <prices.csv> [signals.csv] [--mode rsi]
+// cargo run --bin backtest -- <prices.csv> --history 30   (closes per decision)
+//
+// prices.csv holds `timestamp,price,change_24h` rows, oldest first, with
+// the price in currency units and the change in percent (left empty when
+// it is missing); a first line starting with `timestamp` is a header. Each
+// row is decided with the default rules (but config.toml's thresholds), in
+// Threshold mode unless --mode names another, and with the prices of the
+// last --history rows up to it as the price history, so the history-based
+// modes and volatility risk apply once there are enough rows. Malformed
+// rows are skipped with a warning.
+//
+// The window keeps each row's decide linear in --history rather than in
+// the rows so far. It is also what the guest sees: build.rs writes a fixed
+// number of closes (ZKMD_HISTORY_DAYS), and the EMA-based indicators (RSI's
+// smoothing, MACD, the EMA basis) depend on where their series starts, so
+// a backtest row only matches a proof made with the same history length.
+//
+// signals.csv repeats the three input columns, then the signal, the risk
+// and the confidence (empty when the mode isn't scored).
+
+use std::env;
+use std::error::Error;
+use std::fs;
+
+use sha_hasher::config::Config;
+use sha_hasher::market::{self, Record, ReturnBps, SignalMode};
+use sha_hasher::{fixed, strategy};
+
+const DEFAULT_SIGNALS_PATH: &str = "backtest.csv";
+const USAGE: &str = "usage: backtest <prices.csv> [signals.csv] [--mode NAME] [--history CLOSES]";
+
+// Closes per decision unless --history says otherwise: a year of dailies,
+// enough for every indicator's period.
+const DEFAULT_HISTORY_CLOSES: usize = 365;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let mut mode = SignalMode::Threshold;
+    let mut history = DEFAULT_HISTORY_CLOSES;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--mode" {
+            let name = args.next().ok_or(USAGE)?;
+            mode = SignalMode::from_name(&name).ok_or_else(|| format!("unknown mode {:?}", name))?;
+        } else if arg == "--history" {
+            let closes = args.next().ok_or(USAGE)?;
+            history = closes.parse().ok().filter(|&n| n > 0).ok_or_else(|| format!("bad --history {:?}", closes))?;
+        } else {
+            paths.push(arg);
+        }
+    }
+    let prices_path = paths.first().ok_or(USAGE)?;
+    let signals_path = paths.get(1).map(String::as_str).unwrap_or(DEFAULT_SIGNALS_PATH);
+
+    let contents = fs::read_to_string(prices_path).map_err(|e| format!("cannot read {}: {}", prices_path, e))?;
+    let thresholds = Config::load()?.thresholds()?.unwrap_or(market::DEFAULT_THRESHOLDS);
+    let rules = market::Rules { mode, thresholds, ..market::DEFAULT_RULES };
+    let mut closes = Vec::new();
+    let mut out = String::from("timestamp,price,change_24h,signal,risk,confidence\n");
+    let (mut rows, mut skipped) = (0, 0);
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.starts_with("timestamp")) {
+            continue;
+        }
+        let record = match parse_row(line) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("warning: {} line {}: {}; skipped", prices_path, i + 1, e);
+                skipped += 1;
+                continue;
+            }
+        };
+        closes.push(record.price);
+        let recent = &closes[closes.len().saturating_sub(history)..];
+        let decision = strategy::decide(&record, &rules, Some(recent), None);
+        let confidence = decision.confidence.map(|c| c.to_string()).unwrap_or_default();
+        out.push_str(&format!("{},{},{},{}\n", line, decision.signal.label(), decision.risk.label(), confidence));
+        rows += 1;
+    }
+
+    fs::write(signals_path, out).map_err(|e| format!("cannot write {}: {}", signals_path, e))?;
+    println!("Wrote {} ({} rows, {} skipped)", signals_path, rows, skipped);
+    Ok(())
+}
+
+// One `timestamp,price,change_24h` row as the guest's record, in its
+// fixed-point units. There is no volume column, so the volume is 0.
+fn parse_row(line: &str) -> Result<Record, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [timestamp, price, change] = fields[..] else {
+        return Err(format!("expected 3 fields, got {}", fields.len()));
+    };
+    let timestamp = timestamp.parse().map_err(|e| format!("bad timestamp {:?}: {}", timestamp, e))?;
+    let price: f64 = price.parse().map_err(|e| format!("bad price {:?}: {}", price, e))?;
+    let price = fixed::to_fixed(price, market::PRICE_DECIMALS).map_err(|e| format!("bad price: {}", e))?;
+    let change_24h = match change {
+        "" => market::CHANGE_UNAVAILABLE,
+        _ => {
+            let pct: f64 = change.parse().map_err(|e| format!("bad change {:?}: {}", change, e))?;
+            ReturnBps::from_percent(pct).map_err(|e| format!("bad change: {}", e))?
+        }
+    };
+    Ok(Record { price, change_24h, timestamp, volume: 0 })
+}
@@ -0,0 +1,165 @@
+// Host-side debugging aid: parses two input.bin files with the guest's own
+// parser (header, CRC and market::read_market_input) and prints them side by
+// side, field by field, so two runs that proved different things can be
+// told apart without reading hex.
+//
+// cargo run --bin diff_input -- <a/input.bin> <b/input.bin>
+//
+// Each line is one field, `  name: value` when the files agree and
+// `* name: a | b` when they don't. Either file may be gzipped (by its .gz
+// suffix). Files of different format versions have different layouts, so
+// only the versions are compared. Exits non-zero when anything differs.
+// Record secrets are private, so only a fingerprint of each is printed
+// (the start of its SHA-256), which is enough to tell whether they differ.
+
+use std::env;
+use std::error::Error;
+use std::fmt::{Debug, Display};
+use std::process;
+
+use sha2::{Digest, Sha256};
+use sha_hasher::market::{self, MarketInput, Record, Rules};
+use sha_hasher::codec::hex;
+use sha_hasher::{crc32, header, input_gen};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let (Some(path_a), Some(path_b)) = (args.get(1), args.get(2)) else {
+        return Err("usage: diff_input <a/input.bin> <b/input.bin>".into());
+    };
+    let bytes_a = input_gen::read_input_file(path_a).map_err(|e| format!("cannot read {}: {}", path_a, e))?;
+    let bytes_b = input_gen::read_input_file(path_b).map_err(|e| format!("cannot read {}: {}", path_b, e))?;
+
+    let (version_a, version_b) = (format_version(&bytes_a), format_version(&bytes_b));
+    if version_a != version_b {
+        println!("* format version: {} | {}", version_label(version_a), version_label(version_b));
+        println!("FAIL: {} and {} have different layouts, so their fields can't be compared", path_a, path_b);
+        process::exit(1);
+    }
+    if version_a != Some(header::FORMAT_VERSION) {
+        return Err(format!(
+            "both files are {}, this tool reads format version {}",
+            version_label(version_a),
+            header::FORMAT_VERSION
+        )
+        .into());
+    }
+
+    let a = parse(path_a, &bytes_a)?;
+    let b = parse(path_b, &bytes_b)?;
+    println!("  format version: {}", header::FORMAT_VERSION);
+    let mut diff = Diff::default();
+    diff.inputs(&a, &b);
+    if diff.differences > 0 {
+        println!("FAIL: {} fields differ between {} and {}", diff.differences, path_a, path_b);
+        process::exit(1);
+    }
+    println!("PASS: {} and {} carry the same fields", path_a, path_b);
+    Ok(())
+}
+
+// The version in the header, or None when the file doesn't start with one.
+fn format_version(bytes: &[u8]) -> Option<u16> {
+    match header::read_header(bytes) {
+        Ok(_) => Some(header::FORMAT_VERSION),
+        Err(header::HeaderError::BadVersion(version)) => Some(version),
+        Err(header::HeaderError::BadMagic) => None,
+    }
+}
+
+fn version_label(version: Option<u16>) -> String {
+    version.map_or("not an input.bin (bad magic)".to_string(), |v| format!("format version {}", v))
+}
+
+fn parse(path: &str, bytes: &[u8]) -> Result<MarketInput, Box<dyn Error>> {
+    let body = crc32::strip_crc(bytes).map_err(|_| format!("{}: CRC mismatch (truncated or corrupted)", path))?;
+    let payload = header::read_header(body).map_err(|e| format!("{}: bad header: {:?}", path, e))?;
+    Ok(market::read_market_input(payload).map_err(|e| format!("{}: {}", path, e))?)
+}
+
+#[derive(Default)]
+struct Diff {
+    differences: usize,
+}
+
+impl Diff {
+    fn field(&mut self, name: &str, a: impl Display, b: impl Display) {
+        let (a, b) = (a.to_string(), b.to_string());
+        if a == b {
+            println!("  {}: {}", name, a);
+        } else {
+            println!("* {}: {} | {}", name, a, b);
+            self.differences += 1;
+        }
+    }
+
+    fn inputs(&mut self, a: &MarketInput, b: &MarketInput) {
+        self.field("records", a.records.len(), b.records.len());
+        for i in 0..a.records.len().max(b.records.len()) {
+            // "none" for a record only one file has
+            let record = |input: &MarketInput, value: fn(&Record) -> String| opt(input.records.get(i).map(value));
+            let price = |r: &Record| r.price.to_string();
+            self.field(&format!("record {} price", i), record(a, price), record(b, price));
+            let change = |r: &Record| format!("{} bps", r.change_24h.bps());
+            self.field(&format!("record {} change_24h", i), record(a, change), record(b, change));
+            let timestamp = |r: &Record| r.timestamp.to_string();
+            self.field(&format!("record {} timestamp", i), record(a, timestamp), record(b, timestamp));
+            let volume = |r: &Record| r.volume.to_string();
+            self.field(&format!("record {} volume", i), record(a, volume), record(b, volume));
+
+            let secret = |input: &MarketInput| opt(input.secrets.as_ref().and_then(|s| s.get(i)).map(fingerprint));
+            self.field(&format!("record {} secret", i), secret(a), secret(b));
+            let history = |input: &MarketInput| input.history.as_ref().and_then(|h| h.get(i)).cloned();
+            let (ha, hb) = (history(a), history(b));
+            self.field(&format!("record {} closes", i), opt(ha.as_ref().map(Vec::len)), opt(hb.as_ref().map(Vec::len)));
+            // Same length but different prices: the first close that differs
+            if let (Some(ha), Some(hb)) = (&ha, &hb) {
+                if let Some(k) = ha.iter().zip(hb).position(|(x, y)| x != y) {
+                    self.field(&format!("record {} close {}", i, k), ha[k], hb[k]);
+                }
+            }
+        }
+
+        self.field("currency", String::from_utf8_lossy(&a.currency), String::from_utf8_lossy(&b.currency));
+        self.field("price decimals", a.price_decimals, b.price_decimals);
+        self.field("source digest", opt(a.source_digest.map(|d| hex(&d))), opt(b.source_digest.map(|d| hex(&d))));
+        self.field("prev signals", opt(a.prev_signals.as_ref().map(debug)), opt(b.prev_signals.as_ref().map(debug)));
+        self.field("weights", opt(a.weights.as_ref().map(debug)), opt(b.weights.as_ref().map(debug)));
+        self.field("nonce", opt(a.nonce), opt(b.nonce));
+        self.field("output mode", a.output_mode.name(), b.output_mode.name());
+        self.field("data source", a.data_source.name(), b.data_source.name());
+
+        let (ra, rb) = (&a.rules, &b.rules);
+        self.field("thresholds", debug(ra.thresholds), debug(rb.thresholds));
+        self.field("signal mode", ra.mode.name(), rb.mode.name());
+        self.field("freshness", opt(ra.freshness.map(debug)), opt(rb.freshness.map(debug)));
+        self.field("min volume", opt(ra.min_volume), opt(rb.min_volume));
+        self.field("squeeze bps", ra.squeeze_bps, rb.squeeze_bps);
+        self.field("position", opt(ra.position.map(debug)), opt(rb.position.map(debug)));
+        self.field("levels", opt(ra.levels.map(debug)), opt(rb.levels.map(debug)));
+        self.field("direction", ra.direction.name(), rb.direction.name());
+        self.field("hysteresis bps", opt(ra.hysteresis_bps), opt(rb.hysteresis_bps));
+        self.field("price basis", ra.price_basis.name(), rb.price_basis.name());
+        self.field("ema period", ra.ema_period, rb.ema_period);
+        self.field("neutral band bps", opt(ra.neutral_band_bps), opt(rb.neutral_band_bps));
+        self.field("current time", opt(ra.current_time), opt(rb.current_time));
+        self.field("max change bps", opt(ra.max_change_bps), opt(rb.max_change_bps));
+        self.field("min hold", opt(ra.min_hold.map(debug)), opt(rb.min_hold.map(debug)));
+        let bands = |r: &Rules| opt(r.risk_bands.map(|b| debug(b.thresholds())));
+        self.field("risk bands", bands(ra), bands(rb));
+    }
+}
+
+// An absent optional field reads "none".
+fn opt(value: Option<impl Display>) -> String {
+    value.map_or("none".to_string(), |v| v.to_string())
+}
+
+fn debug(value: impl Debug) -> String {
+    format!("{:?}", value)
+}
+
+// A secret's printable stand-in: the first 8 bytes of its SHA-256.
+fn fingerprint(secret: &impl AsRef<[u8]>) -> String {
+    format!("sha256 {}...", hex(&Sha256::digest(secret.as_ref())[..8]))
+}
@@ -0,0 +1,153 @@
+// Host-side tool: turns the guest's output slots into a results.json that
+// dashboards can read without knowing the slot layout.
+//
+// cargo run --bin postprocess -- <outputs.txt> [results.json]
+//
+// outputs.txt is in the same format verify reads: the output slots in
+// order, whitespace-separated, each decimal or 0x-prefixed hex.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+
+use serde_json::{json, Value};
+use sha_hasher::codec::{self, hex};
+use sha_hasher::input_gen;
+use sha_hasher::market::{self, DataSource, OutputMode, ReturnBps};
+use sha_hasher::message::{self, DecisionMessage};
+use sha_hasher::output_layout::{self as layout, ERROR_CODE_BASE};
+use sha_hasher::strategy::{self, HoldReason, PortfolioSignal, RiskLevel, Signal};
+
+const DEFAULT_RESULTS_PATH: &str = "results.json";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let outputs_path = args.get(1).ok_or("usage: postprocess <outputs.txt> [results.json]")?;
+    let results_path = args.get(2).map(String::as_str).unwrap_or(DEFAULT_RESULTS_PATH);
+
+    let outputs = market::parse_outputs(&fs::read_to_string(outputs_path)?)?;
+    let slot = |i: usize| outputs.get(i).copied().ok_or_else(|| format!("missing output slot {}", i));
+
+    let n = slot(layout::SLOT_COUNT)?;
+    if n >= ERROR_CODE_BASE {
+        return Err(format!("guest rejected its input: error code {:#010x}", n).into());
+    }
+    let n = n as usize;
+
+    let (currency, mode) = layout::split_currency_mode(slot(layout::SLOT_CURRENCY)?);
+    let currency = String::from_utf8_lossy(&currency.to_be_bytes()[1..]).into_owned();
+    let mode = u8::try_from(mode)
+        .ok()
+        .and_then(OutputMode::from_byte)
+        .ok_or_else(|| format!("unknown output mode {}", mode))?;
+
+    let (portfolio, portfolio_tie, (bullish, bearish)) = layout::split_portfolio_vote(slot(layout::SLOT_PORTFOLIO)?);
+    let portfolio = PortfolioSignal::try_from(portfolio).map_err(|v| format!("unknown portfolio signal {}", v))?;
+
+    let (decision_version, source, decimals) =
+        layout::split_version_source_decimals(slot(layout::SLOT_PRICE_DECIMALS)?);
+    let source = u8::try_from(source)
+        .ok()
+        .and_then(DataSource::from_byte)
+        .ok_or_else(|| format!("unknown data source {}", source))?;
+    if decimals > market::MAX_PRICE_DECIMALS {
+        return Err(format!("{} price decimals, maximum is {}", decimals, market::MAX_PRICE_DECIMALS).into());
+    }
+    let price_scale = 10u64.pow(decimals) as f64;
+    let mut root = [0u32; layout::MERKLE_ROOT_SLOTS];
+    for (i, word) in root.iter_mut().enumerate() {
+        *word = slot(layout::SLOT_MERKLE_ROOT + i)?;
+    }
+    let commitment = codec::read_u32_be_chunks(&root);
+    let mut digest = [0u32; layout::MESSAGES_DIGEST_SLOTS];
+    for (i, word) in digest.iter_mut().enumerate() {
+        *word = slot(layout::SLOT_MESSAGES_DIGEST + i)?;
+    }
+    let messages_digest = codec::read_u32_be_chunks(&digest);
+    let nonce = market::join_u64(slot(layout::SLOT_NONCE_LO)?, slot(layout::SLOT_NONCE_HI)?);
+    let nonce = Some(nonce).filter(|&n| n != market::NO_NONCE);
+    let runs = slot(layout::SLOT_RUNS)?;
+    let records_layout = layout::Layout::new(n, runs).ok_or_else(|| format!("no layout for runs {:#x}", runs))?;
+    let value = |run: usize, i: usize| match records_layout.record_slot(run, i) {
+        Some(at) => slot(at),
+        None => Err(format!("run {} isn't published", run)),
+    };
+    // A u64 split over two runs, or None when the input's rules don't publish it
+    let optional_u64 = |lo: usize, hi: usize, i: usize| -> Result<Option<u64>, String> {
+        if !records_layout.has(lo) {
+            return Ok(None);
+        }
+        Ok(Some(market::join_u64(value(lo, i)?, value(hi, i)?)))
+    };
+    let mut results = Vec::new();
+    let mut messages = Vec::with_capacity(n);
+    for i in 0..n {
+        let (signal, weight) = layout::split_signal_weight(value(layout::RUN_SIGNAL, i)?);
+        let signal = Signal::try_from(signal).map_err(|v| format!("asset {}: unknown signal {}", i, v))?;
+        let (risk, risk_band) = layout::split_risk_band(value(layout::RUN_RISK, i)?);
+        let risk = RiskLevel::try_from(risk).map_err(|v| format!("asset {}: unknown risk {}", i, v))?;
+        let price = market::join_u64(value(layout::RUN_PRICE_LO, i)?, value(layout::RUN_PRICE_HI, i)?);
+        let position_size = optional_u64(layout::RUN_POSITION_LO, layout::RUN_POSITION_HI, i)?;
+        let stop_loss = optional_u64(layout::RUN_STOP_LO, layout::RUN_STOP_HI, i)?;
+        let take_profit = optional_u64(layout::RUN_TARGET_LO, layout::RUN_TARGET_HI, i)?;
+        // Only a HOLD has a reason (and, for missing data, the closes it had)
+        let (reason, confidence, closes) = layout::split_reason_confidence_closes(value(layout::RUN_HOLD_REASON, i)?);
+        let hold_reason = (signal == Signal::Hold)
+            .then(|| HoldReason::try_from(reason).map_err(|v| format!("asset {}: unknown hold reason {}", i, v)))
+            .transpose()?;
+        let closes_available = (hold_reason == Some(HoldReason::MissingData)).then_some(closes);
+        let confidence = Some(confidence).filter(|&c| c != strategy::CONFIDENCE_UNAVAILABLE);
+        let timestamp = value(layout::RUN_TIMESTAMP, i)?;
+        // Only published with a current time
+        let age_secs = records_layout.has(layout::RUN_AGE).then(|| value(layout::RUN_AGE, i)).transpose()?;
+        // Only published with the inputs
+        let change_24h = optional_u64(layout::RUN_CHANGE_LO, layout::RUN_CHANGE_HI, i)?
+            .map(|change| ReturnBps(change as i64))
+            .filter(|&c| c != market::CHANGE_UNAVAILABLE)
+            .map(ReturnBps::to_percent);
+        let message = DecisionMessage { timestamp: timestamp.into(), signal, risk, price, commitment };
+        messages.push(message);
+        results.push(json!({
+            "timestamp": timestamp,
+            "signal": signal.label(),
+            "hold_reason": hold_reason.map(HoldReason::name),
+            "hold_reason_code": hold_reason.map(|reason| reason as u32),
+            "confidence": confidence,
+            "closes_available": closes_available,
+            "risk": risk.label(),
+            "risk_band": risk_band,
+            "age_secs": age_secs,
+            "weight": weight,
+            "price": price as f64 / price_scale,
+            "change_24h": change_24h,
+            "position_size": position_size.map(|p| p as f64 / price_scale),
+            "stop_loss": stop_loss.map(|p| p as f64 / price_scale),
+            "take_profit": take_profit.map(|p| p as f64 / price_scale),
+            "message": hex(&message.to_bytes()),
+            "message_hash": hex(&message.hash()),
+        }));
+    }
+
+    // The messages are rebuilt from the slots above, so they only disagree
+    // with the guest's digest when the outputs aren't what it published
+    if message::messages_digest(&messages) != messages_digest {
+        return Err("the published messages digest doesn't match the messages rebuilt from the outputs".into());
+    }
+
+    let document = json!({
+        "currency": currency,
+        "output_mode": mode.name(),
+        "data_source": source.name(),
+        "portfolio": portfolio.label(),
+        "portfolio_tie": portfolio_tie,
+        "portfolio_bullish": bullish,
+        "portfolio_bearish": bearish,
+        "decision_version": decision_version,
+        "nonce": nonce,
+        "messages_digest": hex(&messages_digest),
+        "results": Value::Array(results),
+    });
+    input_gen::write_atomic(results_path, serde_json::to_string_pretty(&document)?.as_bytes())?;
+    println!("Wrote {} ({} assets)", results_path, n);
+    Ok(())
+}
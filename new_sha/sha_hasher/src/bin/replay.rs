@@ -0,0 +1,75 @@
+// Host-side oracle: re-derives the output slots from an archived input.bin
+// by running the guest's computation natively (guest::run, the same code
+// src/main.rs publishes from: parsing, decide and the Merkle commitment),
+// so disputed outputs can be checked without the zkVM.
+//
+// cargo run --bin replay -- [input.bin]                 (the outputs, in outputs.txt format)
+// cargo run --bin replay -- build/input.bin.gz           (gunzipped first, by its suffix)
+// cargo run --bin replay -- [input.bin] <outputs.txt>   (and compare them with a claim)
+//
+// The slots go to stdout and everything else to stderr, so the first form
+// can be redirected straight into verify or postprocess. Given a claimed
+// outputs.txt, a mismatch exits non-zero. Replay is only an oracle for the
+// decision logic version it was built with: a claim made under another
+// version gets a warning, and its decision slots are expected to differ.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+use sha_hasher::output_layout::{self as layout, SLOT_COUNT};
+use sha_hasher::{guest, input_gen, market, strategy};
+
+const DEFAULT_INPUT_PATH: &str = "build/input.bin";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let input_path = args.get(1).map(String::as_str).unwrap_or(DEFAULT_INPUT_PATH);
+    let claimed_path = args.get(2);
+
+    let input = input_gen::read_input_file(input_path).map_err(|e| format!("cannot read {}: {}", input_path, e))?;
+    if let Some(message) = guest::short_input_message(&input) {
+        eprintln!("{}", message);
+    }
+    let outputs = guest::run(&input);
+    print!("{}", outputs.to_text());
+    let outputs = outputs.as_slice();
+    if outputs[SLOT_COUNT] >= layout::ERROR_CODE_BASE {
+        eprintln!("note: the guest rejects {}: error code {:#010x}", input_path, outputs[SLOT_COUNT]);
+    }
+
+    let Some(claimed_path) = claimed_path else {
+        return Ok(());
+    };
+    let claimed = market::parse_outputs(&fs::read_to_string(claimed_path)?)?;
+    if let Some(&slot) = claimed.get(layout::SLOT_PRICE_DECIMALS) {
+        let (version, _, _) = layout::split_version_source_decimals(slot);
+        if version != strategy::DECISION_VERSION {
+            eprintln!(
+                "warning: {} was produced by decision logic version {}, this replay implements {}",
+                claimed_path,
+                version,
+                strategy::DECISION_VERSION
+            );
+        }
+    }
+
+    let mut failures = 0;
+    if claimed.len() != outputs.len() {
+        eprintln!("FAIL: {} has {} output slots, expected {}", claimed_path, claimed.len(), outputs.len());
+        failures += 1;
+    }
+    for (slot, (&got, &replayed)) in claimed.iter().zip(outputs).enumerate() {
+        if got != replayed {
+            eprintln!("FAIL output[{}]: claimed {:#010x}, replayed {:#010x}", slot, got, replayed);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        eprintln!("FAIL: {} mismatches between {} and the replay of {}", failures, claimed_path, input_path);
+        process::exit(1);
+    }
+    eprintln!("PASS: all {} output slots match the replay of {}", outputs.len(), input_path);
+    Ok(())
+}
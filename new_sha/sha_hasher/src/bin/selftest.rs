@@ -0,0 +1,203 @@
+// Host-side smoke test: runs the guest (guest::run) natively on a built-in
+// input and compares every output slot with golden values, so a fresh
+// checkout can be checked end to end without the zkVM.
+//
+// cargo run --bin selftest
+// cargo run --bin selftest -- --print   (the outputs, in outputs.txt format)
+// cargo run --bin selftest -- --assert-signal BUY,HOLD   (and each asset's signal, in order)
+// cargo run --bin selftest -- --input build/input.bin --assert-signal buy
+//
+// Exits non-zero on any mismatch. The goldens pin the input format, the
+// hashing and the strategy together, so a deliberate change to any of them
+// means regenerating GOLDEN with --print, and a change to what the guest
+// decides or publishes also bumps strategy::DECISION_VERSION and
+// GOLDEN_VERSION. --assert-signal pins just the
+// decisions, so CI can gate on the outcome for a known input (such as a
+// build from a ZKMD_FIXTURE): the names are matched in any case, and a
+// shorter list than the assets checks only the first ones. With --input
+// the guest runs on that file instead, and the goldens, which only hold
+// for the built-in input, aren't compared.
+
+use std::env;
+use std::error::Error;
+use std::process;
+
+use sha_hasher::market::{self, Position, Record, ReturnBps, SignalMode};
+use sha_hasher::output_layout::{self as layout, SLOT_COUNT};
+use sha_hasher::strategy::{self, Signal};
+use sha_hasher::{crc32, guest, input_gen};
+
+// Closes of price history per asset, enough for every indicator.
+const CLOSES: u64 = 40;
+
+// guest::run on fixture(), slot by slot.
+const GOLDEN: [u32; 63] = [
+    0x00000002, 0x4902c5f5, 0x8bda95cc, 0x77e8c3e5, 0x3c701983, 0x6b644639, 0x7b7b273c, 0x049ee02e,
+    0xfbf4ee32, 0x00555344, 0x00000011, 0x00100002, 0xf2ca716e, 0x533d650c, 0x563d7ac7, 0x555e5fb6,
+    0x0c819d91, 0xbc516a41, 0x64810dc3, 0x9d2b6fef, 0x007f8e6b, 0x00000000, 0x00000000, 0xb6eb459a,
+    0x8fb4d773, 0xc3dfa08e, 0x5c6bf344, 0x62fdad7a, 0x1bb872ce, 0x778ba351, 0xa2312566, 0x00000001,
+    0x00000000, 0x00000001, 0x00000001, 0x6553f100, 0x6553f100, 0x00647aa8, 0x00048440, 0x00000000,
+    0x00000000, 0x00000c00, 0x00000000, 0xd21dba00, 0xcb417800, 0x00000005, 0x00000002, 0x00000000,
+    0x00000043, 0x00004e20, 0x00000000, 0x00000000, 0x00000000, 0x00627834, 0x00046d20, 0x00000000,
+    0x00000000, 0x006a8204, 0x0004c9a0, 0x00000000, 0x00000000, 0x00000258, 0x00000258,
+];
+
+// The DECISION_VERSION GOLDEN was generated with, and the CRC-32 of its
+// other slots. Regenerating GOLDEN changes the CRC, so the self-test fails
+// until both are pinned again, with the version bumped.
+const GOLDEN_VERSION: u16 = 16;
+const GOLDEN_CRC: u32 = 0xb6623f24;
+
+// Two assets with a price history, in Threshold mode with position sizing
+// and exit levels: the first is a BUY (down 6.2%), the second a HOLD, both
+// ten minutes old.
+fn fixture() -> Result<Vec<u8>, market::RecordError> {
+    let records = [
+        Record { price: 6_585_000, change_24h: ReturnBps(-620), timestamp: 1_700_000_000, volume: 25_000_000_000 },
+        Record { price: 296_000, change_24h: ReturnBps(150), timestamp: 1_700_000_000, volume: 12_000_000_000 },
+    ];
+    let history: Vec<Vec<u64>> = vec![
+        (0..CLOSES).map(|i| 6_000_000 + 15_000 * i).collect(),
+        (0..CLOSES).map(|i| 300_000 - 1_000 * (i % 5)).collect(),
+    ];
+    let mut payload = Vec::new();
+    market::write_records(&mut payload, &records)?;
+    market::write_price_history(&mut payload, &history)?;
+    market::write_signal_mode(&mut payload, SignalMode::Threshold)?;
+    market::write_position(&mut payload, &Position { balance: 1_000_000, risk_fraction: 200 })?;
+    market::write_levels(&mut payload, &market::DEFAULT_LEVELS)?;
+    market::write_current_time(&mut payload, 1_700_000_600)?;
+    Ok(input_gen::frame(&payload))
+}
+
+#[derive(Default)]
+struct Args {
+    print: bool,
+    input: Option<String>,
+    assert_signals: Vec<Signal>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut rest = env::args().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--print" => args.print = true,
+            "--input" => args.input = Some(rest.next().ok_or("--input needs a path")?),
+            "--assert-signal" => {
+                let names = rest.next().ok_or("--assert-signal needs a signal, e.g. BUY")?;
+                for name in names.split(',') {
+                    let signal = Signal::from_label(name.trim()).ok_or_else(|| format!("unknown signal {:?}", name))?;
+                    args.assert_signals.push(signal);
+                }
+            }
+            other => return Err(format!("unknown argument {:?}", other)),
+        }
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args()?;
+    let input = match &args.input {
+        Some(path) => input_gen::read_input_file(path).map_err(|e| format!("cannot read {}: {}", path, e))?,
+        None => fixture()?,
+    };
+    let outputs = guest::run(&input);
+    if args.print {
+        print!("{}", outputs.to_text());
+        return Ok(());
+    }
+    let outputs = outputs.as_slice();
+
+    let mut failures = 0;
+    if args.input.is_none() {
+        failures += check_pins();
+        failures += check_golden(outputs);
+        if failures == 0 {
+            println!("PASS: all {} output slots match ({}-byte input)", outputs.len(), input.len());
+        }
+    }
+    if !args.assert_signals.is_empty() {
+        failures += check_signals(outputs, &args.assert_signals);
+    }
+    if failures > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// 1 when GOLDEN or DECISION_VERSION no longer match their pins.
+fn check_pins() -> usize {
+    let mut bytes = Vec::with_capacity(4 * GOLDEN.len());
+    for (_, word) in GOLDEN.iter().enumerate().filter(|&(slot, _)| slot != layout::SLOT_DECISION_VERSION) {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    let crc = crc32::crc32(&bytes);
+    if crc == GOLDEN_CRC && strategy::DECISION_VERSION == GOLDEN_VERSION {
+        return 0;
+    }
+    println!(
+        "FAIL: GOLDEN (CRC {:#010x}) and DECISION_VERSION {} aren't the pinned CRC {:#010x} and version {}; \
+         a change to the outputs bumps the version, then re-pins both",
+        crc,
+        strategy::DECISION_VERSION,
+        GOLDEN_CRC,
+        GOLDEN_VERSION
+    );
+    1
+}
+
+// The number of slots that differ from GOLDEN, each reported.
+fn check_golden(outputs: &[u32]) -> usize {
+    let mut failures = 0;
+    if outputs.len() != GOLDEN.len() {
+        println!("FAIL: {} output slots, expected {}", outputs.len(), GOLDEN.len());
+        failures += 1;
+    }
+    for (slot, (&got, &expected)) in outputs.iter().zip(&GOLDEN).enumerate() {
+        if got != expected {
+            println!("FAIL output[{}]: {:#010x}, expected {:#010x}", slot, got, expected);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        println!("FAIL: {} mismatches against the golden outputs", failures);
+    }
+    failures
+}
+
+// The number of assets whose published signal isn't the expected one.
+fn check_signals(outputs: &[u32], expected: &[Signal]) -> usize {
+    let n = outputs[SLOT_COUNT];
+    if n >= layout::ERROR_CODE_BASE {
+        println!("FAIL: the guest rejected the input (error code {:#010x}), so it has no signals", n);
+        return 1;
+    }
+    let n = n as usize;
+    if expected.len() > n {
+        println!("FAIL: {} signals asserted, but the input has {} assets", expected.len(), n);
+        return 1;
+    }
+    let Some(records_layout) = layout::Layout::new(n, outputs[layout::SLOT_RUNS]) else {
+        println!("FAIL: output[{}] is {:#x}, which isn't a layout", layout::SLOT_RUNS, outputs[layout::SLOT_RUNS]);
+        return 1;
+    };
+    let mut failures = 0;
+    for (i, &want) in expected.iter().enumerate() {
+        let at = records_layout.record_slot(layout::RUN_SIGNAL, i).expect("every layout publishes signals");
+        let (code, _) = layout::split_signal_weight(outputs[at]);
+        match Signal::try_from(code) {
+            Ok(got) if got == want => println!("PASS: asset {} is {}", i, got.label()),
+            Ok(got) => {
+                println!("FAIL: asset {} is {}, expected {}", i, got.label(), want.label());
+                failures += 1;
+            }
+            Err(code) => {
+                println!("FAIL: asset {} published unknown signal {}, expected {}", i, code, want.label());
+                failures += 1;
+            }
+        }
+    }
+    failures
+}
@@ -0,0 +1,508 @@
+// Host-side check: recomputes each asset's signal, risk and age from the
+// published inputs (build/public.json, written by build.rs) and compares
+// them with the output slots the guest claims to have produced.
+//
+// cargo run --bin verify -- <outputs.txt> [public.json] [market_response.json]
+//
+// outputs.txt lists the output slots in order, separated by whitespace,
+// each as decimal or 0x-prefixed hex. The Merkle root is recomputed from
+// the records, the source digest and the nonce, which is checked against
+// its own slots as well; given the archived responses
+// (build/market_response.json), the source digest is recomputed from their
+// bodies instead of taken from public.json. With the inputs published
+// (output_mode "inputs"), each record's republished price, 24h change and
+// timestamp are checked against public.json too, and the published data
+// source always is: a proof of CoinGecko data doesn't reconcile against an
+// archived Binance response.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+use serde_json::Value;
+use sha_hasher::market::{
+    self, DataSource, Direction, Freshness, Levels, MinHold, OutputMode, Position, PriceBasis, Record, ReturnBps,
+    RiskBands, Rules, SignalMode, Thresholds,
+};
+use sha_hasher::message::{self, DecisionMessage};
+use sha_hasher::codec::{self, hex};
+use sha_hasher::merkle;
+use sha_hasher::output_layout::{self as layout, SLOT_COUNT};
+use sha_hasher::strategy::{self, PortfolioSignal, RiskLevel, Signal};
+
+const DEFAULT_PUBLIC_PATH: &str = "build/public.json";
+
+struct PublicInputs {
+    coins: Vec<String>,
+    records: Vec<Record>,
+    history: Option<Vec<Vec<u64>>>,
+    rules: Rules,
+    currency: [u8; 3],
+    price_decimals: u32,
+    source_digest: Option<[u8; 32]>,
+    prev_signals: Option<Vec<u32>>,
+    weights: Option<Vec<u32>>,
+    nonce: Option<u64>,
+    output_mode: OutputMode,
+    data_source: DataSource,
+    // The input paired each record with a secret in its Merkle leaf; the
+    // secrets themselves are private and not in public.json.
+    record_secrets: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    let outputs_path = args.get(1).ok_or("usage: verify <outputs.txt> [public.json] [market_response.json]")?;
+    let public_path = args.get(2).map(String::as_str).unwrap_or(DEFAULT_PUBLIC_PATH);
+    let responses_path = args.get(3);
+
+    let outputs = market::parse_outputs(&fs::read_to_string(outputs_path)?)?;
+    let public: Value = serde_json::from_str(&fs::read_to_string(public_path)?)?;
+    let public = parse_public(&public)?;
+
+    let slot = |i: usize| outputs.get(i).copied().ok_or_else(|| format!("missing output slot {}", i));
+    let n = public.records.len();
+    if slot(SLOT_COUNT)? != n as u32 {
+        println!("FAIL: output[{}] is {:#x}, expected record count {}", SLOT_COUNT, slot(SLOT_COUNT)?, n);
+        process::exit(1);
+    }
+    // Where each value is depends on which runs the rules publish
+    let runs = layout::runs_for(&public.rules, public.history.is_some(), public.output_mode);
+    let published = slot(layout::SLOT_RUNS)?;
+    let Some(records_layout) = layout::Layout::new(n, runs).filter(|_| published == runs) else {
+        println!("FAIL: output[{}] is {:#x}, expected runs {:#x}", layout::SLOT_RUNS, published, runs);
+        process::exit(1);
+    };
+    let value = |run: usize, i: usize| match records_layout.record_slot(run, i) {
+        Some(at) => slot(at),
+        None => Err(format!("run {} isn't published", run)),
+    };
+
+    let mut failures = 0;
+    let (currency, mode) = layout::split_currency_mode(slot(layout::SLOT_CURRENCY)?);
+    if currency != market::currency_code(public.currency) {
+        println!(
+            "FAIL: currency code is {:#x}, expected {:#x} ({})",
+            currency,
+            market::currency_code(public.currency),
+            String::from_utf8_lossy(&public.currency)
+        );
+        failures += 1;
+    }
+    if mode != public.output_mode as u32 {
+        let expected = public.output_mode;
+        println!("FAIL: output mode is {}, expected {} ({})", mode, expected as u32, expected.name());
+        failures += 1;
+    }
+    let (version, source, decimals) = layout::split_version_source_decimals(slot(layout::SLOT_PRICE_DECIMALS)?);
+    if version != strategy::DECISION_VERSION {
+        println!(
+            "FAIL: the guest ran decision logic version {}, this verifier implements {}",
+            version,
+            strategy::DECISION_VERSION
+        );
+        failures += 1;
+    }
+    if decimals != public.price_decimals {
+        println!("FAIL: price decimals are {}, expected {}", decimals, public.price_decimals);
+        failures += 1;
+    }
+    if source != public.data_source as u32 {
+        let expected = public.data_source;
+        println!("FAIL: data source is {}, expected {} ({})", source, expected as u32, expected.name());
+        failures += 1;
+    }
+
+    let expected_digest = match responses_path {
+        Some(path) => Some(digest_of_responses(&fs::read_to_string(path)?)?),
+        None => public.source_digest,
+    };
+    // The eight big-endian words of a digest published from slot `start` on
+    let digest_slots = |start: usize| -> Result<[u32; 8], String> {
+        let mut words = [0u32; 8];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = slot(start + i)?;
+        }
+        Ok(words)
+    };
+    let root_slots = digest_slots(layout::SLOT_MERKLE_ROOT)?;
+    let expected_root = merkle::records_root(&public.records, expected_digest.as_ref(), None, public.nonce);
+    let mut source = match expected_digest {
+        Some(digest) => format!("source digest {}", hex(&digest)),
+        None => "no source digest (built from the cache)".to_string(),
+    };
+    if let Some(nonce) = public.nonce {
+        source += &format!(", nonce {}", nonce);
+    }
+    if public.record_secrets {
+        println!("SKIP merkle root: the leaves include per-record secrets, which public.json doesn't carry");
+    } else if root_slots == market::digest_words(&expected_root) {
+        println!("PASS merkle root: {} ({})", hex(&expected_root), source);
+    } else {
+        println!(
+            "FAIL merkle root: outputs disagree with the records and {} from {}",
+            source,
+            responses_path.map_or(public_path, String::as_str)
+        );
+        failures += 1;
+    }
+    // Published on its own too, so a consumer can read it without the tree
+    let nonce = market::join_u64(slot(layout::SLOT_NONCE_LO)?, slot(layout::SLOT_NONCE_HI)?);
+    let expected_nonce = public.nonce.unwrap_or(market::NO_NONCE);
+    let nonce_label = |nonce: u64| if nonce == market::NO_NONCE { "none".to_string() } else { nonce.to_string() };
+    if nonce == expected_nonce {
+        println!("PASS nonce: {}", nonce_label(nonce));
+    } else {
+        println!("FAIL nonce: {} (expected {})", nonce_label(nonce), nonce_label(expected_nonce));
+        failures += 1;
+    }
+
+    let mut signals = Vec::with_capacity(n);
+    let mut messages = Vec::with_capacity(n);
+    // The messages commit to the published root, which is checked above
+    let commitment = codec::read_u32_be_chunks(&root_slots);
+    for (i, record) in public.records.iter().enumerate() {
+        let closes = public.history.as_ref().and_then(|h| h.get(i)).map(Vec::as_slice);
+        let prev = public.prev_signals.as_ref().and_then(|p| p.get(i)).and_then(|&s| Signal::try_from(s).ok());
+        let expected = strategy::decide(record, &public.rules, closes, prev);
+        signals.push(expected.signal);
+        messages.push(DecisionMessage::from_decision(&expected, commitment));
+        let (signal, weight) = layout::split_signal_weight(value(layout::RUN_SIGNAL, i)?);
+        let (risk, band) = layout::split_risk_band(value(layout::RUN_RISK, i)?);
+        let age = records_layout.has(layout::RUN_AGE).then(|| value(layout::RUN_AGE, i)).transpose()?.map(u64::from);
+        // An age past the run's u32 overflowed and saturated
+        let expected_age = expected.age_secs.map(|a| a.min(u32::MAX.into()));
+        let expected_weight = public.weights.as_ref().and_then(|w| w.get(i)).copied();
+        let decoded = (Signal::try_from(signal), RiskLevel::try_from(risk), band, age, weight);
+        let age_label = |age: Option<u64>| age.map_or("unknown".to_string(), |a| format!("{}s", a));
+        let weight_label = |weight: Option<u32>| weight.map_or("none".to_string(), |w| w.to_string());
+        let band_label = |band: Option<u32>| band.map_or("none".to_string(), |b| b.to_string());
+        if decoded == (Ok(expected.signal), Ok(expected.risk), expected.risk_band, expected_age, expected_weight) {
+            println!(
+                "PASS {}: signal {}, risk {}, band {}, age {}, weight {}",
+                public.coins[i],
+                expected.signal.label(),
+                expected.risk.label(),
+                band_label(band),
+                age_label(age),
+                weight_label(weight)
+            );
+        } else {
+            println!(
+                "FAIL {}: signal {} (expected {}), risk {} (expected {}), band {} (expected {}), \
+                 age {} (expected {}), weight {} (expected {})",
+                public.coins[i],
+                signal,
+                expected.signal.label(),
+                risk,
+                expected.risk.label(),
+                band_label(band),
+                band_label(expected.risk_band),
+                age_label(age),
+                age_label(expected_age),
+                weight_label(weight),
+                weight_label(expected_weight)
+            );
+            failures += 1;
+        }
+        if public.output_mode == OutputMode::WithInputs {
+            failures += check_inputs(&public.coins[i], record, |run| value(run, i))?;
+        }
+    }
+
+    let expected_messages = message::messages_digest(&messages);
+    if digest_slots(layout::SLOT_MESSAGES_DIGEST)? == market::digest_words(&expected_messages) {
+        println!("PASS messages digest: {}", hex(&expected_messages));
+    } else {
+        println!("FAIL messages digest: outputs disagree with the messages of the expected decisions");
+        failures += 1;
+    }
+
+    let weights = public.weights.as_deref();
+    let (expected, expected_tie, expected_totals) = (
+        strategy::aggregate(&signals, weights),
+        strategy::is_tie(&signals, weights),
+        strategy::vote_totals(&signals, weights),
+    );
+    let (portfolio, tie, totals) = layout::split_portfolio_vote(slot(layout::SLOT_PORTFOLIO)?);
+    let tie_label = |tie: bool| if tie { " (tie)" } else { "" };
+    let totals_label = |(bullish, bearish): (u64, u64)| format!("{} bullish to {} bearish", bullish, bearish);
+    if PortfolioSignal::try_from(portfolio) == Ok(expected) && tie == expected_tie && totals == expected_totals {
+        println!("PASS portfolio: {}{}, {}", expected.label(), tie_label(tie), totals_label(totals));
+    } else {
+        println!(
+            "FAIL portfolio: {}{}, {} (expected {}{}, {})",
+            portfolio,
+            tie_label(tie),
+            totals_label(totals),
+            expected.label(),
+            tie_label(expected_tie),
+            totals_label(expected_totals)
+        );
+        failures += 1;
+    }
+
+    if failures > 0 {
+        println!("FAIL: {} mismatches across {} assets", failures, n);
+        process::exit(1);
+    }
+    println!("PASS: all {} assets match", n);
+    Ok(())
+}
+
+fn parse_public(public: &Value) -> Result<PublicInputs, Box<dyn Error>> {
+    let u64_field = |v: &Value, name: &str| v[name].as_u64().ok_or_else(|| format!("missing {}", name));
+    let i64_field = |v: &Value, name: &str| v[name].as_i64().ok_or_else(|| format!("missing {}", name));
+    let bps_field = |v: &Value, name: &str| i64_field(v, name).map(ReturnBps);
+
+    let mut coins = Vec::new();
+    let mut records = Vec::new();
+    for r in public["records"].as_array().ok_or("missing records")? {
+        coins.push(r["coin"].as_str().ok_or("missing coin")?.to_string());
+        records.push(Record {
+            price: u64_field(r, "price")?,
+            change_24h: bps_field(r, "change_24h")?,
+            timestamp: u64_field(r, "timestamp")?,
+            volume: u64_field(r, "volume")?,
+        });
+    }
+
+    let thresholds = Thresholds {
+        buy: bps_field(&public["thresholds"], "buy")?,
+        sell: bps_field(&public["thresholds"], "sell")?,
+    };
+
+    let history = match public["history"].as_array() {
+        Some(series) => Some(
+            series
+                .iter()
+                .map(|s| {
+                    s.as_array()
+                        .ok_or("history entry is not an array")?
+                        .iter()
+                        .map(|p| p.as_u64().ok_or("history price is not an integer"))
+                        .collect::<Result<Vec<u64>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+    if history.as_ref().is_some_and(|h| h.len() != records.len()) {
+        return Err("history has a different number of series than records".into());
+    }
+
+    let mode = public["mode"].as_str().ok_or("missing mode")?;
+    let mode = SignalMode::from_name(mode).ok_or_else(|| format!("unknown mode {:?}", mode))?;
+
+    let freshness = match &public["freshness"] {
+        Value::Null => None,
+        f => Some(Freshness {
+            current_time: u64_field(f, "current_time")?,
+            max_age_secs: u64_field(f, "max_age_secs")?,
+        }),
+    };
+
+    let min_volume = match &public["min_volume"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "min_volume")?),
+    };
+
+    let squeeze_bps = u64_field(public, "squeeze_bps")?;
+
+    let direction = public["direction"].as_str().ok_or("missing direction")?;
+    let direction = Direction::from_name(direction).ok_or_else(|| format!("unknown direction {:?}", direction))?;
+
+    let levels = match &public["levels"] {
+        Value::Null => None,
+        l => Some(Levels {
+            stop_bps: u64_field(l, "stop_bps")?,
+            target_bps: u64_field(l, "target_bps")?,
+        }),
+    };
+
+    let position = match &public["position"] {
+        Value::Null => None,
+        p => Some(Position {
+            balance: u64_field(p, "balance")?,
+            risk_fraction: u64_field(p, "risk_fraction")?,
+        }),
+    };
+
+    let currency = public["currency"].as_str().ok_or("missing currency")?.as_bytes();
+    if !market::is_currency(currency) {
+        return Err(format!("bad currency {:?}", String::from_utf8_lossy(currency)).into());
+    }
+    let currency = [currency[0], currency[1], currency[2]];
+    let price_decimals = u32::try_from(u64_field(public, "price_decimals")?)
+        .ok()
+        .filter(|&d| d <= market::MAX_PRICE_DECIMALS)
+        .ok_or("price_decimals is out of range")?;
+
+    let source_digest = match &public["source_digest"] {
+        Value::Null => None,
+        d => {
+            let text = d.as_str().ok_or("source_digest is not a string")?;
+            Some(codec::digest_from_hex(text).ok_or_else(|| format!("bad digest {:?}", text))?)
+        }
+    };
+
+    let hysteresis_bps = match &public["hysteresis_bps"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "hysteresis_bps")?),
+    };
+    let prev_signals = match public["prev_signals"].as_array() {
+        Some(codes) => Some(
+            codes
+                .iter()
+                .map(|c| c.as_u64().and_then(|c| u32::try_from(c).ok()).ok_or("previous signal is not a u32"))
+                .collect::<Result<Vec<u32>, _>>()?,
+        ),
+        None => None,
+    };
+    if prev_signals.as_ref().is_some_and(|p| p.len() != records.len()) {
+        return Err("prev_signals has a different length than records".into());
+    }
+    let weights = match public["weights"].as_array() {
+        Some(weights) => Some(
+            weights
+                .iter()
+                .map(|w| w.as_u64().and_then(|w| u32::try_from(w).ok()).ok_or("weight is not a u32"))
+                .collect::<Result<Vec<u32>, _>>()?,
+        ),
+        None => None,
+    };
+    if weights.as_ref().is_some_and(|w| w.len() != records.len()) {
+        return Err("weights has a different length than records".into());
+    }
+
+    let price_basis = public["price_basis"].as_str().ok_or("missing price_basis")?;
+    let price_basis =
+        PriceBasis::from_name(price_basis).ok_or_else(|| format!("unknown price_basis {:?}", price_basis))?;
+    let ema_period = u32::try_from(u64_field(public, "ema_period")?).map_err(|_| "ema_period is not a u32")?;
+    let neutral_band_bps = match &public["neutral_band_bps"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "neutral_band_bps")?),
+    };
+    let current_time = match &public["current_time"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "current_time")?),
+    };
+    let nonce = match &public["nonce"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "nonce")?),
+    };
+    let output_mode = match &public["output_mode"] {
+        Value::Null => OutputMode::Standard,
+        m => {
+            let name = m.as_str().ok_or("output_mode is not a string")?;
+            OutputMode::from_name(name).ok_or_else(|| format!("unknown output_mode {:?}", name))?
+        }
+    };
+    let data_source = match &public["data_source"] {
+        Value::Null => DataSource::Unknown,
+        s => {
+            let name = s.as_str().ok_or("data_source is not a string")?;
+            DataSource::from_name(name).ok_or_else(|| format!("unknown data_source {:?}", name))?
+        }
+    };
+    let max_change_bps = match &public["max_change_bps"] {
+        Value::Null => None,
+        _ => Some(u64_field(public, "max_change_bps")?),
+    };
+
+    let min_hold = match &public["min_hold"] {
+        Value::Null => None,
+        m => Some(MinHold {
+            last_trade_time: u64_field(m, "last_trade_time")?,
+            min_hold_secs: u64_field(m, "min_hold_secs")?,
+        }),
+    };
+
+    let risk_bands = match public["risk_bands"].as_array() {
+        Some(thresholds) => {
+            let thresholds = thresholds
+                .iter()
+                .map(|t| t.as_u64().ok_or("risk band threshold is not a u64"))
+                .collect::<Result<Vec<u64>, _>>()?;
+            Some(RiskBands::new(&thresholds).ok_or("risk_bands are not strictly ascending thresholds")?)
+        }
+        None => None,
+    };
+
+    let rules = Rules {
+        thresholds,
+        mode,
+        freshness,
+        min_volume,
+        squeeze_bps,
+        position,
+        levels,
+        direction,
+        hysteresis_bps,
+        price_basis,
+        ema_period,
+        neutral_band_bps,
+        current_time,
+        max_change_bps,
+        min_hold,
+        risk_bands,
+    };
+    let record_secrets = public["record_secrets"].as_bool().unwrap_or(false);
+    Ok(PublicInputs {
+        coins,
+        records,
+        history,
+        rules,
+        currency,
+        price_decimals,
+        source_digest,
+        prev_signals,
+        weights,
+        nonce,
+        output_mode,
+        data_source,
+        record_secrets,
+    })
+}
+
+// The inputs the guest republished for one record, against public.json:
+// the number of mismatches (0 or 1).
+fn check_inputs(
+    coin: &str,
+    record: &Record,
+    value: impl Fn(usize) -> Result<u32, String>,
+) -> Result<usize, Box<dyn Error>> {
+    let price = market::join_u64(value(layout::RUN_PRICE_LO)?, value(layout::RUN_PRICE_HI)?);
+    let change = market::join_u64(value(layout::RUN_CHANGE_LO)?, value(layout::RUN_CHANGE_HI)?) as i64;
+    let timestamp = value(layout::RUN_TIMESTAMP)?;
+    // A timestamp past u32 publishes u32::MAX (and the record HOLDs)
+    let expected_timestamp = u32::try_from(record.timestamp).unwrap_or(u32::MAX);
+    if (price, change, timestamp) == (record.price, record.change_24h.bps(), expected_timestamp) {
+        println!("PASS {} inputs: price {}, change {} bps, timestamp {}", coin, price, change, timestamp);
+        return Ok(0);
+    }
+    println!(
+        "FAIL {} inputs: price {} (expected {}), change {} bps (expected {}), timestamp {} (expected {})",
+        coin,
+        price,
+        record.price,
+        change,
+        record.change_24h.bps(),
+        timestamp,
+        expected_timestamp
+    );
+    Ok(1)
+}
+
+// The digest build.rs committed to, recomputed from the archived bodies.
+fn digest_of_responses(contents: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    let document: Value = serde_json::from_str(contents)?;
+    let bodies = document["responses"]
+        .as_array()
+        .ok_or("missing responses")?
+        .iter()
+        .map(|r| r["body"].as_str().map(str::as_bytes).ok_or("response is missing its body"))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(market::source_digest(bodies))
+}
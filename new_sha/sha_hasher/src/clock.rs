@@ -0,0 +1,55 @@
+// Where host code gets "now" from. build.rs stamps records and writes the
+// current time field from a Clock rather than the system, so a pinned build
+// time (FixedClock) and a test's made-up one go down the same path as the
+// wall clock. The guest never reads a clock: its time is TAG_CURRENT_TIME.
+
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+
+pub trait Clock {
+    // Unix seconds.
+    fn now_secs(&self) -> Result<u64, SystemTimeError>;
+}
+
+// The wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_secs(&self) -> Result<u64, SystemTimeError> {
+        Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+    }
+}
+
+// Always the same Unix seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now_secs(&self) -> Result<u64, SystemTimeError> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::{Freshness, Record, ReturnBps, Rules, DEFAULT_RULES};
+    use crate::strategy::{decide, HoldReason, Signal};
+
+    #[test]
+    fn fixed_clock_drives_freshness_and_age() {
+        let record =
+            Record { price: 250_012, change_24h: ReturnBps(-1000), timestamp: 1_700_000_000, volume: 5_000_000 };
+        let rules_at = |clock: &dyn Clock| {
+            let now = clock.now_secs().unwrap();
+            Rules { freshness: Some(Freshness { current_time: now, max_age_secs: 900 }), ..DEFAULT_RULES }
+        };
+        // Exactly max_age_secs old is still fresh
+        let fresh = decide(&record, &rules_at(&FixedClock(1_700_000_900)), None, None);
+        assert_eq!((fresh.signal, fresh.age_secs), (Signal::Buy, Some(900)));
+        let stale = decide(&record, &rules_at(&FixedClock(1_700_000_901)), None, None);
+        assert_eq!((stale.hold_reason, stale.age_secs), (Some(HoldReason::StaleTimestamp), Some(901)));
+        // A clock behind the record: age 0, never stale
+        let skewed = decide(&record, &rules_at(&FixedClock(1_699_999_000)), None, None);
+        assert_eq!((skewed.signal, skewed.age_secs), (Signal::Buy, Some(0)));
+    }
+}
@@ -0,0 +1,210 @@
+// Settings from config.toml, shared by build.rs and the host tools. Each
+// key stands for the environment variable of the same name without ZKMD_,
+// in lower case (`coins` for ZKMD_COINS), and takes the same value:
+//
+//   coins = ["bitcoin", "ethereum"]
+//   currency = "usd"
+//   buy_threshold = -500      # bps
+//   sell_threshold = 300      # bps
+//   sell_threshold_pct = "3%" # or in percent, instead of sell_threshold
+//   fetch_retries = 3
+//   http_timeout = 10         # seconds
+//
+// A variable that is set wins over the file, and a key the file leaves out
+// keeps its built-in default: defaults < config.toml < environment.
+
+use std::env;
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::market::{self, ReturnBps, Thresholds};
+
+// Read from the working directory unless ZKMD_CONFIG names another file.
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub coins: Option<Vec<String>>,
+    pub currency: Option<String>,
+    pub buy_threshold: Option<i64>,
+    pub sell_threshold: Option<i64>,
+    pub buy_threshold_pct: Option<Percent>,
+    pub sell_threshold_pct: Option<Percent>,
+    pub fetch_retries: Option<u32>,
+    pub http_timeout: Option<u64>,
+}
+
+// A *_pct key: a TOML number (5, -2.5) or a string ("5%", "-2.5%").
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Percent {
+    Number(f64),
+    Text(String),
+}
+
+impl Config {
+    // The file at ZKMD_CONFIG or DEFAULT_CONFIG_PATH. No file means every
+    // default; a file that doesn't parse (or has an unknown key) is an error.
+    pub fn load() -> Result<Config, String> {
+        let path = env::var("ZKMD_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("invalid {}: {}", path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("cannot read {}: {}", path, e)),
+        }
+    }
+
+    // The setting for environment variable `name`, as that variable would
+    // spell it: the variable when it is set, otherwise the file's value.
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.resolve(name, env::var(name).ok())
+    }
+
+    fn resolve(&self, name: &str, from_env: Option<String>) -> Option<String> {
+        from_env.or_else(|| self.file_value(name))
+    }
+
+    fn file_value(&self, name: &str) -> Option<String> {
+        match name {
+            "ZKMD_COINS" => self.coins.as_ref().map(|coins| coins.join(",")),
+            "ZKMD_CURRENCY" => self.currency.clone(),
+            "ZKMD_BUY_THRESHOLD" => self.buy_threshold.map(|bps| bps.to_string()),
+            "ZKMD_SELL_THRESHOLD" => self.sell_threshold.map(|bps| bps.to_string()),
+            "ZKMD_BUY_THRESHOLD_PCT" => self.buy_threshold_pct.as_ref().map(Percent::spelled),
+            "ZKMD_SELL_THRESHOLD_PCT" => self.sell_threshold_pct.as_ref().map(Percent::spelled),
+            "ZKMD_FETCH_RETRIES" => self.fetch_retries.map(|n| n.to_string()),
+            "ZKMD_HTTP_TIMEOUT" => self.http_timeout.map(|secs| secs.to_string()),
+            _ => None,
+        }
+    }
+
+    // ZKMD_BUY_THRESHOLD and/or ZKMD_SELL_THRESHOLD in bps, or the same
+    // with _PCT in percent; an unset one keeps its default. None when
+    // neither is set anywhere.
+    pub fn thresholds(&self) -> Result<Option<Thresholds>, String> {
+        let buy = self.threshold("ZKMD_BUY_THRESHOLD")?;
+        let sell = self.threshold("ZKMD_SELL_THRESHOLD")?;
+        if buy.is_none() && sell.is_none() {
+            return Ok(None);
+        }
+        let defaults = market::DEFAULT_THRESHOLDS;
+        let thresholds = Thresholds { buy: buy.unwrap_or(defaults.buy), sell: sell.unwrap_or(defaults.sell) };
+        if thresholds.buy > thresholds.sell {
+            return Err(format!(
+                "the BUY threshold ({} bps) is above the SELL threshold ({} bps)",
+                thresholds.buy.bps(),
+                thresholds.sell.bps()
+            ));
+        }
+        Ok(Some(thresholds))
+    }
+
+    fn threshold(&self, name: &str) -> Result<Option<ReturnBps>, String> {
+        let pct_name = format!("{}_PCT", name);
+        self.resolve_threshold(name, env::var(name).ok(), env::var(&pct_name).ok())
+    }
+
+    // Either spelling set in the environment overrides both in the file;
+    // both spellings at the same level is an error rather than a guess.
+    fn resolve_threshold(
+        &self,
+        name: &str,
+        bps_env: Option<String>,
+        pct_env: Option<String>,
+    ) -> Result<Option<ReturnBps>, String> {
+        let pct_name = format!("{}_PCT", name);
+        let (bps, pct) = if bps_env.is_some() || pct_env.is_some() {
+            (bps_env, pct_env)
+        } else {
+            (self.file_value(name), self.file_value(&pct_name))
+        };
+        match (bps, pct) {
+            (Some(_), Some(_)) => Err(format!("set {} or {}, not both", name, pct_name)),
+            (Some(bps), None) => {
+                bps.parse().map(|bps| Some(ReturnBps(bps))).map_err(|e| format!("{} must be whole bps: {}", name, e))
+            }
+            (None, Some(pct)) => ReturnBps::parse_percent(&pct).map(Some).map_err(|e| format!("{}: {}", pct_name, e)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+impl Percent {
+    // As the _PCT variable would spell it.
+    fn spelled(&self) -> String {
+        match self {
+            Percent::Number(pct) => pct.to_string(),
+            Percent::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let config = Config {
+            coins: Some(vec!["bitcoin".to_string(), "solana".to_string()]),
+            buy_threshold: Some(-800),
+            ..Config::default()
+        };
+        // File values come back spelled like the variable
+        assert_eq!(config.resolve("ZKMD_COINS", None).as_deref(), Some("bitcoin,solana"));
+        assert_eq!(config.resolve("ZKMD_BUY_THRESHOLD", None).as_deref(), Some("-800"));
+        // A variable that is set wins
+        let from_env = Some("ethereum".to_string());
+        assert_eq!(config.resolve("ZKMD_COINS", from_env).as_deref(), Some("ethereum"));
+        // Neither: the caller's default applies
+        assert_eq!(config.resolve("ZKMD_CURRENCY", None), None);
+        assert_eq!(Config::default().resolve("ZKMD_COINS", None), None);
+    }
+
+    #[test]
+    fn thresholds_in_percent() {
+        for text in ["5", "5%", "5.0%", " 5 % "] {
+            assert_eq!(ReturnBps::parse_percent(text), Ok(ReturnBps(500)), "{:?}", text);
+        }
+        assert_eq!(ReturnBps::parse_percent("-2.5%"), Ok(ReturnBps(-250)));
+        assert_eq!(ReturnBps::parse_percent("0.125%"), Ok(ReturnBps(13)));
+        for bad in ["", "%", "5%%", "five", "nan"] {
+            assert!(ReturnBps::parse_percent(bad).is_err(), "{:?}", bad);
+        }
+
+        let config = Config {
+            buy_threshold_pct: Some(Percent::Number(-8.0)),
+            sell_threshold_pct: Some(Percent::Text("4.5%".to_string())),
+            ..Config::default()
+        };
+        let buy = |bps: Option<&str>, pct: Option<&str>| {
+            config.resolve_threshold("ZKMD_BUY_THRESHOLD", bps.map(String::from), pct.map(String::from))
+        };
+        assert_eq!(buy(None, None), Ok(Some(ReturnBps(-800))));
+        assert_eq!(config.resolve_threshold("ZKMD_SELL_THRESHOLD", None, None), Ok(Some(ReturnBps(450))));
+        // Either spelling in the environment wins over the file
+        assert_eq!(buy(Some("-300"), None), Ok(Some(ReturnBps(-300))));
+        assert_eq!(buy(None, Some("-6%")), Ok(Some(ReturnBps(-600))));
+        assert!(buy(Some("-300"), Some("-6%")).is_err());
+        let both = Config { buy_threshold: Some(-300), ..config.clone() };
+        assert!(both.resolve_threshold("ZKMD_BUY_THRESHOLD", None, None).is_err());
+    }
+
+    #[test]
+    fn the_readme_example_fits_one_proof() {
+        use crate::market::{OutputMode, Rules, DEFAULT_RULES};
+        use crate::output_layout::{runs_for, Layout};
+
+        // The coins of the README's example config.toml leave room for the
+        // default rules' runs, with or without a history, and the age that
+        // build.rs's current time publishes
+        let n = ["bitcoin", "ethereum", "solana"].len();
+        let rules = Rules { current_time: Some(1_700_000_000), ..DEFAULT_RULES };
+        for has_history in [false, true] {
+            let runs = runs_for(&rules, has_history, OutputMode::Standard);
+            assert!(Layout::new(n, runs).is_some(), "history: {}", has_history);
+        }
+    }
+}
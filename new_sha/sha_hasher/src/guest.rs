@@ -0,0 +1,349 @@
+// The guest's whole computation, from the raw input.bin to its output
+// slots. main.rs only moves the slots in and out of the zkVM, so host tools
+// (selftest) can run exactly what gets proven. Like the rest of the
+// decision core it needs only core and alloc.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::output_layout::{self as layout, SLOT_COUNT};
+use crate::message::{self, DecisionMessage};
+use crate::{codec, crc32, header, market, merkle, strategy};
+
+// Smallest input.bin with a valid layout: the header, a record count and
+// the CRC trailer (zero records, no optional fields).
+pub const MIN_INPUT_LEN: usize = header::HEADER_LEN + 4 + crc32::CRC_LEN;
+
+// Value published in output slot 0 when the input is shorter than
+// MIN_INPUT_LEN (the same code as the top-level example's ERR_TOO_SHORT).
+pub const ERR_TOO_SHORT: u32 = 0xE000_0005;
+
+// Output slots 0..len, with every slot the guest didn't write left at 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outputs(Vec<u32>);
+
+impl Outputs {
+    pub fn set(&mut self, slot: usize, value: u32) {
+        if self.0.len() <= slot {
+            self.0.resize(slot + 1, 0);
+        }
+        self.0[slot] = value;
+    }
+
+    pub fn as_slice(&self) -> &[u32] {
+        &self.0
+    }
+
+    // The slots in outputs.txt format, one hex word per line, as replay and
+    // selftest --print write them and market::parse_outputs reads them back.
+    pub fn to_text(&self) -> String {
+        self.0.iter().map(|value| format!("{:#010x}\n", value)).collect()
+    }
+}
+
+// What to tell the operator when `input` is rejected before any field is
+// read, with both lengths; None for an input long enough to parse. An
+// empty one is almost always a build step that failed, so it says so.
+// run() itself prints nothing, so its callers choose the stream: main.rs
+// prints it from the guest, replay to stderr.
+pub fn short_input_message(input: &[u8]) -> Option<String> {
+    match input.len() {
+        0 => Some("error: input.bin is empty — did the build step fail?".to_string()),
+        len if len < MIN_INPUT_LEN => {
+            Some(format!("error: input.bin too short: expected >= {} bytes, got {}", MIN_INPUT_LEN, len))
+        }
+        _ => None,
+    }
+}
+
+pub fn run(input: &[u8]) -> Outputs {
+    let mut out = Outputs::default();
+
+    // Commit to the exact input bytes first, so every outcome (including
+    // errors) is bound to the input that produced it
+    let digest: [u8; 32] = Sha256::digest(input).into();
+    codec::write_u32_be_chunks(&digest, |i, word| out.set(layout::SLOT_INPUT_DIGEST + i, word));
+
+    // A wrong-sized file from the build pipeline gets its own code (and
+    // short_input_message), before any field is read
+    if input.len() < MIN_INPUT_LEN {
+        out.set(SLOT_COUNT, ERR_TOO_SHORT);
+        return out;
+    }
+
+    // Catch truncated/corrupted files before reading any field
+    let input = match crc32::strip_crc(input) {
+        Ok(body) => body,
+        Err(_) => {
+            out.set(SLOT_COUNT, crc32::ERR_BAD_CRC);
+            return out;
+        }
+    };
+
+    // Refuse to parse offsets out of a file with the wrong magic/version
+    let input = match header::read_header(input) {
+        Ok(payload) => payload,
+        Err(e) => {
+            out.set(SLOT_COUNT, e.code());
+            return out;
+        }
+    };
+
+    // Parse the asset records (and optional fields) from input.bin
+    let parsed = match market::read_market_input(input) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            out.set(SLOT_COUNT, market::ERR_BAD_RECORDS);
+            return out;
+        }
+    };
+    // read_market_input refuses an input whose records don't fit
+    let Some(slots) = parsed.layout() else {
+        out.set(SLOT_COUNT, market::ERR_BAD_RECORDS);
+        return out;
+    };
+    let market::MarketInput {
+        records,
+        history,
+        rules,
+        currency,
+        price_decimals,
+        source_digest,
+        prev_signals,
+        secrets,
+        weights,
+        nonce,
+        output_mode,
+        data_source,
+    } = parsed;
+    let n = records.len();
+    out.set(SLOT_COUNT, n as u32);
+    out.set(layout::SLOT_CURRENCY, layout::currency_and_mode(market::currency_code(currency), output_mode as u32));
+    let version = strategy::DECISION_VERSION;
+    out.set(layout::SLOT_PRICE_DECIMALS, layout::version_source_decimals(version, data_source as u32, price_decimals));
+    // Each record's secret is paired with it in its Merkle leaf
+    let root = merkle::records_root(&records, source_digest.as_ref(), secrets.as_deref(), nonce);
+    codec::write_u32_be_chunks(&root, |i, word| out.set(layout::SLOT_MERKLE_ROOT + i, word));
+    out.set(layout::SLOT_RUNS, slots.runs);
+    let (nonce_lo, nonce_hi) = market::split_u64(nonce.unwrap_or(market::NO_NONCE));
+    out.set(layout::SLOT_NONCE_LO, nonce_lo);
+    out.set(layout::SLOT_NONCE_HI, nonce_hi);
+
+    let mut signals = Vec::with_capacity(n);
+    let mut messages = Vec::with_capacity(n);
+    for (i, record) in records.iter().enumerate() {
+        let closes = history.as_ref().and_then(|h| h.get(i)).map(Vec::as_slice);
+        // An unknown previous code is treated as no previous signal
+        let prev = prev_signals.as_ref().and_then(|p| p.get(i)).and_then(|&s| strategy::Signal::try_from(s).ok());
+        let decision = strategy::decide(record, &rules, closes, prev);
+        signals.push(decision.signal);
+        messages.push(DecisionMessage::from_decision(&decision, root));
+
+        // Make results public: each published field gets its own run of N
+        // slots, and the record's weight rides along with its signal
+        let mut values = decision.outputs();
+        let weight = weights.as_ref().and_then(|w| w.get(i)).copied();
+        values[layout::RUN_WEIGHT] = layout::signal_and_weight(values[layout::RUN_SIGNAL], weight);
+        // The price and timestamp are in the runs above already
+        let (change_lo, change_hi) = market::split_u64(record.change_24h.bps() as u64);
+        let values = values.into_iter().chain([change_lo, change_hi]);
+        for (run, value) in values.enumerate() {
+            if let Some(slot) = slots.record_slot(run, i) {
+                out.set(slot, value);
+            }
+        }
+    }
+    let digest = message::messages_digest(&messages);
+    codec::write_u32_be_chunks(&digest, |i, word| out.set(layout::SLOT_MESSAGES_DIGEST + i, word));
+    let weights = weights.as_deref();
+    let portfolio = layout::portfolio_vote(
+        strategy::aggregate(&signals, weights) as u32,
+        strategy::is_tie(&signals, weights),
+        strategy::vote_totals(&signals, weights),
+    );
+    out.set(layout::SLOT_PORTFOLIO, portfolio);
+    out
+}
+
+// The inputs are framed with input_gen, a std module
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    // Value `run` of record `i`, found through the runs `out` says it publishes.
+    fn value(out: &Outputs, run: usize, i: usize) -> u32 {
+        let slots = out.as_slice();
+        let layout = layout::Layout::new(slots[SLOT_COUNT] as usize, slots[layout::SLOT_RUNS]).unwrap();
+        slots[layout.record_slot(run, i).unwrap()]
+    }
+
+    #[test]
+    fn short_input_is_rejected_with_its_own_code() {
+        let mut payload = Vec::new();
+        market::write_records(&mut payload, &[]).unwrap();
+        let empty = crate::input_gen::frame(&payload);
+        assert_eq!(empty.len(), MIN_INPUT_LEN);
+        assert_eq!(run(&empty).as_slice()[SLOT_COUNT], 0);
+
+        assert_eq!(short_input_message(&empty), None);
+        assert_eq!(
+            short_input_message(&empty[..3]).as_deref(),
+            Some("error: input.bin too short: expected >= 16 bytes, got 3")
+        );
+        assert_eq!(short_input_message(&[]).as_deref(), Some("error: input.bin is empty — did the build step fail?"));
+        for len in 0..MIN_INPUT_LEN {
+            let out = run(&empty[..len]);
+            assert_eq!(out.as_slice()[SLOT_COUNT], ERR_TOO_SHORT, "{} bytes", len);
+            // Still bound to the bytes it was given
+            let digest: [u8; 32] = Sha256::digest(&empty[..len]).into();
+            let words = &out.as_slice()[layout::SLOT_INPUT_DIGEST..][..layout::INPUT_DIGEST_SLOTS];
+            assert_eq!(words, market::digest_words(&digest));
+        }
+        // What replay prints for it reads back as the same slots
+        let out = run(&empty[..3]);
+        assert_eq!(market::parse_outputs(&out.to_text()).unwrap(), out.as_slice());
+    }
+
+    #[test]
+    fn with_inputs_republishes_the_change() {
+        let record = market::Record {
+            price: 6_585_000,
+            change_24h: market::ReturnBps(-620),
+            timestamp: 1_700_000_000,
+            volume: 5_000_000,
+        };
+        let input = |records: &[market::Record], mode| {
+            let mut payload = Vec::new();
+            market::write_records(&mut payload, records).unwrap();
+            market::write_output_mode(&mut payload, mode).unwrap();
+            crate::input_gen::frame(&payload)
+        };
+
+        let standard = run(&input(&[record], market::OutputMode::Standard));
+        let with_inputs = run(&input(&[record], market::OutputMode::WithInputs));
+        let slots = with_inputs.as_slice();
+        let usd = market::currency_code(market::DEFAULT_CURRENCY);
+        assert_eq!(layout::split_currency_mode(slots[layout::SLOT_OUTPUT_MODE]), (usd, 1));
+        let change = |run| value(&with_inputs, run, 0);
+        let change = market::join_u64(change(layout::RUN_CHANGE_LO), change(layout::RUN_CHANGE_HI)) as i64;
+        assert_eq!(change, -620);
+        // Everything but the mode, the runs, the input digest and the new
+        // runs is as before
+        assert_eq!(slots[layout::SLOT_RUNS], standard.as_slice()[layout::SLOT_RUNS] | 0b11 << layout::RUN_CHANGE_LO);
+        let published = slots.len() - layout::INPUT_RUNS;
+        assert_eq!(slots[layout::FIRST_RECORD_SLOT..published], standard.as_slice()[layout::FIRST_RECORD_SLOT..]);
+        assert_eq!(standard.as_slice()[layout::SLOT_CURRENCY], usd);
+
+        // As many records as fit without their inputs don't fit with them
+        let most = [record; layout::MAX_RECORDS];
+        assert_eq!(run(&input(&most, market::OutputMode::Standard)).as_slice()[SLOT_COUNT], most.len() as u32);
+        let with_inputs = run(&input(&most, market::OutputMode::WithInputs));
+        assert_eq!(with_inputs.as_slice()[SLOT_COUNT], market::ERR_BAD_RECORDS);
+    }
+
+    #[test]
+    fn negative_changes_survive_the_round_trip() {
+        // What build.rs does with a fetched percentage: rounded, not truncated
+        // towards zero, so -6.2% (-620.0000000000001 bps in f64) is -620
+        let bps = |pct: f64| market::ReturnBps::from_percent(pct).unwrap().bps();
+        assert_eq!((bps(-5.0), bps(-6.2), bps(-5.019), bps(-0.004), bps(-0.005)), (-500, -620, -502, 0, -1));
+        // Nothing reaches CHANGE_UNAVAILABLE, the smallest i64
+        let limit = i64::MIN as f64 / 100.0;
+        assert!(market::ReturnBps::from_percent(limit).is_err());
+        assert!(market::ReturnBps::from_percent(-limit).is_err());
+        assert!(market::ReturnBps::from_percent(f64::NEG_INFINITY).is_err());
+
+        // Through input.bin's little-endian record and the guest's decision
+        let decide_with = |change: i64, thresholds: Option<market::Thresholds>| {
+            let record = market::Record {
+                price: 6_585_000,
+                change_24h: market::ReturnBps(change),
+                timestamp: 1_700_000_000,
+                volume: 5_000_000,
+            };
+            let mut payload = Vec::new();
+            market::write_records(&mut payload, &[record]).unwrap();
+            market::write_output_mode(&mut payload, market::OutputMode::WithInputs).unwrap();
+            if let Some(thresholds) = &thresholds {
+                market::write_thresholds(&mut payload, thresholds).unwrap();
+            }
+            let out = run(&crate::input_gen::frame(&payload));
+            let change = market::join_u64(value(&out, layout::RUN_CHANGE_LO, 0), value(&out, layout::RUN_CHANGE_HI, 0));
+            let change = change as i64;
+            let signal = layout::split_signal_weight(value(&out, layout::RUN_SIGNAL, 0)).0;
+            (change, strategy::Signal::try_from(signal))
+        };
+        let decide = |change: i64| decide_with(change, None);
+        use strategy::Signal::{Buy, Hold, Sell};
+        // The README's thresholds are inclusive: -5% is on DEFAULT_BUY_BELOW
+        // and BUYs, and HOLDs once the buy threshold is -501 bps
+        assert_eq!(decide(bps(-5.0)), (-500, Ok(Buy)));
+        assert_eq!(decide(bps(-4.99)), (-499, Ok(Hold)));
+        let lower = market::Thresholds { buy: market::ReturnBps(-501), ..market::DEFAULT_THRESHOLDS };
+        assert_eq!(decide_with(bps(-5.0), Some(lower)), (-500, Ok(Hold)));
+        assert_eq!(decide(bps(-5.01)), (-501, Ok(Buy)));
+        assert_eq!(decide(bps(-6.2)), (-620, Ok(Buy)));
+        assert_eq!(decide(i64::MIN + 1), (i64::MIN + 1, Ok(Buy)));
+        assert_eq!(decide(i64::MAX), (i64::MAX, Ok(Sell)));
+        assert_eq!(decide(market::CHANGE_UNAVAILABLE.bps()), (i64::MIN, Ok(Hold)));
+    }
+
+    #[test]
+    fn publishes_the_nonce() {
+        let record = market::Record { price: 6_585_000, change_24h: market::ReturnBps(-620), timestamp: 0, volume: 0 };
+        let mut payload = Vec::new();
+        market::write_records(&mut payload, &[record]).unwrap();
+        let published = |payload: &[u8]| {
+            let out = run(&crate::input_gen::frame(payload));
+            market::join_u64(out.as_slice()[layout::SLOT_NONCE_LO], out.as_slice()[layout::SLOT_NONCE_HI])
+        };
+        assert_eq!(published(&payload), market::NO_NONCE);
+        assert_eq!(market::write_nonce(&mut payload.clone(), market::NO_NONCE), Err(market::RecordError::BadField(market::TAG_NONCE)));
+        market::write_nonce(&mut payload, 1 << 40 | 7).unwrap();
+        assert_eq!(published(&payload), 1 << 40 | 7);
+    }
+
+    #[test]
+    fn publishes_the_messages_digest() {
+        let record =
+            market::Record { price: 6_585_000, change_24h: market::ReturnBps(-620), timestamp: 1_700_000_000, volume: 0 };
+        let mut payload = Vec::new();
+        market::write_records(&mut payload, &[record]).unwrap();
+        let out = run(&crate::input_gen::frame(&payload));
+        let slots = out.as_slice();
+
+        // The message a consumer rebuilds from the slots alone
+        let words = |start: usize| -> [u32; 8] { slots[start..start + 8].try_into().unwrap() };
+        let message = DecisionMessage {
+            timestamp: value(&out, layout::RUN_TIMESTAMP, 0).into(),
+            signal: strategy::Signal::Buy,
+            risk: strategy::RiskLevel::Low,
+            price: record.price,
+            commitment: codec::read_u32_be_chunks(&words(layout::SLOT_MERKLE_ROOT)),
+        };
+        assert_eq!(layout::split_signal_weight(value(&out, layout::RUN_SIGNAL, 0)).0, message.signal as u32);
+        let risk = layout::split_risk_band(value(&out, layout::RUN_RISK, 0)).0;
+        assert_eq!(risk, message.risk as u32);
+        let digest = codec::read_u32_be_chunks(&words(layout::SLOT_MESSAGES_DIGEST));
+        assert_eq!(digest, message::messages_digest(&[message]));
+    }
+
+    #[test]
+    fn publishes_the_data_source() {
+        let record =
+            market::Record { price: 6_585_000, change_24h: market::ReturnBps(-620), timestamp: 0, volume: 0 };
+        let mut payload = Vec::new();
+        market::write_records(&mut payload, &[record]).unwrap();
+        let unknown = run(&crate::input_gen::frame(&payload));
+        market::write_data_source(&mut payload, market::DataSource::Binance).unwrap();
+        let binance = run(&crate::input_gen::frame(&payload));
+
+        let published = |out: &Outputs| layout::split_version_source_decimals(out.as_slice()[layout::SLOT_DATA_SOURCE]);
+        let version = strategy::DECISION_VERSION;
+        assert_eq!(published(&unknown), (version, market::DataSource::Unknown as u32, market::PRICE_DECIMALS));
+        assert_eq!(published(&binance), (version, market::DataSource::Binance as u32, market::PRICE_DECIMALS));
+    }
+}
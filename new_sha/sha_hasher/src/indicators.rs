@@ -0,0 +1,434 @@
+// Integer-only technical indicators for the guest.
+//
+// Moving averages are simple means of integer cents, rounded down.
+//
+// RSI fixed-point scheme: prices are integer cents. Wilder's average gain
+// and loss are kept in cents scaled by RSI_AVG_SCALE (u128, so no
+// overflow for any u64 price), seeded with the simple average of the
+// first RSI_PERIOD changes and then smoothed as
+//     avg = (avg * (RSI_PERIOD - 1) + change * RSI_AVG_SCALE) / RSI_PERIOD
+// The result is RSI scaled by 100 (0..=10000, i.e. 2 implied decimals):
+//     rsi_x100 = 10000 * avg_gain / (avg_gain + avg_loss)
+// which equals 100 - 100 / (1 + RS). A flat series gives 5000.
+//
+// Bollinger Bands are SMA +/- BOLLINGER_K population standard deviations
+// over the last BOLLINGER_PERIOD closes. The variance is computed exactly
+// in u128 cents^2 and the deviation is its integer square root, rounded
+// down. Band width is (upper - lower) in basis points of the middle band,
+// saturating at u64::MAX.
+//
+// Realized volatility is the population standard deviation of the last
+// VOLATILITY_PERIOD close-to-close returns, each in basis points (i128,
+// truncated toward zero). The variance comes from sums of integers,
+//     var = (n * sum(r^2) - sum(r)^2) / n^2
+// which is exact until the final division; the deviation is its integer
+// square root. Returns too wild for those sums to fit an i128 saturate the
+// volatility at u64::MAX.
+//
+// MACD fixed-point scheme: closes are scaled to cents * MACD_SCALE (i128,
+// since the MACD line goes negative). An N-period EMA uses the usual
+// smoothing factor 2 / (N + 1), applied as an exact ratio of integers
+//     ema = (ema * (N - 1) + 2 * value) / (N + 1)
+// so there is no rounded multiplier constant, only one division per step
+// (truncating toward zero). Each EMA is seeded with the simple mean of its
+// first N inputs. MACD = EMA(MACD_FAST) - EMA(MACD_SLOW) of the closes, the
+// signal line is EMA(MACD_SIGNAL) of MACD, and the histogram is
+// MACD - signal. The histogram is published in hundredths of a cent.
+//
+// The price EMA (PriceBasis::Ema) uses the same EMA over closes scaled by
+// MACD_SCALE, with the period from the input. Its change is the move from
+// the EMA one close earlier, in basis points (truncated toward zero, and
+// kept as an i128 since it can outgrow a record's i64 change), and the
+// price itself is published in cents, rounded down.
+//
+// The price WMA (PriceBasis::Wma) is linearly weighted: over the last N
+// closes, oldest first, close k (1..=N) has weight k, so the latest close
+// counts N times as much as the oldest:
+//     wma = sum(k * close_k) / (N * (N + 1) / 2)
+// The sum is exact in u128 and the division rounds down, to MACD_SCALE
+// fractions of a cent. Its change and published price are taken exactly as
+// for the price EMA, from the WMA of the N closes before the latest.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub const RSI_PERIOD: usize = 14;
+pub const RSI_AVG_SCALE: u128 = 1_000_000;
+
+// RSI thresholds, scaled by 100 like the RSI value itself.
+pub const RSI_OVERSOLD_X100: u32 = 3000;
+pub const RSI_OVERBOUGHT_X100: u32 = 7000;
+
+// Published in the RSI slot when there isn't enough history.
+pub const RSI_UNAVAILABLE: u32 = u32::MAX;
+
+// Moving-average crossover periods, in closes.
+pub const SMA_SHORT_PERIOD: usize = 5;
+pub const SMA_LONG_PERIOD: usize = 20;
+
+// Published in the SMA slots when there isn't enough history (or the
+// average doesn't fit 32 bits).
+pub const SMA_UNAVAILABLE: u32 = u32::MAX;
+
+// Bollinger window and width, in closes and standard deviations.
+pub const BOLLINGER_PERIOD: usize = 20;
+pub const BOLLINGER_K: u64 = 2;
+
+// Published in the band width and squeeze slots when there isn't enough
+// history.
+pub const BANDS_UNAVAILABLE: u32 = u32::MAX;
+
+// Returns in the realized volatility window (so one more close is needed).
+pub const VOLATILITY_PERIOD: usize = 20;
+
+// Published in the volatility slot when there isn't enough history.
+pub const VOLATILITY_UNAVAILABLE: u32 = u32::MAX;
+
+// MACD periods, in closes.
+pub const MACD_FAST: usize = 12;
+pub const MACD_SLOW: usize = 26;
+pub const MACD_SIGNAL: usize = 9;
+pub const MACD_SCALE: i128 = 1_000_000;
+
+// Fewest closes that give a histogram value; a crossover needs one more.
+pub const MACD_MIN_CLOSES: usize = MACD_SLOW + MACD_SIGNAL - 1;
+
+// Published in the histogram slot when there isn't enough history; the slot
+// otherwise holds an i32, so this is the one value it can't take.
+pub const MACD_UNAVAILABLE: u32 = i32::MIN as u32;
+
+// Published in the EMA price slot when there isn't enough history (or the
+// price doesn't fit 32 bits). The WMA shares the slot, and this value.
+pub const EMA_UNAVAILABLE: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cross {
+    Above,
+    Below,
+}
+
+// RSI x 100 over `closes` (oldest first), or None with fewer than
+// RSI_PERIOD + 1 prices.
+pub fn rsi_x100(closes: &[u64]) -> Option<u32> {
+    if closes.len() < RSI_PERIOD + 1 {
+        return None;
+    }
+    let period = RSI_PERIOD as u128;
+    let moves = closes.windows(2).map(|w| {
+        let (prev, cur) = (w[0] as u128, w[1] as u128);
+        if cur >= prev { (cur - prev, 0) } else { (0, prev - cur) }
+    });
+
+    let (mut gain, mut loss) = (0u128, 0u128);
+    for (i, (up, down)) in moves.enumerate() {
+        if i < RSI_PERIOD {
+            gain += up * RSI_AVG_SCALE;
+            loss += down * RSI_AVG_SCALE;
+            if i == RSI_PERIOD - 1 {
+                gain /= period;
+                loss /= period;
+            }
+        } else {
+            gain = (gain * (period - 1) + up * RSI_AVG_SCALE) / period;
+            loss = (loss * (period - 1) + down * RSI_AVG_SCALE) / period;
+        }
+    }
+
+    if gain + loss == 0 {
+        return Some(5000);
+    }
+    Some((10_000 * gain / (gain + loss)) as u32)
+}
+
+// The last `len` values, or None when there are fewer (or `len` is 0).
+// Every indicator takes its window through this or a length check, so a
+// short series is a None (and a MissingData HOLD), never a panic.
+fn window<T>(values: &[T], len: usize) -> Option<&[T]> {
+    if len == 0 {
+        return None;
+    }
+    values.get(values.len().checked_sub(len)?..)
+}
+
+// Mean of the last `period` closes, or None with fewer than `period` prices.
+pub fn sma(closes: &[u64], period: usize) -> Option<u64> {
+    let sum: u128 = window(closes, period)?.iter().map(|&p| p as u128).sum();
+    Some((sum / period as u128) as u64)
+}
+
+// Whether the short SMA crossed the long one on the latest close: Above if
+// it was at or below the long SMA one close earlier and is now above it,
+// Below for the reverse. None without a cross, or with fewer than
+// SMA_LONG_PERIOD + 1 closes.
+pub fn sma_cross(closes: &[u64]) -> Option<Cross> {
+    if closes.len() < SMA_LONG_PERIOD + 1 {
+        return None;
+    }
+    let (_, prev) = closes.split_last()?;
+    let (short_before, long_before) = (sma(prev, SMA_SHORT_PERIOD)?, sma(prev, SMA_LONG_PERIOD)?);
+    let (short_now, long_now) = (sma(closes, SMA_SHORT_PERIOD)?, sma(closes, SMA_LONG_PERIOD)?);
+    if short_before <= long_before && short_now > long_now {
+        Some(Cross::Above)
+    } else if short_before >= long_before && short_now < long_now {
+        Some(Cross::Below)
+    } else {
+        None
+    }
+}
+
+// Bollinger Bands over the last BOLLINGER_PERIOD closes, in cents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bands {
+    pub lower: u64,
+    pub middle: u64,
+    pub upper: u64,
+}
+
+impl Bands {
+    // (upper - lower) / middle in basis points; 0 for an all-zero window.
+    pub fn width_bps(&self) -> u64 {
+        if self.middle == 0 {
+            return 0;
+        }
+        let width = (self.upper - self.lower) as u128 * 10_000 / self.middle as u128;
+        u64::try_from(width).unwrap_or(u64::MAX)
+    }
+
+    // Above when `price` is over the upper band, Below when it is under the
+    // lower one, None inside (or on) the bands.
+    pub fn breakout(&self, price: u64) -> Option<Cross> {
+        if price > self.upper {
+            Some(Cross::Above)
+        } else if price < self.lower {
+            Some(Cross::Below)
+        } else {
+            None
+        }
+    }
+}
+
+// None with fewer than BOLLINGER_PERIOD closes. The lower band saturates at
+// zero rather than going negative.
+pub fn bollinger(closes: &[u64]) -> Option<Bands> {
+    let middle = sma(closes, BOLLINGER_PERIOD)?;
+    let sum_sq = window(closes, BOLLINGER_PERIOD)?.iter().fold(0u128, |acc, &p| {
+        let d = p.abs_diff(middle) as u128;
+        acc.saturating_add(d * d)
+    });
+    let stddev = isqrt(sum_sq / BOLLINGER_PERIOD as u128) as u64;
+    let offset = stddev.saturating_mul(BOLLINGER_K);
+    Some(Bands {
+        lower: middle.saturating_sub(offset),
+        middle,
+        upper: middle.saturating_add(offset),
+    })
+}
+
+// Stddev of the last VOLATILITY_PERIOD returns in basis points, or None with
+// fewer than VOLATILITY_PERIOD + 1 closes. A zero close gives a zero return
+// rather than dividing by it.
+pub fn volatility_bps(closes: &[u64]) -> Option<u64> {
+    let recent = window(closes, VOLATILITY_PERIOD + 1)?;
+    let (mut sum, mut sum_sq) = (0i128, Some(0i128));
+    for w in recent.windows(2) {
+        let (prev, cur) = (w[0] as i128, w[1] as i128);
+        let r = if prev == 0 { 0 } else { (cur - prev) * 10_000 / prev };
+        sum += r;
+        sum_sq = sum_sq.and_then(|s| s.checked_add(r.checked_mul(r)?));
+    }
+    let n = VOLATILITY_PERIOD as i128;
+    let variance = sum_sq
+        .and_then(|s| n.checked_mul(s)?.checked_sub(sum.checked_mul(sum)?))
+        .map(|v| v.max(0) / (n * n));
+    Some(variance.map_or(u64::MAX, |v| isqrt(v as u128) as u64))
+}
+
+// Largest r with r * r <= n (Newton's method, integers only).
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+// Latest MACD values, scaled by MACD_SCALE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Macd {
+    pub macd: i128,
+    pub signal: i128,
+    pub histogram: i128,
+    // Above when MACD crossed over its signal line on the latest close,
+    // Below for the reverse; None without a cross or without the close
+    // before the latest.
+    pub cross: Option<Cross>,
+}
+
+impl Macd {
+    // The histogram in hundredths of a cent, clamped to fit the output slot.
+    pub fn histogram_x100(&self) -> i32 {
+        let x100 = self.histogram * 100 / MACD_SCALE;
+        x100.clamp(i32::MIN as i128 + 1, i32::MAX as i128) as i32
+    }
+
+    // The same, or None when it had to be clamped.
+    pub fn checked_histogram_x100(&self) -> Option<i32> {
+        let x100 = self.histogram * 100 / MACD_SCALE;
+        i32::try_from(x100).ok().filter(|&h| h != i32::MIN)
+    }
+}
+
+// None with fewer than MACD_MIN_CLOSES closes.
+pub fn macd(closes: &[u64]) -> Option<Macd> {
+    if closes.len() < MACD_MIN_CLOSES {
+        return None;
+    }
+    let scaled: Vec<i128> = closes.iter().map(|&p| p as i128 * MACD_SCALE).collect();
+    let fast = ema(&scaled, MACD_FAST)?;
+    let slow = ema(&scaled, MACD_SLOW)?;
+    // fast starts at close MACD_FAST - 1 and slow at MACD_SLOW - 1; line them up
+    let line: Vec<i128> = fast.get(MACD_SLOW - MACD_FAST..)?.iter().zip(&slow).map(|(f, s)| f - s).collect();
+    let signal = ema(&line, MACD_SIGNAL)?;
+    let histogram: Vec<i128> = line.get(MACD_SIGNAL - 1..)?.iter().zip(&signal).map(|(m, s)| m - s).collect();
+
+    let (&now, earlier) = histogram.split_last()?;
+    let cross = match earlier.last() {
+        Some(&before) if before <= 0 && now > 0 => Some(Cross::Above),
+        Some(&before) if before >= 0 && now < 0 => Some(Cross::Below),
+        _ => None,
+    };
+    Some(Macd { macd: *line.last()?, signal: *signal.last()?, histogram: now, cross })
+}
+
+// Latest EMA or WMA of the closes and its move since the close before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmoothedPrice {
+    // In cents.
+    pub price: u64,
+    pub change_bps: i128,
+}
+
+// None with fewer than `period` + 1 closes.
+pub fn price_ema(closes: &[u64], period: usize) -> Option<SmoothedPrice> {
+    if period == 0 || closes.len() <= period {
+        return None;
+    }
+    let scaled: Vec<i128> = closes.iter().map(|&p| p as i128 * MACD_SCALE).collect();
+    let [.., before, now] = ema(&scaled, period)?[..] else {
+        return None;
+    };
+    Some(smoothed_price(now, before))
+}
+
+// None with fewer than `period` + 1 closes. `period` comes from
+// TAG_PRICE_BASIS's u32, which keeps every weighted sum inside a u128.
+pub fn price_wma(closes: &[u64], period: usize) -> Option<SmoothedPrice> {
+    if period == 0 || closes.len() <= period {
+        return None;
+    }
+    let (_, earlier) = closes.split_last()?;
+    Some(smoothed_price(wma_scaled(closes, period)?, wma_scaled(earlier, period)?))
+}
+
+// The latest scaled EMA or WMA `now`, and `before` it, as a SmoothedPrice.
+fn smoothed_price(now: i128, before: i128) -> SmoothedPrice {
+    let change_bps = if before == 0 { 0 } else { (now - before) * 10_000 / before };
+    SmoothedPrice {
+        price: (now / MACD_SCALE) as u64,
+        change_bps,
+    }
+}
+
+// WMA of the last `period` values in cents * MACD_SCALE, rounded down. None
+// with fewer than `period` values.
+fn wma_scaled(values: &[u64], period: usize) -> Option<i128> {
+    let sum: u128 = window(values, period)?.iter().zip(1u128..).map(|(&p, weight)| weight * p as u128).sum();
+    let total = period as u128 * (period as u128 + 1) / 2;
+    let scale = MACD_SCALE as u128;
+    // sum * scale / total, without the product overflowing
+    Some((sum / total * scale + sum % total * scale / total) as i128)
+}
+
+// EMA of `values` from index `period - 1` on (one value per input after the
+// seed), using the exact 2 / (period + 1) smoothing described above. None
+// with fewer than `period` values.
+fn ema(values: &[i128], period: usize) -> Option<Vec<i128>> {
+    if period == 0 {
+        return None;
+    }
+    let (seed, rest) = (values.get(..period)?, values.get(period..)?);
+    let n = period as i128;
+    let mut current = seed.iter().sum::<i128>() / n;
+    let mut out = vec![current];
+    for &v in rest {
+        current = (current * (n - 1) + 2 * v) / (n + 1);
+        out.push(current);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_series_are_unavailable() {
+        assert_eq!(sma(&[], SMA_SHORT_PERIOD), None);
+        assert_eq!(sma(&[1, 2, 3], 4), None);
+        assert_eq!(sma(&[1, 2, 3], 0), None);
+        assert_eq!(sma_cross(&[7; SMA_LONG_PERIOD]), None);
+        assert_eq!(rsi_x100(&[]), None);
+        assert_eq!(rsi_x100(&[100; RSI_PERIOD]), None);
+        assert_eq!(bollinger(&[100; BOLLINGER_PERIOD - 1]), None);
+        assert_eq!(volatility_bps(&[100; VOLATILITY_PERIOD]), None);
+        assert_eq!(macd(&[100; MACD_MIN_CLOSES - 1]), None);
+        for period in [0, 3] {
+            assert_eq!(price_ema(&[100; 3][..period], period), None);
+            assert_eq!(price_wma(&[100; 3][..period], period), None);
+        }
+        assert_eq!(price_ema(&[], 0), None);
+    }
+
+    #[test]
+    fn sma_rounds_down() {
+        assert_eq!(sma(&[1, 2, 4], 2), Some(3));
+        assert_eq!(sma(&[1, 2], 2), Some(1));
+    }
+
+    #[test]
+    fn rsi_extremes() {
+        assert_eq!(rsi_x100(&[100; RSI_PERIOD + 1]), Some(5000));
+        let rising: Vec<u64> = (0..=RSI_PERIOD as u64).collect();
+        assert_eq!(rsi_x100(&rising), Some(10_000));
+        let falling: Vec<u64> = rising.iter().rev().copied().collect();
+        assert_eq!(rsi_x100(&falling), Some(0));
+    }
+
+    #[test]
+    fn u64_maxima_do_not_overflow() {
+        let max = [u64::MAX; 2 * SMA_LONG_PERIOD];
+        assert_eq!(sma(&max, SMA_LONG_PERIOD), Some(u64::MAX));
+        assert_eq!(rsi_x100(&max), Some(5000));
+        // Swinging between the extremes: equal gains and losses
+        let swings: Vec<u64> = (0..=RSI_PERIOD).map(|i| if i % 2 == 0 { 0 } else { u64::MAX }).collect();
+        assert_eq!(rsi_x100(&swings), Some(5000));
+        let top: Vec<u64> = (0..=RSI_PERIOD as u64).map(|i| u64::MAX - RSI_PERIOD as u64 + i).collect();
+        assert_eq!(rsi_x100(&top), Some(10_000));
+
+        let flat = SmoothedPrice { price: u64::MAX, change_bps: 0 };
+        assert_eq!(price_ema(&max, 3), Some(flat));
+        assert_eq!(price_wma(&max, 3), Some(flat));
+        let bands = Bands { lower: u64::MAX, middle: u64::MAX, upper: u64::MAX };
+        assert_eq!(bollinger(&max), Some(bands));
+        assert_eq!(bands.width_bps(), 0);
+        // Returns too wild for the sums saturate rather than wrap
+        let wild: Vec<u64> = (0..=VOLATILITY_PERIOD).map(|i| if i % 2 == 0 { 1 } else { u64::MAX }).collect();
+        assert_eq!(volatility_bps(&wild), Some(u64::MAX));
+        assert_eq!(volatility_bps(&max), Some(0));
+    }
+}
@@ -0,0 +1,41 @@
+// Code shared by the guest (src/main.rs) and the host-side binaries in
+// src/bin. build.rs can't link this crate, so it includes the modules it
+// needs by path instead.
+//
+// The decision core (guest::run, which is what gets proven, and the
+// parsing, indicators, strategy, Merkle root and signed message it runs)
+// only uses core and alloc. The `std` feature, on by default, adds the
+// host-only modules: clock, config, fixed and percent (f64 rounding) and
+// input_gen (it writes files). `cargo check --lib --no-default-features`
+// builds the core alone, which is how a no_std target would take it; its
+// tests still link std.
+
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
+#[path = "../../../src/codec.rs"]
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod clock;
+#[path = "../../../src/crc32.rs"]
+pub mod crc32;
+#[cfg(feature = "std")]
+pub mod config;
+pub mod guest;
+#[cfg(feature = "std")]
+#[path = "../../../src/fixed.rs"]
+pub mod fixed;
+#[path = "../../../src/header.rs"]
+pub mod header;
+pub mod indicators;
+#[cfg(feature = "std")]
+#[path = "../../../src/input_gen.rs"]
+pub mod input_gen;
+pub mod market;
+pub mod merkle;
+pub mod message;
+pub mod output_layout;
+#[cfg(feature = "std")]
+pub mod percent;
+pub mod strategy;
@@ -0,0 +1,1274 @@
+// Market records carried in the input.bin payload (after the header).
+//
+// Layout:
+//   [0..4]             record count N (u32, little-endian)
+//   [4 + 32*i ..]      record i:
+//     [0..8]   price, fixed-point with the input's price decimals
+//              (TAG_PRICE_DECIMALS), i.e. cents by default (u64,
+//              little-endian), in the input's currency (TAG_CURRENCY)
+//     [8..16]  24h change as a ReturnBps, i.e. in basis points (i64,
+//              little-endian); CHANGE_UNAVAILABLE when the source had none
+//     [16..24] Unix timestamp (u64, little-endian)
+//     [24..32] 24h volume, VOLUME_DECIMALS fixed-point, i.e. whole dollars
+//              (u64, little-endian)
+//   then zero or more optional fields, each tag at most once, each:
+//     [0]      tag (u8)
+//     [1..3]   value length L (u16, little-endian)
+//     [3..3+L] value
+//
+// Optional fields:
+//   TAG_THRESHOLDS    BUY then SELL threshold in basis points (2 x i64 LE),
+//                     BUY at most SELL. Absent: DEFAULT_THRESHOLDS.
+//   TAG_PRICE_HISTORY one series of recent closes per record, in record
+//                     order: count (u16 LE) then count x price in cents
+//                     (u64 LE), oldest first.
+//   TAG_SIGNAL_MODE   SignalMode as one byte. Absent: Rsi when a price
+//                     history is present, Threshold otherwise.
+//   TAG_FRESHNESS     current time then maximum record age in seconds
+//                     (2 x u64 LE). Absent: records never go stale.
+//   TAG_MIN_VOLUME    24h volume floor, same unit as the record (u64 LE).
+//                     Absent: no liquidity gate.
+//   TAG_CURRENCY      ISO 4217 code of every price and volume, 3 uppercase
+//                     ASCII letters. Absent: DEFAULT_CURRENCY.
+//   TAG_SQUEEZE       Bollinger band width, in basis points of the middle
+//                     band, below which a record is in a squeeze (u64 LE).
+//                     Absent: DEFAULT_SQUEEZE_BPS.
+//   TAG_POSITION      account balance in cents, then the fraction of it to
+//                     risk per trade, RISK_FRACTION_DECIMALS fixed-point
+//                     (2 x u64 LE). Absent: no position sizing.
+//   TAG_LEVELS        stop-loss distance per risk level, then take-profit
+//                     distance, both in basis points of the price
+//                     (2 x u64 LE). Absent: no exit levels.
+//   TAG_DIRECTION     Direction as one byte. Absent: Long.
+//   TAG_HYSTERESIS    margin in basis points (u64 LE), then each record's
+//                     previous signal as published (u32 LE each), in
+//                     record order. Absent: no hysteresis.
+//   TAG_SOURCE_DIGEST source_digest of the API response bodies the records
+//                     were parsed from (32 bytes). Absent: zeros in the
+//                     Merkle tree (e.g. a build from the cache).
+//   TAG_PRICE_BASIS   PriceBasis as one byte, then the EMA or WMA period in
+//                     closes (u32 LE, at least 1); an EMA's smoothing factor
+//                     is 2 / (period + 1). Absent: Raw.
+//   TAG_PRICE_DECIMALS decimal places of every price, close and balance
+//                     (one byte, at most MAX_PRICE_DECIMALS). Absent:
+//                     PRICE_DECIMALS.
+//   TAG_SECRETS       one private secret per record, in record order: length
+//                     (u16 LE) then that many bytes. Never published; each
+//                     is hashed into its record's Merkle leaf (merkle.rs).
+//                     Absent: leaves without secrets.
+//   TAG_NEUTRAL_BAND  BandMode as one byte, then the symmetric band's width
+//                     in basis points (u64 LE, 1..=i64::MAX with Symmetric,
+//                     0 with Thresholds). Absent: Thresholds.
+//   TAG_CURRENT_TIME  current time in Unix seconds (u64 LE), which every
+//                     record's age is measured from. Absent: TAG_FRESHNESS's
+//                     current time, or no ages without that either.
+//   TAG_WEIGHTS       each record's portfolio weight, in record order (u32
+//                     LE each, at most MAX_WEIGHT). Absent: one vote each.
+//   TAG_MAX_CHANGE    largest plausible |24h change| in basis points
+//                     (u64 LE); a record beyond it HOLDs for data sanity.
+//                     Absent: no bound.
+//   TAG_NONCE         replay-protection nonce (u64 LE, never NO_NONCE), one
+//                     higher for each input a generator writes; committed
+//                     in the Merkle root (merkle.rs). Absent: no nonce leaf.
+//   TAG_OUTPUT_MODE   OutputMode as one byte; WithInputs publishes
+//                     INPUT_RUNS more runs per record. Absent: Standard.
+//   TAG_MIN_HOLD      Unix seconds of the last trade (NO_LAST_TRADE before
+//                     the first), then the minimum seconds between trades
+//                     (2 x u64 LE); a BUY or SELL sooner than that after
+//                     the last trade HOLDs. Absent: no cooldown.
+//   TAG_DATA_SOURCE   DataSource the records were read from, as one byte.
+//                     Absent: Unknown.
+//   TAG_RISK_BANDS    the thresholds between risk bands, in basis points of
+//                     the risk metric (u64 LE each, strictly ascending, 1 to
+//                     MAX_RISK_BANDS - 1 of them). Absent: no risk bands.
+//
+// "Cents" below means one unit of the last price decimal.
+//
+// Where the guest publishes its results is in output_layout.rs, which also
+// caps the record count at MAX_RECORDS, and at fewer for an input whose
+// rules publish more runs (MarketInput::layout).
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::codec;
+use crate::output_layout::{self as layout, Layout, MAX_RECORDS, MAX_RISK_BANDS, MAX_WEIGHT};
+
+pub const PRICE_DECIMALS: u32 = 2;
+pub const MAX_PRICE_DECIMALS: u32 = 18;
+pub const CHANGE_DECIMALS: u32 = 2;
+pub const VOLUME_DECIMALS: u32 = 0;
+pub const RISK_FRACTION_DECIMALS: u32 = 4;
+
+pub const RECORD_LEN: usize = 32;
+
+// The currency as published: its three letters packed big-endian into the
+// low 24 bits, so b"USD" is 0x00555344.
+pub fn currency_code(currency: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, currency[0], currency[1], currency[2]])
+}
+
+pub fn is_currency(code: &[u8]) -> bool {
+    code.len() == 3 && code.iter().all(u8::is_ascii_uppercase)
+}
+
+// A 32-byte digest spans eight u32 slots (codec::write_u32_be_chunks), so
+// the slots read in order as the usual hex digest.
+pub fn digest_words(digest: &[u8; 32]) -> [u32; 8] {
+    let mut words = [0u32; 8];
+    codec::write_u32_be_chunks(digest, |i, word| words[i] = word);
+    words
+}
+
+// SHA-256 of the response bodies in the order they were fetched, each
+// after its length (u64 LE), so no two lists of bodies share a digest by
+// moving bytes from one body to the next.
+pub fn source_digest<'a>(bodies: impl IntoIterator<Item = &'a [u8]>) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for body in bodies {
+        let mut len = Vec::with_capacity(8);
+        codec::write_u64_le(&mut len, body.len() as u64);
+        hasher.update(&len);
+        hasher.update(body);
+    }
+    hasher.finalize().into()
+}
+
+// A u64 output spans two u32 slots, low word first.
+pub fn split_u64(value: u64) -> (u32, u32) {
+    (value as u32, (value >> 32) as u32)
+}
+
+pub fn join_u64(lo: u32, hi: u32) -> u64 {
+    (hi as u64) << 32 | lo as u64
+}
+
+// Output slots as the host tools read them: whitespace-separated, each
+// decimal or 0x-prefixed hex.
+pub fn parse_outputs(text: &str) -> Result<Vec<u32>, String> {
+    text.split_whitespace()
+        .map(|word| {
+            let value = match word.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => word.parse(),
+            };
+            value.map_err(|e| format!("bad output value {:?}: {}", word, e))
+        })
+        .collect()
+}
+
+pub const TAG_THRESHOLDS: u8 = 1;
+pub const TAG_PRICE_HISTORY: u8 = 2;
+pub const TAG_SIGNAL_MODE: u8 = 3;
+pub const TAG_FRESHNESS: u8 = 4;
+pub const TAG_MIN_VOLUME: u8 = 5;
+pub const TAG_CURRENCY: u8 = 6;
+pub const TAG_SQUEEZE: u8 = 7;
+pub const TAG_POSITION: u8 = 8;
+pub const TAG_LEVELS: u8 = 9;
+pub const TAG_DIRECTION: u8 = 10;
+pub const TAG_SOURCE_DIGEST: u8 = 11;
+pub const TAG_HYSTERESIS: u8 = 12;
+pub const TAG_PRICE_BASIS: u8 = 13;
+pub const TAG_PRICE_DECIMALS: u8 = 14;
+pub const TAG_SECRETS: u8 = 15;
+pub const TAG_NEUTRAL_BAND: u8 = 16;
+pub const TAG_CURRENT_TIME: u8 = 17;
+pub const TAG_WEIGHTS: u8 = 18;
+pub const TAG_MAX_CHANGE: u8 = 19;
+pub const TAG_NONCE: u8 = 20;
+pub const TAG_OUTPUT_MODE: u8 = 21;
+pub const TAG_MIN_HOLD: u8 = 22;
+pub const TAG_DATA_SOURCE: u8 = 23;
+pub const TAG_RISK_BANDS: u8 = 24;
+
+pub const DEFAULT_CURRENCY: [u8; 3] = *b"USD";
+
+// Published in output_layout::SLOT_NONCE_* for an input without a nonce, so
+// no input can carry it.
+pub const NO_NONCE: u64 = 0;
+
+// Value published in output slot 0 when the record section is malformed.
+pub const ERR_BAD_RECORDS: u32 = 0xE000_0006;
+
+// Stored as the 24h change when the source omitted it (CoinGecko does for
+// some illiquid coins).
+pub const CHANGE_UNAVAILABLE: ReturnBps = ReturnBps(i64::MIN);
+
+// A price change in basis points of the earlier price: ReturnBps(100) is
+// +1%, ReturnBps(-500) is -5%. Record changes and Thresholds are both in
+// this unit, so a percentage can't be compared with them by mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReturnBps(pub i64);
+
+// Conversions from and to a percentage are in percent.rs: they round f64s,
+// which needs std, so they're host-only.
+impl ReturnBps {
+    pub fn bps(self) -> i64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub price: u64,
+    pub change_24h: ReturnBps,
+    pub timestamp: u64,
+    pub volume: u64,
+}
+
+// The record layout lives here alone: the build script, the generators,
+// the guest and the Merkle leaves all go through these two.
+impl Record {
+    // Exactly as it appears in input.bin (the layout at the top).
+    pub fn to_bytes(self) -> [u8; RECORD_LEN] {
+        let mut out = Vec::with_capacity(RECORD_LEN);
+        codec::write_u64_le(&mut out, self.price);
+        codec::write_i64_le(&mut out, self.change_24h.bps());
+        codec::write_u64_le(&mut out, self.timestamp);
+        codec::write_u64_le(&mut out, self.volume);
+        out.try_into().unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8; RECORD_LEN]) -> Record {
+        Record {
+            price: codec::read_u64_le(&bytes[0..]),
+            change_24h: ReturnBps(codec::read_i64_le(&bytes[8..])),
+            timestamp: codec::read_u64_le(&bytes[16..]),
+            volume: codec::read_u64_le(&bytes[24..]),
+        }
+    }
+}
+
+// BUY when the 24h change is at or below `buy`, SELL when it is at or above
+// `sell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub buy: ReturnBps,
+    pub sell: ReturnBps,
+}
+
+// BUY on a drop of 5% or more, SELL on a rise of 3% or more.
+pub const DEFAULT_BUY_BELOW: ReturnBps = ReturnBps(-500);
+pub const DEFAULT_SELL_ABOVE: ReturnBps = ReturnBps(300);
+pub const DEFAULT_THRESHOLDS: Thresholds = Thresholds { buy: DEFAULT_BUY_BELOW, sell: DEFAULT_SELL_ABOVE };
+
+impl Thresholds {
+    // The bounds of a symmetric neutral band: HOLD while |change| <
+    // band_bps, BUY at -band_bps or below, SELL at band_bps or above. A
+    // width of 0 is treated as 1 and one beyond i64 as i64::MAX.
+    pub fn symmetric(band_bps: u64) -> Thresholds {
+        let band = i64::try_from(band_bps).unwrap_or(i64::MAX).max(1);
+        Thresholds { buy: ReturnBps(-band), sell: ReturnBps(band) }
+    }
+}
+
+// Where Threshold mode's neutral band comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BandMode {
+    // Between Rules::thresholds, which needn't be symmetric
+    #[default]
+    Thresholds = 0,
+    // |change| below Rules::neutral_band_bps
+    Symmetric = 1,
+}
+
+impl BandMode {
+    pub fn from_byte(byte: u8) -> Option<BandMode> {
+        match byte {
+            0 => Some(BandMode::Thresholds),
+            1 => Some(BandMode::Symmetric),
+            _ => None,
+        }
+    }
+}
+
+// Bands narrower than 4% of the middle band count as a squeeze.
+pub const DEFAULT_SQUEEZE_BPS: u64 = 400;
+
+// A record is stale when it is more than `max_age_secs` older than
+// `current_time` (Unix seconds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Freshness {
+    pub current_time: u64,
+    pub max_age_secs: u64,
+}
+
+impl Freshness {
+    pub fn is_stale(&self, timestamp: u64) -> bool {
+        age_secs(self.current_time, timestamp) > self.max_age_secs
+    }
+}
+
+// last_trade_time before any trade has been made.
+pub const NO_LAST_TRADE: u64 = 0;
+
+// A cooldown between trades: for `min_hold_secs` after `last_trade_time`
+// (Unix seconds) every BUY or SELL HOLDs instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinHold {
+    pub last_trade_time: u64,
+    pub min_hold_secs: u64,
+}
+
+impl MinHold {
+    // Never before the first trade. Without a current time the cooldown
+    // can't be shown to be over, so it still applies.
+    pub fn is_cooling_down(&self, now: Option<u64>) -> bool {
+        if self.last_trade_time == NO_LAST_TRADE {
+            return false;
+        }
+        now.is_none_or(|now| age_secs(now, self.last_trade_time) < self.min_hold_secs)
+    }
+}
+
+// Seconds from `timestamp` to `current_time`. A timestamp in the future
+// (clock skew) counts as age 0.
+pub fn age_secs(current_time: u64, timestamp: u64) -> u64 {
+    current_time.saturating_sub(timestamp)
+}
+
+// Stop-loss at price x (1 - stop_bps x risk level), take-profit at
+// price x (1 + target_bps), with both distances in basis points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Levels {
+    pub stop_bps: u64,
+    pub target_bps: u64,
+}
+
+// A 2% stop per risk level (2/4/6% for LOW/MEDIUM/HIGH) and a 6% target.
+pub const DEFAULT_LEVELS: Levels = Levels { stop_bps: 200, target_bps: 600 };
+
+// Risk bands 1..=count() over a risk metric in basis points (realized
+// volatility, or |24h change| without the history for it): N - 1 strictly
+// ascending thresholds, where a metric below the first is band 1 and one at
+// or above the last is band N. A fixed array, so Rules stays Copy. Bands
+// are published beside strategy::RiskLevel rather than replacing it; its
+// doc says why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiskBands {
+    len: usize,
+    thresholds: [u64; MAX_RISK_BANDS - 1],
+}
+
+impl RiskBands {
+    // None unless there are 1 to MAX_RISK_BANDS - 1 thresholds, strictly
+    // ascending.
+    pub fn new(thresholds: &[u64]) -> Option<RiskBands> {
+        if thresholds.is_empty() || thresholds.len() >= MAX_RISK_BANDS || thresholds.windows(2).any(|w| w[0] >= w[1]) {
+            return None;
+        }
+        let mut bands = RiskBands { len: thresholds.len(), thresholds: [0; MAX_RISK_BANDS - 1] };
+        bands.thresholds[..thresholds.len()].copy_from_slice(thresholds);
+        Some(bands)
+    }
+
+    pub fn thresholds(&self) -> &[u64] {
+        &self.thresholds[..self.len]
+    }
+
+    // N, the highest band.
+    pub fn count(&self) -> u32 {
+        self.len as u32 + 1
+    }
+
+    // 1 plus the number of thresholds at or below `metric_bps`.
+    pub fn band(&self, metric_bps: u64) -> u32 {
+        1 + self.thresholds().iter().filter(|&&t| metric_bps >= t).count() as u32
+    }
+}
+
+// EMA or WMA period used when TAG_PRICE_BASIS asks for one without it.
+pub const DEFAULT_EMA_PERIOD: u32 = 10;
+
+// What Threshold mode compares against the thresholds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriceBasis {
+    // The record's 24h change
+    #[default]
+    Raw = 0,
+    // The latest change of the EMA of the price history
+    Ema = 1,
+    // The latest change of the linearly weighted moving average of the
+    // price history (indicators::price_wma)
+    Wma = 2,
+}
+
+impl PriceBasis {
+    pub fn from_byte(byte: u8) -> Option<PriceBasis> {
+        match byte {
+            0 => Some(PriceBasis::Raw),
+            1 => Some(PriceBasis::Ema),
+            2 => Some(PriceBasis::Wma),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PriceBasis> {
+        match name {
+            "raw" => Some(PriceBasis::Raw),
+            "ema" => Some(PriceBasis::Ema),
+            "wma" => Some(PriceBasis::Wma),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PriceBasis::Raw => "raw",
+            PriceBasis::Ema => "ema",
+            PriceBasis::Wma => "wma",
+        }
+    }
+}
+
+// Which side the signals trade. Long buys dips and sells rips; Short
+// opens a short on a rip and covers it on a dip.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Long = 0,
+    Short = 1,
+}
+
+impl Direction {
+    pub fn from_byte(byte: u8) -> Option<Direction> {
+        match byte {
+            0 => Some(Direction::Long),
+            1 => Some(Direction::Short),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "long" => Some(Direction::Long),
+            "short" => Some(Direction::Short),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Long => "long",
+            Direction::Short => "short",
+        }
+    }
+}
+
+// What the guest publishes besides its decisions. Standard is the decisions
+// alone; WithInputs adds the inputs each was made from (the RUN_CHANGE_*
+// runs in output_layout.rs), at the cost of record slots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    #[default]
+    Standard = 0,
+    WithInputs = 1,
+}
+
+impl OutputMode {
+    pub fn from_byte(byte: u8) -> Option<OutputMode> {
+        match byte {
+            0 => Some(OutputMode::Standard),
+            1 => Some(OutputMode::WithInputs),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OutputMode> {
+        match name {
+            "standard" => Some(OutputMode::Standard),
+            "inputs" => Some(OutputMode::WithInputs),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputMode::Standard => "standard",
+            OutputMode::WithInputs => "inputs",
+        }
+    }
+}
+
+// Where the generator got the records, published so a verifier knows which
+// archived responses to reconcile the proof against. Cache is a build from
+// the local cache (which keeps no responses); Unknown is an input that
+// doesn't say.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataSource {
+    #[default]
+    Unknown = 0,
+    CoinGecko = 1,
+    Binance = 2,
+    Fixture = 3,
+    Cache = 4,
+}
+
+impl DataSource {
+    pub fn from_byte(byte: u8) -> Option<DataSource> {
+        match byte {
+            0 => Some(DataSource::Unknown),
+            1 => Some(DataSource::CoinGecko),
+            2 => Some(DataSource::Binance),
+            3 => Some(DataSource::Fixture),
+            4 => Some(DataSource::Cache),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DataSource> {
+        match name {
+            "unknown" => Some(DataSource::Unknown),
+            "coingecko" => Some(DataSource::CoinGecko),
+            "binance" => Some(DataSource::Binance),
+            "fixture" => Some(DataSource::Fixture),
+            "cache" => Some(DataSource::Cache),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DataSource::Unknown => "unknown",
+            DataSource::CoinGecko => "coingecko",
+            DataSource::Binance => "binance",
+            DataSource::Fixture => "fixture",
+            DataSource::Cache => "cache",
+        }
+    }
+}
+
+// Account to size positions for: `balance` in cents and `risk_fraction` as
+// a RISK_FRACTION_DECIMALS fixed-point fraction (200 = 2%).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub balance: u64,
+    pub risk_fraction: u64,
+}
+
+// How the guest turns a record into a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalMode {
+    // 24h change against Thresholds
+    Threshold = 0,
+    // RSI over the price history
+    Rsi = 1,
+    // Short vs long SMA crossover over the price history
+    MaCrossover = 2,
+    // Latest close breaking out of the Bollinger Bands
+    Bollinger = 3,
+    // MACD crossing its signal line
+    Macd = 4,
+}
+
+impl SignalMode {
+    pub fn from_byte(byte: u8) -> Option<SignalMode> {
+        match byte {
+            0 => Some(SignalMode::Threshold),
+            1 => Some(SignalMode::Rsi),
+            2 => Some(SignalMode::MaCrossover),
+            3 => Some(SignalMode::Bollinger),
+            4 => Some(SignalMode::Macd),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SignalMode> {
+        match name {
+            "threshold" => Some(SignalMode::Threshold),
+            "rsi" => Some(SignalMode::Rsi),
+            "ma_crossover" => Some(SignalMode::MaCrossover),
+            "bollinger" => Some(SignalMode::Bollinger),
+            "macd" => Some(SignalMode::Macd),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SignalMode::Threshold => "threshold",
+            SignalMode::Rsi => "rsi",
+            SignalMode::MaCrossover => "ma_crossover",
+            SignalMode::Bollinger => "bollinger",
+            SignalMode::Macd => "macd",
+        }
+    }
+
+    // The mode used when TAG_SIGNAL_MODE is absent.
+    pub fn default_for(has_history: bool) -> SignalMode {
+        if has_history { SignalMode::Rsi } else { SignalMode::Threshold }
+    }
+}
+
+// The optional fields that steer the decision, with their defaults filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub thresholds: Thresholds,
+    pub mode: SignalMode,
+    pub freshness: Option<Freshness>,
+    // A 24h volume below this forces HOLD.
+    pub min_volume: Option<u64>,
+    // Bollinger band width (bps) below which the squeeze flag is set.
+    pub squeeze_bps: u64,
+    pub position: Option<Position>,
+    pub levels: Option<Levels>,
+    pub direction: Direction,
+    // Extra margin (bps) a Threshold signal needs to reverse the previous one.
+    pub hysteresis_bps: Option<u64>,
+    pub price_basis: PriceBasis,
+    // EMA or WMA period in closes; only used with PriceBasis::Ema and Wma.
+    pub ema_period: u32,
+    // Width of a symmetric neutral band (BandMode::Symmetric), which
+    // replaces `thresholds`.
+    pub neutral_band_bps: Option<u64>,
+    // TAG_CURRENT_TIME, when the input has one.
+    pub current_time: Option<u64>,
+    // A 24h change (bps) further from zero than this is a broken feed
+    // value, and forces HOLD.
+    pub max_change_bps: Option<u64>,
+    pub min_hold: Option<MinHold>,
+    pub risk_bands: Option<RiskBands>,
+}
+
+impl Rules {
+    // What Threshold mode, its confidence and hysteresis compare against.
+    pub fn active_thresholds(&self) -> Thresholds {
+        self.neutral_band_bps.map_or(self.thresholds, Thresholds::symmetric)
+    }
+
+    // What records' ages are measured from: the current time field, or
+    // the freshness gate's current time without it.
+    pub fn now(&self) -> Option<u64> {
+        self.current_time.or(self.freshness.map(|f| f.current_time))
+    }
+}
+
+// The rules of an input.bin that sets none of the optional fields.
+pub const DEFAULT_RULES: Rules = Rules {
+    thresholds: DEFAULT_THRESHOLDS,
+    mode: SignalMode::Threshold,
+    freshness: None,
+    min_volume: None,
+    squeeze_bps: DEFAULT_SQUEEZE_BPS,
+    position: None,
+    levels: None,
+    direction: Direction::Long,
+    hysteresis_bps: None,
+    price_basis: PriceBasis::Raw,
+    ema_period: DEFAULT_EMA_PERIOD,
+    neutral_band_bps: None,
+    current_time: None,
+    max_change_bps: None,
+    min_hold: None,
+    risk_bands: None,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketInput {
+    pub records: Vec<Record>,
+    // One series per record when TAG_PRICE_HISTORY is present.
+    pub history: Option<Vec<Vec<u64>>>,
+    pub rules: Rules,
+    pub currency: [u8; 3],
+    pub price_decimals: u32,
+    pub source_digest: Option<[u8; 32]>,
+    // One previous signal code per record when TAG_HYSTERESIS is present.
+    pub prev_signals: Option<Vec<u32>>,
+    // One secret per record when TAG_SECRETS is present.
+    pub secrets: Option<Vec<Vec<u8>>>,
+    // One portfolio weight per record when TAG_WEIGHTS is present.
+    pub weights: Option<Vec<u32>>,
+    pub nonce: Option<u64>,
+    pub output_mode: OutputMode,
+    pub data_source: DataSource,
+}
+
+impl MarketInput {
+    // Where the guest publishes this input's records, or None when the
+    // runs its rules publish leave no room for them all.
+    pub fn layout(&self) -> Option<Layout> {
+        Layout::new(self.records.len(), layout::runs_for(&self.rules, self.history.is_some(), self.output_mode))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordError {
+    TooMany(usize),
+    NoRoom { records: usize, max: usize },
+    Truncated { expected: usize, actual: usize },
+    BadField(u8),
+    FieldTooLong(usize),
+    HistoryMismatch { records: usize, series: usize },
+    SecretMismatch { records: usize, secrets: usize },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::TooMany(n) => write!(f, "{} records, maximum is {}", n, MAX_RECORDS),
+            RecordError::NoRoom { records, max } => {
+                write!(f, "{} records, the outputs this input publishes have room for {}", records, max)
+            }
+            RecordError::Truncated { expected, actual } => {
+                write!(f, "record section truncated: expected {} bytes, got {}", expected, actual)
+            }
+            RecordError::BadField(tag) => write!(f, "unknown or malformed field tag {}", tag),
+            RecordError::FieldTooLong(len) => {
+                write!(f, "field is {} bytes, maximum is {}", len, u16::MAX)
+            }
+            RecordError::HistoryMismatch { records, series } => {
+                write!(f, "{} price series for {} records", series, records)
+            }
+            RecordError::SecretMismatch { records, secrets } => {
+                write!(f, "{} secrets for {} records", secrets, records)
+            }
+        }
+    }
+}
+
+impl core::error::Error for RecordError {}
+
+pub fn write_records(out: &mut Vec<u8>, records: &[Record]) -> Result<(), RecordError> {
+    if records.len() > MAX_RECORDS {
+        return Err(RecordError::TooMany(records.len()));
+    }
+    codec::write_u32_le(out, records.len() as u32);
+    for r in records {
+        out.extend_from_slice(&r.to_bytes());
+    }
+    Ok(())
+}
+
+fn write_field(out: &mut Vec<u8>, tag: u8, value: &[u8]) -> Result<(), RecordError> {
+    if value.len() > u16::MAX as usize {
+        return Err(RecordError::FieldTooLong(value.len()));
+    }
+    out.push(tag);
+    codec::write_u16_le(out, value.len() as u16);
+    out.extend_from_slice(value);
+    Ok(())
+}
+
+pub fn write_thresholds(out: &mut Vec<u8>, thresholds: &Thresholds) -> Result<(), RecordError> {
+    if thresholds.buy > thresholds.sell {
+        return Err(RecordError::BadField(TAG_THRESHOLDS));
+    }
+    let mut value = Vec::with_capacity(16);
+    codec::write_i64_le(&mut value, thresholds.buy.bps());
+    codec::write_i64_le(&mut value, thresholds.sell.bps());
+    write_field(out, TAG_THRESHOLDS, &value)
+}
+
+pub fn write_signal_mode(out: &mut Vec<u8>, mode: SignalMode) -> Result<(), RecordError> {
+    write_field(out, TAG_SIGNAL_MODE, &[mode as u8])
+}
+
+pub fn write_freshness(out: &mut Vec<u8>, freshness: &Freshness) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(16);
+    codec::write_u64_le(&mut value, freshness.current_time);
+    codec::write_u64_le(&mut value, freshness.max_age_secs);
+    write_field(out, TAG_FRESHNESS, &value)
+}
+
+pub fn write_min_hold(out: &mut Vec<u8>, min_hold: &MinHold) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(16);
+    codec::write_u64_le(&mut value, min_hold.last_trade_time);
+    codec::write_u64_le(&mut value, min_hold.min_hold_secs);
+    write_field(out, TAG_MIN_HOLD, &value)
+}
+
+pub fn write_min_volume(out: &mut Vec<u8>, min_volume: u64) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8);
+    codec::write_u64_le(&mut value, min_volume);
+    write_field(out, TAG_MIN_VOLUME, &value)
+}
+
+pub fn write_squeeze(out: &mut Vec<u8>, squeeze_bps: u64) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8);
+    codec::write_u64_le(&mut value, squeeze_bps);
+    write_field(out, TAG_SQUEEZE, &value)
+}
+
+pub fn write_position(out: &mut Vec<u8>, position: &Position) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(16);
+    codec::write_u64_le(&mut value, position.balance);
+    codec::write_u64_le(&mut value, position.risk_fraction);
+    write_field(out, TAG_POSITION, &value)
+}
+
+pub fn write_levels(out: &mut Vec<u8>, levels: &Levels) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(16);
+    codec::write_u64_le(&mut value, levels.stop_bps);
+    codec::write_u64_le(&mut value, levels.target_bps);
+    write_field(out, TAG_LEVELS, &value)
+}
+
+pub fn write_direction(out: &mut Vec<u8>, direction: Direction) -> Result<(), RecordError> {
+    write_field(out, TAG_DIRECTION, &[direction as u8])
+}
+
+pub fn write_hysteresis(out: &mut Vec<u8>, margin_bps: u64, prev_signals: &[u32]) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8 + 4 * prev_signals.len());
+    codec::write_u64_le(&mut value, margin_bps);
+    for &signal in prev_signals {
+        codec::write_u32_le(&mut value, signal);
+    }
+    write_field(out, TAG_HYSTERESIS, &value)
+}
+
+pub fn write_price_basis(out: &mut Vec<u8>, basis: PriceBasis, ema_period: u32) -> Result<(), RecordError> {
+    if ema_period == 0 {
+        return Err(RecordError::BadField(TAG_PRICE_BASIS));
+    }
+    let mut value = vec![basis as u8];
+    codec::write_u32_le(&mut value, ema_period);
+    write_field(out, TAG_PRICE_BASIS, &value)
+}
+
+pub fn write_source_digest(out: &mut Vec<u8>, digest: &[u8; 32]) -> Result<(), RecordError> {
+    write_field(out, TAG_SOURCE_DIGEST, digest)
+}
+
+pub fn write_price_decimals(out: &mut Vec<u8>, decimals: u32) -> Result<(), RecordError> {
+    if decimals > MAX_PRICE_DECIMALS {
+        return Err(RecordError::BadField(TAG_PRICE_DECIMALS));
+    }
+    write_field(out, TAG_PRICE_DECIMALS, &[decimals as u8])
+}
+
+pub fn write_currency(out: &mut Vec<u8>, currency: [u8; 3]) -> Result<(), RecordError> {
+    if !is_currency(&currency) {
+        return Err(RecordError::BadField(TAG_CURRENCY));
+    }
+    write_field(out, TAG_CURRENCY, &currency)
+}
+
+pub fn write_price_history(out: &mut Vec<u8>, history: &[Vec<u64>]) -> Result<(), RecordError> {
+    let mut value = Vec::new();
+    for series in history {
+        if series.len() > u16::MAX as usize {
+            return Err(RecordError::FieldTooLong(series.len()));
+        }
+        codec::write_u16_le(&mut value, series.len() as u16);
+        for &price in series {
+            codec::write_u64_le(&mut value, price);
+        }
+    }
+    write_field(out, TAG_PRICE_HISTORY, &value)
+}
+
+// `band_bps` Some writes BandMode::Symmetric with that width, None
+// BandMode::Thresholds.
+pub fn write_neutral_band(out: &mut Vec<u8>, band_bps: Option<u64>) -> Result<(), RecordError> {
+    let (mode, width) = match band_bps {
+        Some(width) if (1..=i64::MAX as u64).contains(&width) => (BandMode::Symmetric, width),
+        Some(_) => return Err(RecordError::BadField(TAG_NEUTRAL_BAND)),
+        None => (BandMode::Thresholds, 0),
+    };
+    let mut value = vec![mode as u8];
+    codec::write_u64_le(&mut value, width);
+    write_field(out, TAG_NEUTRAL_BAND, &value)
+}
+
+pub fn write_current_time(out: &mut Vec<u8>, current_time: u64) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8);
+    codec::write_u64_le(&mut value, current_time);
+    write_field(out, TAG_CURRENT_TIME, &value)
+}
+
+pub fn write_max_change(out: &mut Vec<u8>, max_change_bps: u64) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8);
+    codec::write_u64_le(&mut value, max_change_bps);
+    write_field(out, TAG_MAX_CHANGE, &value)
+}
+
+pub fn write_nonce(out: &mut Vec<u8>, nonce: u64) -> Result<(), RecordError> {
+    if nonce == NO_NONCE {
+        return Err(RecordError::BadField(TAG_NONCE));
+    }
+    let mut value = Vec::with_capacity(8);
+    codec::write_u64_le(&mut value, nonce);
+    write_field(out, TAG_NONCE, &value)
+}
+
+pub fn write_output_mode(out: &mut Vec<u8>, mode: OutputMode) -> Result<(), RecordError> {
+    write_field(out, TAG_OUTPUT_MODE, &[mode as u8])
+}
+
+pub fn write_data_source(out: &mut Vec<u8>, source: DataSource) -> Result<(), RecordError> {
+    write_field(out, TAG_DATA_SOURCE, &[source as u8])
+}
+
+pub fn write_risk_bands(out: &mut Vec<u8>, bands: &RiskBands) -> Result<(), RecordError> {
+    let mut value = Vec::with_capacity(8 * bands.thresholds().len());
+    for &threshold in bands.thresholds() {
+        codec::write_u64_le(&mut value, threshold);
+    }
+    write_field(out, TAG_RISK_BANDS, &value)
+}
+
+pub fn write_weights(out: &mut Vec<u8>, weights: &[u32]) -> Result<(), RecordError> {
+    if weights.iter().any(|&w| w > MAX_WEIGHT) {
+        return Err(RecordError::BadField(TAG_WEIGHTS));
+    }
+    let mut value = Vec::with_capacity(4 * weights.len());
+    for &weight in weights {
+        codec::write_u32_le(&mut value, weight);
+    }
+    write_field(out, TAG_WEIGHTS, &value)
+}
+
+pub fn write_secrets(out: &mut Vec<u8>, secrets: &[Vec<u8>]) -> Result<(), RecordError> {
+    let mut value = Vec::new();
+    for secret in secrets {
+        if secret.len() > u16::MAX as usize {
+            return Err(RecordError::FieldTooLong(secret.len()));
+        }
+        codec::write_u16_le(&mut value, secret.len() as u16);
+        value.extend_from_slice(secret);
+    }
+    write_field(out, TAG_SECRETS, &value)
+}
+
+pub fn read_market_input(payload: &[u8]) -> Result<MarketInput, RecordError> {
+    let records = read_records(payload)?;
+    let mut input = MarketInput {
+        records,
+        history: None,
+        rules: DEFAULT_RULES,
+        currency: DEFAULT_CURRENCY,
+        price_decimals: PRICE_DECIMALS,
+        source_digest: None,
+        prev_signals: None,
+        secrets: None,
+        weights: None,
+        nonce: None,
+        output_mode: OutputMode::Standard,
+        data_source: DataSource::Unknown,
+    };
+    let mut mode = None;
+    let mut seen = [false; 256];
+
+    let mut rest = &payload[4 + input.records.len() * RECORD_LEN..];
+    while !rest.is_empty() {
+        if rest.len() < 3 {
+            return Err(RecordError::Truncated { expected: 3, actual: rest.len() });
+        }
+        let tag = rest[0];
+        let len = codec::read_u16_le(&rest[1..]) as usize;
+        if rest.len() < 3 + len {
+            return Err(RecordError::Truncated { expected: 3 + len, actual: rest.len() });
+        }
+        let value = &rest[3..3 + len];
+        // A second copy would silently replace the first
+        if core::mem::replace(&mut seen[tag as usize], true) {
+            return Err(RecordError::BadField(tag));
+        }
+        match tag {
+            TAG_THRESHOLDS if len == 16 => {
+                let thresholds = Thresholds {
+                    buy: ReturnBps(codec::read_i64_le(&value[0..])),
+                    sell: ReturnBps(codec::read_i64_le(&value[8..])),
+                };
+                if thresholds.buy > thresholds.sell {
+                    return Err(RecordError::BadField(tag));
+                }
+                input.rules.thresholds = thresholds;
+            }
+            TAG_PRICE_HISTORY => {
+                let history = read_price_history(value)?;
+                if history.len() != input.records.len() {
+                    return Err(RecordError::HistoryMismatch {
+                        records: input.records.len(),
+                        series: history.len(),
+                    });
+                }
+                input.history = Some(history);
+            }
+            TAG_SECRETS => {
+                let secrets = read_secrets(value)?;
+                if secrets.len() != input.records.len() {
+                    return Err(RecordError::SecretMismatch {
+                        records: input.records.len(),
+                        secrets: secrets.len(),
+                    });
+                }
+                input.secrets = Some(secrets);
+            }
+            TAG_FRESHNESS if len == 16 => {
+                input.rules.freshness = Some(Freshness {
+                    current_time: codec::read_u64_le(&value[0..]),
+                    max_age_secs: codec::read_u64_le(&value[8..]),
+                });
+            }
+            TAG_MIN_VOLUME if len == 8 => {
+                input.rules.min_volume = Some(codec::read_u64_le(value));
+            }
+            TAG_SQUEEZE if len == 8 => {
+                input.rules.squeeze_bps = codec::read_u64_le(value);
+            }
+            TAG_POSITION if len == 16 => {
+                input.rules.position = Some(Position {
+                    balance: codec::read_u64_le(&value[0..]),
+                    risk_fraction: codec::read_u64_le(&value[8..]),
+                });
+            }
+            TAG_LEVELS if len == 16 => {
+                input.rules.levels = Some(Levels {
+                    stop_bps: codec::read_u64_le(&value[0..]),
+                    target_bps: codec::read_u64_le(&value[8..]),
+                });
+            }
+            TAG_DIRECTION if len == 1 => {
+                input.rules.direction = Direction::from_byte(value[0]).ok_or(RecordError::BadField(tag))?;
+            }
+            TAG_HYSTERESIS if len == 8 + 4 * input.records.len() => {
+                input.rules.hysteresis_bps = Some(codec::read_u64_le(&value[0..]));
+                input.prev_signals = Some(
+                    value[8..]
+                        .chunks_exact(4)
+                        .map(codec::read_u32_le)
+                        .collect(),
+                );
+            }
+            TAG_PRICE_BASIS if len == 5 => {
+                input.rules.price_basis = PriceBasis::from_byte(value[0]).ok_or(RecordError::BadField(tag))?;
+                input.rules.ema_period = codec::read_u32_le(&value[1..]);
+                if input.rules.ema_period == 0 {
+                    return Err(RecordError::BadField(tag));
+                }
+            }
+            TAG_NEUTRAL_BAND if len == 9 => {
+                let width = codec::read_u64_le(&value[1..]);
+                input.rules.neutral_band_bps = match BandMode::from_byte(value[0]) {
+                    Some(BandMode::Symmetric) if (1..=i64::MAX as u64).contains(&width) => Some(width),
+                    Some(BandMode::Thresholds) if width == 0 => None,
+                    _ => return Err(RecordError::BadField(tag)),
+                };
+            }
+            TAG_WEIGHTS if len == 4 * input.records.len() => {
+                let weights: Vec<u32> = value.chunks_exact(4).map(codec::read_u32_le).collect();
+                if weights.iter().any(|&w| w > MAX_WEIGHT) {
+                    return Err(RecordError::BadField(tag));
+                }
+                input.weights = Some(weights);
+            }
+            TAG_CURRENT_TIME if len == 8 => {
+                input.rules.current_time = Some(codec::read_u64_le(value));
+            }
+            TAG_MAX_CHANGE if len == 8 => {
+                input.rules.max_change_bps = Some(codec::read_u64_le(value));
+            }
+            TAG_NONCE if len == 8 && codec::read_u64_le(value) != NO_NONCE => {
+                input.nonce = Some(codec::read_u64_le(value));
+            }
+            TAG_MIN_HOLD if len == 16 => {
+                input.rules.min_hold = Some(MinHold {
+                    last_trade_time: codec::read_u64_le(&value[0..]),
+                    min_hold_secs: codec::read_u64_le(&value[8..]),
+                });
+            }
+            TAG_OUTPUT_MODE if len == 1 => {
+                input.output_mode = OutputMode::from_byte(value[0]).ok_or(RecordError::BadField(tag))?;
+            }
+            TAG_DATA_SOURCE if len == 1 => {
+                input.data_source = DataSource::from_byte(value[0]).ok_or(RecordError::BadField(tag))?;
+            }
+            TAG_RISK_BANDS if len.is_multiple_of(8) => {
+                let thresholds: Vec<u64> = value.chunks_exact(8).map(codec::read_u64_le).collect();
+                input.rules.risk_bands = Some(RiskBands::new(&thresholds).ok_or(RecordError::BadField(tag))?);
+            }
+            TAG_SOURCE_DIGEST if len == 32 => {
+                input.source_digest = Some(value.try_into().unwrap());
+            }
+            TAG_PRICE_DECIMALS if len == 1 && value[0] as u32 <= MAX_PRICE_DECIMALS => {
+                input.price_decimals = value[0] as u32;
+            }
+            TAG_CURRENCY if is_currency(value) => {
+                input.currency = [value[0], value[1], value[2]];
+            }
+            TAG_SIGNAL_MODE if len == 1 => {
+                mode = Some(SignalMode::from_byte(value[0]).ok_or(RecordError::BadField(tag))?);
+            }
+            _ => return Err(RecordError::BadField(tag)),
+        }
+        rest = &rest[3 + len..];
+    }
+    input.rules.mode = mode.unwrap_or(SignalMode::default_for(input.history.is_some()));
+    if input.layout().is_none() {
+        let runs = layout::runs_for(&input.rules, input.history.is_some(), input.output_mode);
+        let max = Layout { n: 0, runs }.max_records();
+        return Err(RecordError::NoRoom { records: input.records.len(), max });
+    }
+    Ok(input)
+}
+
+fn read_price_history(mut value: &[u8]) -> Result<Vec<Vec<u64>>, RecordError> {
+    let mut history = Vec::new();
+    while !value.is_empty() {
+        if value.len() < 2 {
+            return Err(RecordError::BadField(TAG_PRICE_HISTORY));
+        }
+        let count = codec::read_u16_le(value) as usize;
+        let body = &value[2..];
+        if body.len() < count * 8 {
+            return Err(RecordError::BadField(TAG_PRICE_HISTORY));
+        }
+        history.push(
+            body.chunks_exact(8)
+                .take(count)
+                .map(codec::read_u64_le)
+                .collect(),
+        );
+        value = &body[count * 8..];
+    }
+    Ok(history)
+}
+
+fn read_secrets(mut value: &[u8]) -> Result<Vec<Vec<u8>>, RecordError> {
+    let mut secrets = Vec::new();
+    while !value.is_empty() {
+        if value.len() < 2 {
+            return Err(RecordError::BadField(TAG_SECRETS));
+        }
+        let len = codec::read_u16_le(value) as usize;
+        let (secret, rest) = value[2..].split_at_checked(len).ok_or(RecordError::BadField(TAG_SECRETS))?;
+        secrets.push(secret.to_vec());
+        value = rest;
+    }
+    Ok(secrets)
+}
+
+pub fn read_records(section: &[u8]) -> Result<Vec<Record>, RecordError> {
+    if section.len() < 4 {
+        return Err(RecordError::Truncated { expected: 4, actual: section.len() });
+    }
+    let count = codec::read_u32_le(section) as usize;
+    if count > MAX_RECORDS {
+        return Err(RecordError::TooMany(count));
+    }
+    let body = &section[4..];
+    if body.len() < count * RECORD_LEN {
+        return Err(RecordError::Truncated { expected: count * RECORD_LEN, actual: body.len() });
+    }
+    Ok(body
+        .chunks_exact(RECORD_LEN)
+        .take(count)
+        .map(|r| Record::from_bytes(r.try_into().unwrap()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip() {
+        let records = [
+            Record { price: 4_325_012, change_24h: ReturnBps(-620), timestamp: 1_700_000_000, volume: 5_000_000 },
+            Record { price: 0, change_24h: CHANGE_UNAVAILABLE, timestamp: 0, volume: 0 },
+            Record { price: u64::MAX, change_24h: ReturnBps(i64::MAX), timestamp: u64::MAX, volume: u64::MAX },
+        ];
+        for record in records {
+            assert_eq!(Record::from_bytes(&record.to_bytes()), record);
+        }
+        // The layout at the top of this file, byte for byte
+        let bytes = records[0].to_bytes();
+        assert_eq!(bytes[0..8], 4_325_012u64.to_le_bytes());
+        assert_eq!(bytes[8..16], (-620i64).to_le_bytes());
+        assert_eq!(bytes[16..24], 1_700_000_000u64.to_le_bytes());
+        assert_eq!(bytes[24..32], 5_000_000u64.to_le_bytes());
+
+        // As many of them as a section takes
+        let most: Vec<Record> = records.iter().copied().cycle().take(MAX_RECORDS).collect();
+        let mut section = Vec::new();
+        write_records(&mut section, &most).unwrap();
+        assert_eq!(section.len(), 4 + MAX_RECORDS * RECORD_LEN);
+        assert_eq!(read_records(&section).unwrap(), most);
+        assert_eq!(
+            read_records(&section[..section.len() - 1]),
+            Err(RecordError::Truncated { expected: MAX_RECORDS * RECORD_LEN, actual: MAX_RECORDS * RECORD_LEN - 1 })
+        );
+    }
+
+    #[test]
+    fn source_digest_separates_bodies() {
+        use sha2::{Digest, Sha256};
+
+        let digest = |bodies: &[&[u8]]| source_digest(bodies.iter().copied());
+        assert_ne!(digest(&[b"ab", b"c"]), digest(&[b"a", b"bc"]));
+        assert_ne!(digest(&[b"abc"]), digest(&[b"abc", b""]));
+        // One body: SHA-256 of its length, then the body
+        let mut framed = 3u64.to_le_bytes().to_vec();
+        framed.extend_from_slice(b"abc");
+        assert_eq!(digest(&[b"abc"]), <[u8; 32]>::from(Sha256::digest(framed)));
+    }
+
+    #[test]
+    fn risk_bands_at_each_boundary() {
+        let bands = RiskBands::new(&[200, 500, 1000]).unwrap();
+        assert_eq!(bands.count(), 4);
+        for (metric, band) in [(0, 1), (199, 1), (200, 2), (499, 2), (500, 3), (999, 3), (1000, 4), (u64::MAX, 4)] {
+            assert_eq!(bands.band(metric), band, "metric {}", metric);
+        }
+        // One threshold: two bands
+        let two = RiskBands::new(&[1000]).unwrap();
+        assert_eq!((two.band(999), two.band(1000)), (1, 2));
+
+        let most: Vec<u64> = (1..MAX_RISK_BANDS as u64).collect();
+        assert_eq!(RiskBands::new(&most).unwrap().count(), MAX_RISK_BANDS as u32);
+        assert_eq!(RiskBands::new(&(0..MAX_RISK_BANDS as u64).collect::<Vec<_>>()), None);
+        assert_eq!(RiskBands::new(&[]), None);
+        assert_eq!(RiskBands::new(&[500, 200]), None);
+        assert_eq!(RiskBands::new(&[500, 500]), None);
+    }
+
+    #[test]
+    fn risk_bands_round_trip() {
+        let bands = RiskBands::new(&[200, 500, 1000]).unwrap();
+        let mut payload = Vec::new();
+        write_records(&mut payload, &[]).unwrap();
+        write_risk_bands(&mut payload, &bands).unwrap();
+        assert_eq!(read_market_input(&payload).unwrap().rules.risk_bands, Some(bands));
+
+        // Descending thresholds are refused
+        let mut payload = Vec::new();
+        write_records(&mut payload, &[]).unwrap();
+        write_field(&mut payload, TAG_RISK_BANDS, &[&500u64.to_le_bytes()[..], &200u64.to_le_bytes()].concat())
+            .unwrap();
+        assert_eq!(read_market_input(&payload), Err(RecordError::BadField(TAG_RISK_BANDS)));
+    }
+
+    #[test]
+    fn thresholds_must_be_ordered() {
+        let field = |buy: i64, sell: i64| {
+            let mut payload = Vec::new();
+            write_records(&mut payload, &[]).unwrap();
+            write_field(&mut payload, TAG_THRESHOLDS, &[buy.to_le_bytes(), sell.to_le_bytes()].concat()).unwrap();
+            read_market_input(&payload).map(|input| input.rules.thresholds)
+        };
+        assert_eq!(field(-500, 300), Ok(DEFAULT_THRESHOLDS));
+        assert_eq!(field(100, 100), Ok(Thresholds { buy: ReturnBps(100), sell: ReturnBps(100) }));
+        assert_eq!(field(301, 300), Err(RecordError::BadField(TAG_THRESHOLDS)));
+
+        let crossed = Thresholds { buy: ReturnBps(300), sell: ReturnBps(-500) };
+        assert_eq!(write_thresholds(&mut Vec::new(), &crossed), Err(RecordError::BadField(TAG_THRESHOLDS)));
+    }
+
+    #[test]
+    fn repeated_fields_are_refused() {
+        let mut payload = Vec::new();
+        write_records(&mut payload, &[]).unwrap();
+        write_thresholds(&mut payload, &DEFAULT_THRESHOLDS).unwrap();
+        write_current_time(&mut payload, 1_700_000_000).unwrap();
+        assert!(read_market_input(&payload).is_ok());
+        // A second copy is refused rather than replacing the first
+        write_thresholds(&mut payload, &Thresholds { buy: ReturnBps(-900), sell: ReturnBps(900) }).unwrap();
+        assert_eq!(read_market_input(&payload), Err(RecordError::BadField(TAG_THRESHOLDS)));
+    }
+}
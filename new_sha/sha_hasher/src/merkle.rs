@@ -0,0 +1,163 @@
+// SHA-256 Merkle tree over the asset records, so one record can later be
+// disclosed with an inclusion path instead of the whole input.
+//
+// Leaves, in order: leaf(r.to_bytes()) for each record, then
+// digest_leaf(source digest), with 32 zero bytes when the input has none,
+// then nonce_leaf(nonce) when the input has a nonce, so one root commits to
+// the records and where they came from.
+// With per-record secrets (TAG_SECRETS) a record's leaf is
+// leaf(r.to_bytes() || secret) instead: the record is only 32 bytes, so
+// the split is unambiguous, and without its secret a leaf can't be matched
+// against guessed records.
+//
+//   leaf(data)          = SHA-256(0x00 || data)
+//   node(left, right)   = SHA-256(0x01 || left || right)
+//   digest_leaf(digest) = SHA-256(0x02 || digest)
+//   nonce_leaf(nonce)   = SHA-256(0x03 || nonce as u64 LE, as in input.bin)
+//
+// The prefixes keep a leaf from being passed off as an inner node, and a
+// record from being passed off as the source digest or the nonce. A level
+// with an odd number of hashes promotes its last hash to the next level
+// unchanged, so no two trees share a root by repeating a leaf. A single
+// leaf is its own root.
+
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec;
+use crate::market::Record;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const DIGEST_PREFIX: u8 = 0x02;
+const NONCE_PREFIX: u8 = 0x03;
+
+fn tagged(prefix: u8, data: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update([prefix]).chain_update(data).finalize().into()
+}
+
+pub fn leaf(data: &[u8]) -> [u8; 32] {
+    tagged(LEAF_PREFIX, data)
+}
+
+pub fn digest_leaf(digest: &[u8; 32]) -> [u8; 32] {
+    tagged(DIGEST_PREFIX, digest)
+}
+
+pub fn nonce_leaf(nonce: u64) -> [u8; 32] {
+    let mut data = Vec::with_capacity(8);
+    codec::write_u64_le(&mut data, nonce);
+    tagged(NONCE_PREFIX, &data)
+}
+
+pub fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+// Root over `leaves` (already hashed with `leaf`); all zeros when empty.
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| if let [left, right] = pair { node(left, right) } else { pair[0] })
+            .collect();
+    }
+    level[0]
+}
+
+// Record `r`'s leaf, paired with its secret if the input has them.
+pub fn record_leaf(r: &Record, secret: Option<&[u8]>) -> [u8; 32] {
+    let mut data = r.to_bytes().to_vec();
+    data.extend_from_slice(secret.unwrap_or_default());
+    leaf(&data)
+}
+
+// The root the guest publishes at output_layout::SLOT_MERKLE_ROOT.
+// `secrets`, when given, has one entry per record.
+pub fn records_root(
+    records: &[Record],
+    source_digest: Option<&[u8; 32]>,
+    secrets: Option<&[Vec<u8>]>,
+    nonce: Option<u64>,
+) -> [u8; 32] {
+    let mut leaves: Vec<[u8; 32]> = records
+        .iter()
+        .enumerate()
+        .map(|(i, r)| record_leaf(r, secrets.and_then(|s| s.get(i)).map(Vec::as_slice)))
+        .collect();
+    leaves.push(digest_leaf(source_digest.unwrap_or(&[0; 32])));
+    leaves.extend(nonce.map(nonce_leaf));
+    root(&leaves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::ReturnBps;
+
+    #[test]
+    fn odd_levels_promote_the_last_hash() {
+        let [a, b, c, d, e] = [leaf(b"a"), leaf(b"b"), leaf(b"c"), leaf(b"d"), leaf(b"e")];
+        assert_eq!(root(&[a]), a);
+        assert_eq!(root(&[a, b]), node(&a, &b));
+        assert_eq!(root(&[a, b, c]), node(&node(&a, &b), &c));
+        assert_eq!(root(&[a, b, c, d, e]), node(&node(&node(&a, &b), &node(&c, &d)), &e));
+        assert_ne!(root(&[a, b]), root(&[b, a]));
+        // Repeating the odd leaf is a different tree
+        assert_ne!(root(&[a, b, c]), root(&[a, b, c, c]));
+    }
+
+    #[test]
+    fn each_kind_of_leaf_has_its_own_tag() {
+        let bytes = [7u8; 32];
+        assert_ne!(leaf(&bytes), digest_leaf(&bytes));
+        assert_eq!(leaf(&bytes), <[u8; 32]>::from(Sha256::digest([&[0x00][..], &bytes].concat())));
+        assert_eq!(digest_leaf(&bytes), <[u8; 32]>::from(Sha256::digest([&[0x02][..], &bytes].concat())));
+        let nonce = 42u64.to_le_bytes();
+        assert_eq!(nonce_leaf(42), <[u8; 32]>::from(Sha256::digest([&[0x03][..], &nonce].concat())));
+        assert_ne!(nonce_leaf(42), leaf(&nonce));
+    }
+
+    #[test]
+    fn secrets_blind_their_record_leaf() {
+        let r = |price| Record { price, change_24h: ReturnBps(0), timestamp: 0, volume: 0 };
+        let records = [r(100), r(200)];
+        assert_eq!(record_leaf(&records[0], None), leaf(&records[0].to_bytes()));
+        assert_eq!(record_leaf(&records[0], Some(b"")), record_leaf(&records[0], None));
+
+        let plain = records_root(&records, None, None, None);
+        let secrets = vec![b"nonce-a".to_vec(), b"nonce-b".to_vec()];
+        let blinded = records_root(&records, None, Some(&secrets), None);
+        assert_ne!(blinded, plain);
+        // Each secret only affects its own record's leaf
+        let swapped = vec![secrets[1].clone(), secrets[0].clone()];
+        assert_ne!(records_root(&records, None, Some(&swapped), None), blinded);
+        let leaves = [
+            record_leaf(&records[0], Some(b"nonce-a")),
+            record_leaf(&records[1], Some(b"nonce-b")),
+            digest_leaf(&[0; 32]),
+        ];
+        assert_eq!(blinded, root(&leaves));
+    }
+
+    #[test]
+    fn nonce_adds_a_leaf() {
+        let records = [Record { price: 100, change_24h: ReturnBps(0), timestamp: 0, volume: 0 }];
+        let digest = [7u8; 32];
+        let without = records_root(&records, Some(&digest), None, None);
+        assert_eq!(without, root(&[record_leaf(&records[0], None), digest_leaf(&digest)]));
+        let with = records_root(&records, Some(&digest), None, Some(42));
+        assert_eq!(with, root(&[record_leaf(&records[0], None), digest_leaf(&digest), nonce_leaf(42)]));
+        assert_ne!(with, records_root(&records, Some(&digest), None, Some(43)));
+    }
+}
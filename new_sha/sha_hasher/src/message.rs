@@ -0,0 +1,98 @@
+// Canonical bytes of one asset's decision, for an external signer to sign
+// and a smart contract to check. Every field is read from the guest's output
+// slots, so anyone holding the proof rebuilds exactly these bytes, and the
+// guest publishes messages_digest() over every asset's message in
+// output_layout::SLOT_MESSAGES_DIGEST, so the hashes are proven too.
+//
+// Layout (MESSAGE_LEN bytes), big-endian like Solidity's abi.encodePacked:
+//   [0..8]   timestamp, Unix seconds (u64)
+//   [8]      Signal code
+//   [9]      RiskLevel code
+//   [10..18] price in the published price decimals (u64)
+//   [18..50] commitment: the Merkle root the guest published (merkle.rs)
+//
+// The layout is fixed: a new field means a new message type, not a longer
+// one. hash() is SHA-256, which contracts get from the sha256 precompile.
+// messages_digest() is SHA-256 over the hashes of every record's message,
+// in record order, so a contract given all of them checks any one.
+
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec;
+use crate::market;
+use crate::strategy::{Decision, RiskLevel, Signal};
+
+pub const MESSAGE_LEN: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecisionMessage {
+    pub timestamp: u64,
+    pub signal: Signal,
+    pub risk: RiskLevel,
+    pub price: u64,
+    pub commitment: [u8; 32],
+}
+
+impl DecisionMessage {
+    // The message for a decision as the guest publishes it.
+    pub fn from_decision(decision: &Decision, commitment: [u8; 32]) -> DecisionMessage {
+        DecisionMessage {
+            timestamp: decision.timestamp.into(),
+            signal: decision.signal,
+            risk: decision.risk,
+            price: market::join_u64(decision.price_lo, decision.price_hi),
+            commitment,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; MESSAGE_LEN] {
+        let mut out = Vec::with_capacity(MESSAGE_LEN);
+        codec::write_u64_be(&mut out, self.timestamp);
+        out.push(self.signal as u8);
+        out.push(self.risk as u8);
+        codec::write_u64_be(&mut out, self.price);
+        out.extend_from_slice(&self.commitment);
+        out.try_into().unwrap()
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+}
+
+pub fn messages_digest(messages: &[DecisionMessage]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for message in messages {
+        hasher.update(message.hash());
+    }
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_order_and_widths_are_fixed() {
+        let message = DecisionMessage {
+            timestamp: 1_700_000_000,
+            signal: Signal::Buy,
+            risk: RiskLevel::High,
+            price: 6_585_000,
+            commitment: [0xab; 32],
+        };
+        let bytes = message.to_bytes();
+        assert_eq!(bytes[0..8], 1_700_000_000u64.to_be_bytes());
+        assert_eq!(bytes[8..10], [1, 3]);
+        assert_eq!(bytes[10..18], 6_585_000u64.to_be_bytes());
+        assert_eq!(bytes[18..], [0xab; 32]);
+        assert_eq!(message.hash(), <[u8; 32]>::from(Sha256::digest(bytes)));
+
+        let other = DecisionMessage { signal: Signal::Hold, ..message };
+        let hashes = [message.hash(), other.hash()].concat();
+        assert_eq!(messages_digest(&[message, other]), <[u8; 32]>::from(Sha256::digest(hashes)));
+        assert_ne!(messages_digest(&[message, other]), messages_digest(&[other, message]));
+    }
+}
@@ -0,0 +1,491 @@
+// Where the guest publishes each value in ZisK's output slots. The guest
+// (main.rs), Decision::outputs and the host tools (verify, postprocess) all
+// index the outputs through these names, so a new output is added here and
+// nowhere else.
+//
+// Fixed slots:
+//   SLOT_COUNT          record count N, or an error code (at least
+//                       ERROR_CODE_BASE) when the input was rejected
+//   SLOT_INPUT_DIGEST   SHA-256 of the raw input, INPUT_DIGEST_SLOTS words
+//   SLOT_CURRENCY       currency code (market::currency_code) and the
+//                       OutputMode (SLOT_OUTPUT_MODE)
+//   SLOT_PORTFOLIO      PortfolioSignal, the tie flag (SLOT_PORTFOLIO_TIE)
+//                       and the two vote totals (SLOT_PORTFOLIO_BULLISH,
+//                       SLOT_PORTFOLIO_BEARISH)
+//   SLOT_PRICE_DECIMALS decimal places of every published price,
+//                       strategy::DECISION_VERSION (SLOT_DECISION_VERSION)
+//                       and the market::DataSource (SLOT_DATA_SOURCE)
+//   SLOT_MERKLE_ROOT    Merkle root (merkle.rs), MERKLE_ROOT_SLOTS words
+//   SLOT_RUNS           which runs follow: bit `run` set for each RUN_*
+//                       the input publishes (Layout::runs)
+//   SLOT_NONCE_*        the input's nonce as a u64, market::NO_NONCE
+//                       without one
+//   SLOT_MESSAGES_DIGEST message::messages_digest over every record's
+//                       DecisionMessage, MESSAGES_DIGEST_SLOTS words
+//
+// Then one run of N slots per published RUN_* value, in RUN_* order, from
+// FIRST_RECORD_SLOT on: value `run` of record i is at
+// Layout::record_slot(run, i). Every input publishes CORE_RUNS; the others
+// only when the input's rules use them (runs_for), so a feature that is
+// off costs no slots. With just the core runs MAX_RECORDS records fit, and
+// each run an input adds leaves room for fewer (Layout::max_records).
+//
+// Some slots and runs pack several values. Each packed value has a name
+// for its host (SLOT_OUTPUT_MODE is SLOT_CURRENCY, RUN_WEIGHT is
+// RUN_SIGNAL, ...) and a *_BITS range for its bits within the word, and no
+// two values in one word share a bit. RUN_HOLD_REASON packs the
+// HoldReason, the confidence (RUN_CONFIDENCE) and, for a MissingData HOLD,
+// how many closes the record had (reason_confidence_closes); RUN_RISK
+// packs the RiskLevel and the risk band (risk_and_band); RUN_SIGNAL packs
+// the Signal and the record's portfolio weight (RUN_WEIGHT,
+// signal_and_weight).
+//
+// RUN_AGE is the record's age in seconds (market::age_secs), published
+// when the input has a current time. An age past u32::MAX (a current time
+// past 2106) overflows like a timestamp past 2^32.
+//
+// RUN_SMOOTHED_PRICE holds the smoothed price of whichever basis the input
+// picked (PriceBasis::Ema or Wma).
+//
+// With OutputMode::WithInputs the guest also republishes what each decision
+// was made from, so a consumer needs only the slots: the price, timestamp
+// and volume already have runs, and INPUT_RUNS more runs after the
+// Decision's carry the 24h change (RUN_CHANGE_*, the ReturnBps as a u64 in
+// two's complement).
+
+use core::ops::Range;
+
+use crate::market::{OutputMode, PriceBasis, Rules, SignalMode};
+
+pub const OUTPUT_SLOTS: usize = 64;
+
+pub const SLOT_COUNT: usize = 0;
+pub const SLOT_INPUT_DIGEST: usize = SLOT_COUNT + 1;
+pub const INPUT_DIGEST_SLOTS: usize = 8;
+pub const SLOT_CURRENCY: usize = SLOT_INPUT_DIGEST + INPUT_DIGEST_SLOTS;
+pub const SLOT_OUTPUT_MODE: usize = SLOT_CURRENCY;
+pub const SLOT_PORTFOLIO: usize = SLOT_CURRENCY + 1;
+pub const SLOT_PORTFOLIO_TIE: usize = SLOT_PORTFOLIO;
+pub const SLOT_PORTFOLIO_BULLISH: usize = SLOT_PORTFOLIO;
+pub const SLOT_PORTFOLIO_BEARISH: usize = SLOT_PORTFOLIO;
+pub const SLOT_PRICE_DECIMALS: usize = SLOT_PORTFOLIO + 1;
+pub const SLOT_DATA_SOURCE: usize = SLOT_PRICE_DECIMALS;
+pub const SLOT_DECISION_VERSION: usize = SLOT_PRICE_DECIMALS;
+pub const SLOT_MERKLE_ROOT: usize = SLOT_PRICE_DECIMALS + 1;
+pub const MERKLE_ROOT_SLOTS: usize = 8;
+pub const SLOT_RUNS: usize = SLOT_MERKLE_ROOT + MERKLE_ROOT_SLOTS;
+pub const SLOT_NONCE_LO: usize = SLOT_RUNS + 1;
+pub const SLOT_NONCE_HI: usize = SLOT_NONCE_LO + 1;
+pub const SLOT_MESSAGES_DIGEST: usize = SLOT_NONCE_HI + 1;
+pub const MESSAGES_DIGEST_SLOTS: usize = 8;
+pub const FIRST_RECORD_SLOT: usize = SLOT_MESSAGES_DIGEST + MESSAGES_DIGEST_SLOTS;
+
+// The bits of each value packed into a fixed slot.
+pub const CURRENCY_BITS: Range<u32> = 0..24;
+pub const OUTPUT_MODE_BITS: Range<u32> = 24..32;
+pub const PORTFOLIO_BITS: Range<u32> = 0..3;
+pub const PORTFOLIO_TIE_BITS: Range<u32> = 3..4;
+pub const PORTFOLIO_BULLISH_BITS: Range<u32> = 4..18;
+pub const PORTFOLIO_BEARISH_BITS: Range<u32> = 18..32;
+pub const PRICE_DECIMALS_BITS: Range<u32> = 0..8;
+pub const DATA_SOURCE_BITS: Range<u32> = 8..16;
+pub const DECISION_VERSION_BITS: Range<u32> = 16..32;
+
+// SLOT_COUNT holds one of these instead of a count when the guest rejected
+// its input (guest::ERR_TOO_SHORT, crc32::ERR_BAD_CRC, header::HeaderError,
+// market::ERR_BAD_RECORDS).
+pub const ERROR_CODE_BASE: u32 = 0xE000_0000;
+
+// Per-record runs. Values wider than 32 bits span a _LO and a _HI run.
+pub const RUN_SIGNAL: usize = 0;
+pub const RUN_WEIGHT: usize = RUN_SIGNAL;
+pub const RUN_RISK: usize = 1;
+pub const RUN_SMOOTHED_PRICE: usize = 2;
+pub const RUN_TIMESTAMP: usize = 3;
+pub const RUN_RSI: usize = 4;
+pub const RUN_PRICE_LO: usize = 5;
+pub const RUN_PRICE_HI: usize = 6;
+pub const RUN_SMA_SHORT: usize = 7;
+pub const RUN_SMA_LONG: usize = 8;
+pub const RUN_HOLD_REASON: usize = 9;
+pub const RUN_CONFIDENCE: usize = RUN_HOLD_REASON;
+pub const RUN_VOLUME_LO: usize = 10;
+pub const RUN_VOLUME_HI: usize = 11;
+pub const RUN_BAND_WIDTH: usize = 12;
+pub const RUN_SQUEEZE: usize = 13;
+pub const RUN_MACD_HISTOGRAM: usize = 14;
+pub const RUN_VOLATILITY: usize = 15;
+pub const RUN_POSITION_LO: usize = 16;
+pub const RUN_POSITION_HI: usize = 17;
+pub const RUN_STOP_LO: usize = 18;
+pub const RUN_STOP_HI: usize = 19;
+pub const RUN_TARGET_LO: usize = 20;
+pub const RUN_TARGET_HI: usize = 21;
+pub const RUN_AGE: usize = 22;
+pub const VALUES_PER_RECORD: usize = RUN_AGE + 1;
+
+// The bits of each value packed into a record's slot of a run.
+pub const SIGNAL_BITS: Range<u32> = 0..8;
+pub const WEIGHT_BITS: Range<u32> = 8..32;
+pub const RISK_BITS: Range<u32> = 0..4;
+pub const RISK_BAND_BITS: Range<u32> = 4..8;
+pub const HOLD_REASON_BITS: Range<u32> = 0..8;
+pub const CONFIDENCE_BITS: Range<u32> = 8..16;
+pub const CLOSES_BITS: Range<u32> = 16..32;
+
+// Runs published only with OutputMode::WithInputs.
+pub const RUN_CHANGE_LO: usize = VALUES_PER_RECORD;
+pub const RUN_CHANGE_HI: usize = RUN_CHANGE_LO + 1;
+pub const INPUT_RUNS: usize = 2;
+
+// The runs every input publishes, whatever its rules.
+pub const CORE_RUNS: u32 = run_bits(&[
+    RUN_SIGNAL, RUN_RISK, RUN_TIMESTAMP, RUN_PRICE_LO, RUN_PRICE_HI, RUN_HOLD_REASON, RUN_VOLUME_LO, RUN_VOLUME_HI,
+]);
+pub const ALL_RUNS: u32 = (1 << (VALUES_PER_RECORD + INPUT_RUNS)) - 1;
+
+// Most records any input fits: the count with just the core runs.
+pub const MAX_RECORDS: usize = (OUTPUT_SLOTS - FIRST_RECORD_SLOT) / CORE_RUNS.count_ones() as usize;
+
+const fn run_bits(runs: &[usize]) -> u32 {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < runs.len() {
+        bits |= 1 << runs[i];
+        i += 1;
+    }
+    bits
+}
+
+// The runs published for records decided under `rules`: the core runs,
+// plus each one whose value the rules can produce. The indicator runs
+// follow the signal mode, since only its own indicator decides: RSI in Rsi
+// mode, the SMAs in MaCrossover, the band width and squeeze in Bollinger
+// and the MACD histogram in Macd, each only with a price history. The
+// volatility comes with any history (it sets the risk), the smoothed price
+// with a PriceBasis other than Raw, the position size with Rules::position,
+// the exit levels with Rules::levels, the age with a current time
+// (Rules::now) and the 24h change with OutputMode::WithInputs.
+pub fn runs_for(rules: &Rules, has_history: bool, mode: OutputMode) -> u32 {
+    let in_mode = |signal_mode| has_history && rules.mode == signal_mode;
+    let optional = [
+        (RUN_SMOOTHED_PRICE, rules.price_basis != PriceBasis::Raw),
+        (RUN_RSI, in_mode(SignalMode::Rsi)),
+        (RUN_SMA_SHORT, in_mode(SignalMode::MaCrossover)),
+        (RUN_SMA_LONG, in_mode(SignalMode::MaCrossover)),
+        (RUN_BAND_WIDTH, in_mode(SignalMode::Bollinger)),
+        (RUN_SQUEEZE, in_mode(SignalMode::Bollinger)),
+        (RUN_MACD_HISTOGRAM, in_mode(SignalMode::Macd)),
+        (RUN_VOLATILITY, has_history),
+        (RUN_POSITION_LO, rules.position.is_some()),
+        (RUN_POSITION_HI, rules.position.is_some()),
+        (RUN_STOP_LO, rules.levels.is_some()),
+        (RUN_STOP_HI, rules.levels.is_some()),
+        (RUN_TARGET_LO, rules.levels.is_some()),
+        (RUN_TARGET_HI, rules.levels.is_some()),
+        (RUN_AGE, rules.now().is_some()),
+        (RUN_CHANGE_LO, mode == OutputMode::WithInputs),
+        (RUN_CHANGE_HI, mode == OutputMode::WithInputs),
+    ];
+    optional.iter().filter(|&&(_, on)| on).fold(CORE_RUNS, |runs, &(run, _)| runs | 1 << run)
+}
+
+// Where N records' values go, given the runs published for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub n: usize,
+    pub runs: u32,
+}
+
+impl Layout {
+    // The layout of published outputs, from SLOT_COUNT and SLOT_RUNS. None
+    // unless `runs` has every core run, only known ones, and room for `n`.
+    pub fn new(n: usize, runs: u32) -> Option<Layout> {
+        let layout = Layout { n, runs };
+        (runs & CORE_RUNS == CORE_RUNS && runs & !ALL_RUNS == 0 && layout.fits()).then_some(layout)
+    }
+
+    pub fn has(&self, run: usize) -> bool {
+        self.runs >> run & 1 == 1
+    }
+
+    // The slot of value `run` for record `i`, or None when the run isn't
+    // published.
+    pub fn record_slot(&self, run: usize, i: usize) -> Option<usize> {
+        let before = (self.runs & ((1 << run) - 1)).count_ones() as usize;
+        self.has(run).then_some(FIRST_RECORD_SLOT + before * self.n + i)
+    }
+
+    // How many records these runs leave room for.
+    pub fn max_records(&self) -> usize {
+        (OUTPUT_SLOTS - FIRST_RECORD_SLOT) / self.runs.count_ones().max(1) as usize
+    }
+
+    pub fn fits(&self) -> bool {
+        self.n <= self.max_records()
+    }
+}
+
+// `value` placed at `bits`; it must fit them.
+const fn pack(value: u32, bits: Range<u32>) -> u32 {
+    value << bits.start
+}
+
+// The value packed at `bits` of `word`.
+const fn unpack(word: u32, bits: Range<u32>) -> u32 {
+    ((word as u64 >> bits.start) & ((1 << (bits.end - bits.start)) - 1)) as u32
+}
+
+// SLOT_CURRENCY's value: the currency code and the OutputMode, so a
+// Standard output leaves just the code.
+pub fn currency_and_mode(code: u32, mode: u32) -> u32 {
+    pack(mode, OUTPUT_MODE_BITS) | pack(code, CURRENCY_BITS)
+}
+
+pub fn split_currency_mode(slot: u32) -> (u32, u32) {
+    (unpack(slot, CURRENCY_BITS), unpack(slot, OUTPUT_MODE_BITS))
+}
+
+// SLOT_PRICE_DECIMALS's value: the decision version, the DataSource and
+// the decimals (at most market::MAX_PRICE_DECIMALS), so an Unknown source
+// leaves just the version and decimals.
+pub fn version_source_decimals(version: u16, source: u32, decimals: u32) -> u32 {
+    pack(version as u32, DECISION_VERSION_BITS) | pack(source, DATA_SOURCE_BITS) | pack(decimals, PRICE_DECIMALS_BITS)
+}
+
+pub fn split_version_source_decimals(slot: u32) -> (u16, u32, u32) {
+    let version = unpack(slot, DECISION_VERSION_BITS) as u16;
+    (version, unpack(slot, DATA_SOURCE_BITS), unpack(slot, PRICE_DECIMALS_BITS))
+}
+
+// Largest bullish or bearish total the portfolio slot publishes. MAX_WEIGHT
+// keeps every total within it.
+pub const MAX_VOTE_TOTAL: u64 = 0x3FFF;
+
+// SLOT_PORTFOLIO's value: the PortfolioSignal, the tie bit set when the
+// vote was a tie (strategy::is_tie), and the bullish and bearish totals
+// the vote compared (strategy::vote_totals).
+pub fn portfolio_vote(signal: u32, tie: bool, (bullish, bearish): (u64, u64)) -> u32 {
+    let total = |t: u64| t.min(MAX_VOTE_TOTAL) as u32;
+    pack(total(bearish), PORTFOLIO_BEARISH_BITS)
+        | pack(total(bullish), PORTFOLIO_BULLISH_BITS)
+        | pack(tie as u32, PORTFOLIO_TIE_BITS)
+        | pack(signal, PORTFOLIO_BITS)
+}
+
+pub fn split_portfolio_vote(slot: u32) -> (u32, bool, (u64, u64)) {
+    let totals = (unpack(slot, PORTFOLIO_BULLISH_BITS).into(), unpack(slot, PORTFOLIO_BEARISH_BITS).into());
+    (unpack(slot, PORTFOLIO_BITS), unpack(slot, PORTFOLIO_TIE_BITS) == 1, totals)
+}
+
+// RUN_HOLD_REASON's value: the reason, the confidence and the number of
+// closes available, saturating at 0xFFFF. Only a HOLD's reason means
+// anything (any other signal publishes 0 there), a HOLD's confidence is 0,
+// and only HoldReason::MissingData sets the closes.
+pub fn reason_confidence_closes(reason: u32, confidence: u32, closes: usize) -> u32 {
+    pack(closes.min(0xFFFF) as u32, CLOSES_BITS) | pack(confidence, CONFIDENCE_BITS) | pack(reason, HOLD_REASON_BITS)
+}
+
+pub fn split_reason_confidence_closes(slot: u32) -> (u32, u32, u32) {
+    (unpack(slot, HOLD_REASON_BITS), unpack(slot, CONFIDENCE_BITS), unpack(slot, CLOSES_BITS))
+}
+
+// Most risk bands an input may define (market::RiskBands), so that every
+// band index fits in RISK_BAND_BITS.
+pub const MAX_RISK_BANDS: usize = 15;
+
+// RUN_RISK's value: the RiskLevel and the record's risk band
+// (1..=MAX_RISK_BANDS), or 0 when the input has no risk bands, which leaves
+// just the risk level.
+pub fn risk_and_band(risk: u32, band: Option<u32>) -> u32 {
+    pack(band.map_or(0, |b| b.min(MAX_RISK_BANDS as u32)), RISK_BAND_BITS) | pack(risk, RISK_BITS)
+}
+
+pub fn split_risk_band(risk: u32) -> (u32, Option<u32>) {
+    (unpack(risk, RISK_BITS), Some(unpack(risk, RISK_BAND_BITS)).filter(|&b| b != 0))
+}
+
+// Largest portfolio weight an input may give a record (market::TAG_WEIGHTS),
+// so that MAX_RECORDS of them still total at most MAX_VOTE_TOTAL.
+pub const MAX_WEIGHT: u32 = (MAX_VOTE_TOTAL / MAX_RECORDS as u64) as u32;
+
+// RUN_SIGNAL's value: the Signal and the record's weight plus one, or 0
+// when the input has no weights, which leaves just the signal.
+pub fn signal_and_weight(signal: u32, weight: Option<u32>) -> u32 {
+    let weight = weight.map_or(0, |w| w.min(MAX_WEIGHT) + 1);
+    pack(weight, WEIGHT_BITS) | pack(signal, SIGNAL_BITS)
+}
+
+pub fn split_signal_weight(slot: u32) -> (u32, Option<u32>) {
+    (unpack(slot, SIGNAL_BITS), unpack(slot, WEIGHT_BITS).checked_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_do_not_overlap() {
+        // (start, len) of every fixed range and the record area, in slot order
+        let ranges = [
+            (SLOT_COUNT, 1),
+            (SLOT_INPUT_DIGEST, INPUT_DIGEST_SLOTS),
+            (SLOT_CURRENCY, 1),
+            (SLOT_PORTFOLIO, 1),
+            (SLOT_PRICE_DECIMALS, 1),
+            (SLOT_MERKLE_ROOT, MERKLE_ROOT_SLOTS),
+            (SLOT_RUNS, 1),
+            (SLOT_NONCE_LO, 1),
+            (SLOT_NONCE_HI, 1),
+            (SLOT_MESSAGES_DIGEST, MESSAGES_DIGEST_SLOTS),
+            (FIRST_RECORD_SLOT, CORE_RUNS.count_ones() as usize * MAX_RECORDS),
+        ];
+        for pair in ranges.windows(2) {
+            assert!(pair[0].0 + pair[0].1 <= pair[1].0, "{:?} overlaps {:?}", pair[0], pair[1]);
+        }
+        let (last, len) = ranges[ranges.len() - 1];
+        assert!(last + len <= OUTPUT_SLOTS);
+
+        // Values packed into one word keep to their own bits; the fixed
+        // slots' aliases are checked here instead of in the list above
+        let packed = [
+            (SLOT_CURRENCY, CURRENCY_BITS),
+            (SLOT_OUTPUT_MODE, OUTPUT_MODE_BITS),
+            (SLOT_PORTFOLIO, PORTFOLIO_BITS),
+            (SLOT_PORTFOLIO_TIE, PORTFOLIO_TIE_BITS),
+            (SLOT_PORTFOLIO_BULLISH, PORTFOLIO_BULLISH_BITS),
+            (SLOT_PORTFOLIO_BEARISH, PORTFOLIO_BEARISH_BITS),
+            (SLOT_PRICE_DECIMALS, PRICE_DECIMALS_BITS),
+            (SLOT_DATA_SOURCE, DATA_SOURCE_BITS),
+            (SLOT_DECISION_VERSION, DECISION_VERSION_BITS),
+            (FIRST_RECORD_SLOT + RUN_SIGNAL, SIGNAL_BITS),
+            (FIRST_RECORD_SLOT + RUN_WEIGHT, WEIGHT_BITS),
+            (FIRST_RECORD_SLOT + RUN_RISK, RISK_BITS),
+            (FIRST_RECORD_SLOT + RUN_RISK, RISK_BAND_BITS),
+            (FIRST_RECORD_SLOT + RUN_HOLD_REASON, HOLD_REASON_BITS),
+            (FIRST_RECORD_SLOT + RUN_CONFIDENCE, CONFIDENCE_BITS),
+            (FIRST_RECORD_SLOT + RUN_HOLD_REASON, CLOSES_BITS),
+        ];
+        for (i, (slot, bits)) in packed.iter().enumerate() {
+            assert!(bits.start < bits.end && bits.end <= u32::BITS, "{:?}", bits);
+            for (other, other_bits) in &packed[i + 1..] {
+                let disjoint = bits.end <= other_bits.start || other_bits.end <= bits.start;
+                assert!(slot != other || disjoint, "slot {}: {:?} overlaps {:?}", slot, bits, other_bits);
+            }
+        }
+
+        // Every run has its own index
+        let mut runs = [
+            RUN_SIGNAL, RUN_RISK, RUN_SMOOTHED_PRICE, RUN_TIMESTAMP, RUN_RSI, RUN_PRICE_LO, RUN_PRICE_HI,
+            RUN_SMA_SHORT, RUN_SMA_LONG, RUN_HOLD_REASON, RUN_VOLUME_LO, RUN_VOLUME_HI, RUN_BAND_WIDTH,
+            RUN_SQUEEZE, RUN_MACD_HISTOGRAM, RUN_VOLATILITY, RUN_POSITION_LO, RUN_POSITION_HI, RUN_STOP_LO,
+            RUN_STOP_HI, RUN_TARGET_LO, RUN_TARGET_HI, RUN_AGE,
+        ];
+        runs.sort_unstable();
+        assert_eq!(runs, std::array::from_fn::<usize, VALUES_PER_RECORD, _>(|i| i));
+
+        // Every published (run, record) pair lands on its own slot inside the
+        // record area, for as many records as the runs leave room for
+        for runs in [CORE_RUNS, CORE_RUNS | 1 << RUN_RSI | 1 << RUN_VOLATILITY, ALL_RUNS] {
+            let layout = Layout::new(Layout { n: 0, runs }.max_records(), runs).unwrap();
+            let mut slots: Vec<usize> = (0..VALUES_PER_RECORD + INPUT_RUNS)
+                .flat_map(|run| (0..layout.n).filter_map(move |i| layout.record_slot(run, i)))
+                .collect();
+            slots.sort_unstable();
+            slots.dedup();
+            assert_eq!(slots.len(), runs.count_ones() as usize * layout.n);
+            assert!(slots.iter().all(|&s| (FIRST_RECORD_SLOT..OUTPUT_SLOTS).contains(&s)));
+            assert_eq!(Layout::new(layout.n + 1, runs), None);
+        }
+        assert_eq!(Layout { n: 0, runs: CORE_RUNS }.max_records(), MAX_RECORDS);
+    }
+
+    #[test]
+    fn runs_follow_the_rules() {
+        use crate::market::{Levels, Position, DEFAULT_RULES};
+
+        assert_eq!(runs_for(&DEFAULT_RULES, false, OutputMode::Standard), CORE_RUNS);
+        // The indicators need a history, and only the mode's own is published
+        let rsi = Rules { mode: SignalMode::Rsi, ..DEFAULT_RULES };
+        assert_eq!(runs_for(&rsi, false, OutputMode::Standard), CORE_RUNS);
+        assert_eq!(runs_for(&rsi, true, OutputMode::Standard), CORE_RUNS | 1 << RUN_RSI | 1 << RUN_VOLATILITY);
+
+        let everything = Rules {
+            position: Some(Position { balance: 1, risk_fraction: 1 }),
+            levels: Some(Levels { stop_bps: 1, target_bps: 1 }),
+            price_basis: PriceBasis::Ema,
+            current_time: Some(1),
+            ..rsi
+        };
+        let runs = runs_for(&everything, true, OutputMode::WithInputs);
+        let off = [RUN_SMA_SHORT, RUN_SMA_LONG, RUN_BAND_WIDTH, RUN_SQUEEZE, RUN_MACD_HISTOGRAM];
+        assert_eq!(runs, off.iter().fold(ALL_RUNS, |runs, &run| runs & !(1 << run)));
+
+        // Without a core run, or with an unknown one, outputs have no layout
+        assert_eq!(Layout::new(1, CORE_RUNS & !(1 << RUN_SIGNAL)), None);
+        assert_eq!(Layout::new(1, CORE_RUNS | 1 << (VALUES_PER_RECORD + INPUT_RUNS)), None);
+        // A run's slots come after those of every published run before it
+        let layout = Layout::new(2, CORE_RUNS | 1 << RUN_VOLATILITY).unwrap();
+        assert_eq!(layout.record_slot(RUN_RSI, 0), None);
+        assert_eq!(layout.record_slot(RUN_TIMESTAMP, 1), Some(FIRST_RECORD_SLOT + 2 * 2 + 1));
+        assert_eq!(layout.record_slot(RUN_VOLATILITY, 0), Some(FIRST_RECORD_SLOT + 8 * 2));
+    }
+
+    #[test]
+    fn currency_and_mode_round_trip() {
+        let usd = crate::market::currency_code(*b"USD");
+        for mode in [0, 1, 0xFF] {
+            assert_eq!(split_currency_mode(currency_and_mode(usd, mode)), (usd, mode));
+        }
+        assert_eq!(currency_and_mode(usd, 0), usd);
+    }
+
+    #[test]
+    fn portfolio_vote_round_trip() {
+        for (signal, tie, totals) in [(0, false, (0, 0)), (1, false, (3, 1)), (0, true, (MAX_VOTE_TOTAL, MAX_VOTE_TOTAL))] {
+            assert_eq!(split_portfolio_vote(portfolio_vote(signal, tie, totals)), (signal, tie, totals));
+        }
+        // Two BUYs and two SELLs
+        assert_eq!(portfolio_vote(0, true, (2, 2)), 0x0008_0028);
+        assert!(MAX_WEIGHT as u64 * MAX_RECORDS as u64 <= MAX_VOTE_TOTAL);
+    }
+
+    #[test]
+    fn version_source_decimals_round_trip() {
+        for (version, source, decimals) in [(0, 0, 0), (1, 1, 2), (u16::MAX, 0xFF, crate::market::MAX_PRICE_DECIMALS)] {
+            let slot = version_source_decimals(version, source, decimals);
+            assert_eq!(split_version_source_decimals(slot), (version, source, decimals));
+        }
+        assert_eq!(version_source_decimals(1, 0, 2), 0x0001_0002);
+        assert_eq!(version_source_decimals(1, 3, 2), 0x0001_0302);
+    }
+
+    #[test]
+    fn risk_and_band_round_trip() {
+        for (risk, band) in [(1, None), (3, Some(1)), (2, Some(7)), (0, Some(MAX_RISK_BANDS as u32))] {
+            assert_eq!(split_risk_band(risk_and_band(risk, band)), (risk, band));
+        }
+        assert_eq!(risk_and_band(3, None), 3);
+        assert_eq!(risk_and_band(2, Some(4)), 0x42);
+    }
+
+    #[test]
+    fn signal_and_weight_round_trip() {
+        for (signal, weight) in [(0, None), (1, Some(0)), (4, Some(7)), (2, Some(MAX_WEIGHT))] {
+            assert_eq!(split_signal_weight(signal_and_weight(signal, weight)), (signal, weight));
+        }
+        assert_eq!(signal_and_weight(2, None), 2);
+        assert_eq!(signal_and_weight(1, Some(3)), 4 << 8 | 1);
+    }
+
+    #[test]
+    fn reason_confidence_closes_round_trip() {
+        for (reason, confidence, closes) in [(0, 0, 0), (1, 0, 5), (6, 0, 0xFFFF), (0, 100, 0), (0, 0xFF, 0)] {
+            let slot = reason_confidence_closes(reason, confidence, closes);
+            assert_eq!(split_reason_confidence_closes(slot), (reason, confidence, closes as u32));
+        }
+        assert_eq!(reason_confidence_closes(1, 0, 5), 0x0005_0001);
+        assert_eq!(reason_confidence_closes(0, 12, 0), 0x0000_0C00);
+        assert_eq!(split_reason_confidence_closes(reason_confidence_closes(1, 0, 100_000)), (1, 0, 0xFFFF));
+    }
+}
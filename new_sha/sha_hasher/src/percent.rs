@@ -0,0 +1,58 @@
+// ReturnBps to and from a percentage, for the host: reading a CoinGecko
+// change or a threshold written in percent, printing one. Rounding an f64
+// needs std, so this lives outside market.rs and the guest only ever sees
+// basis points.
+
+use crate::fixed::{self, FixedError};
+use crate::market::{ReturnBps, CHANGE_DECIMALS, CHANGE_UNAVAILABLE};
+
+impl ReturnBps {
+    // Rounded to the nearest basis point (CHANGE_DECIMALS places of a
+    // percent), away from zero on a tie, so a negative change keeps its
+    // sign and magnitude. A change that would land on CHANGE_UNAVAILABLE
+    // overflows rather than reading as a missing one.
+    pub fn from_percent(pct: f64) -> Result<ReturnBps, FixedError> {
+        match fixed::to_fixed_signed(pct, CHANGE_DECIMALS).map(ReturnBps)? {
+            CHANGE_UNAVAILABLE => Err(FixedError::Overflow(pct)),
+            change => Ok(change),
+        }
+    }
+
+    pub fn to_percent(self) -> f64 {
+        fixed::from_fixed_signed(self.0, CHANGE_DECIMALS)
+    }
+
+    // A percentage as a person writes it: "5", "5%", "5.0%" or "-2.5 %".
+    // Rounded like from_percent, so "0.125%" is 13 bps.
+    pub fn parse_percent(text: &str) -> Result<ReturnBps, String> {
+        let number = text.trim();
+        let number = number.strip_suffix('%').unwrap_or(number).trim_end();
+        let pct: f64 = number.parse().map_err(|_| format!("{:?} is not a percentage", text))?;
+        ReturnBps::from_percent(pct).map_err(|e| format!("{:?} is out of range: {}", text, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_the_nearest_bps() {
+        assert_eq!(ReturnBps::from_percent(-2.5), Ok(ReturnBps(-250)));
+        assert_eq!(ReturnBps::from_percent(0.125), Ok(ReturnBps(13)));
+        assert_eq!(ReturnBps::from_percent(-0.125), Ok(ReturnBps(-13)));
+        assert_eq!(ReturnBps::from_percent(-0.004), Ok(ReturnBps(0)));
+        assert_eq!(ReturnBps(-250).to_percent(), -2.5);
+    }
+
+    #[test]
+    fn out_of_range_changes_overflow() {
+        // The most negative i64 is CHANGE_UNAVAILABLE, not a change
+        let unavailable = i64::MIN as f64 / 100.0;
+        assert_eq!(ReturnBps::from_percent(unavailable), Err(FixedError::Overflow(unavailable)));
+        let huge = i64::MAX as f64 / 100.0;
+        assert_eq!(ReturnBps::from_percent(huge), Err(FixedError::Overflow(huge)));
+        assert!(matches!(ReturnBps::from_percent(f64::NAN), Err(FixedError::NotFinite(_))));
+        assert!(ReturnBps::parse_percent("1e30%").is_err());
+    }
+}
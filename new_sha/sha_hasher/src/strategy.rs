@@ -0,0 +1,1266 @@
+// The trading decision for one record. The guest and the host-side
+// verifier both call `decide`, so they can't drift apart.
+
+use crate::indicators;
+use crate::indicators::Cross;
+use crate::market::{self, Direction, Levels, Position, PriceBasis, Record, ReturnBps, Rules, SignalMode, Thresholds};
+use crate::output_layout::{self as layout, VALUES_PER_RECORD};
+
+// Bumped whenever `decide`, or anything it relies on, changes what it
+// decides or publishes for the same input, so a verifier can tell which
+// logic a proof ran. Published in output_layout::SLOT_DECISION_VERSION, and
+// pinned with the self-test's goldens (GOLDEN_VERSION in selftest.rs).
+pub const DECISION_VERSION: u16 = 16;
+
+// Published in the signal run; the discriminants are the output values.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Hold = 0,
+    Buy = 1,
+    Sell = 2,
+    // Only with Direction::Short, in place of Sell and Buy respectively
+    OpenShort = 3,
+    CoverShort = 4,
+}
+
+impl Signal {
+    pub fn label(self) -> &'static str {
+        match self {
+            Signal::Hold => "HOLD",
+            Signal::Buy => "BUY",
+            Signal::Sell => "SELL",
+            Signal::OpenShort => "OPEN_SHORT",
+            Signal::CoverShort => "COVER_SHORT",
+        }
+    }
+
+    // The signal a label names, in any case ("buy", "Open_Short").
+    pub fn from_label(label: &str) -> Option<Signal> {
+        [Signal::Hold, Signal::Buy, Signal::Sell, Signal::OpenShort, Signal::CoverShort]
+            .into_iter()
+            .find(|signal| signal.label().eq_ignore_ascii_case(label))
+    }
+
+    // The signal for `direction`: a short trades the same moves the other
+    // way round, opening on a rip (Sell) and covering on a dip (Buy).
+    pub fn for_direction(self, direction: Direction) -> Signal {
+        match (direction, self) {
+            (Direction::Short, Signal::Sell) => Signal::OpenShort,
+            (Direction::Short, Signal::Buy) => Signal::CoverShort,
+            _ => self,
+        }
+    }
+
+    // Buy and CoverShort bet on a rise, Sell and OpenShort on a fall.
+    pub fn is_bullish(self) -> bool {
+        matches!(self, Signal::Buy | Signal::CoverShort)
+    }
+
+    pub fn is_bearish(self) -> bool {
+        matches!(self, Signal::Sell | Signal::OpenShort)
+    }
+}
+
+// Decodes a published signal; the error is the unrecognised value.
+impl TryFrom<u32> for Signal {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(Signal::Hold),
+            1 => Ok(Signal::Buy),
+            2 => Ok(Signal::Sell),
+            3 => Ok(Signal::OpenShort),
+            4 => Ok(Signal::CoverShort),
+            _ => Err(value),
+        }
+    }
+}
+
+// Published in the risk run. Medium only comes from realized volatility.
+//
+// This is one of two risk axes, on purpose. The level has a fixed meaning
+// that sizing (position_size's 1/2/4 divisor) and stops (exit_levels'
+// multiples) are defined against, so the same level always trades the same
+// way. market::RiskBands is a classification only: the input chooses how
+// finely it splits the same metric, and the band never changes a decision.
+// Folding the bands into RiskLevel would make sizing and stops depend on
+// each input's band count.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLevel {
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+impl RiskLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            RiskLevel::Low => "LOW",
+            RiskLevel::Medium => "MEDIUM",
+            RiskLevel::High => "HIGH",
+        }
+    }
+}
+
+impl TryFrom<u32> for RiskLevel {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            1 => Ok(RiskLevel::Low),
+            2 => Ok(RiskLevel::Medium),
+            3 => Ok(RiskLevel::High),
+            _ => Err(value),
+        }
+    }
+}
+
+// The basket-level view published in output_layout::SLOT_PORTFOLIO.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortfolioSignal {
+    Neutral = 0,
+    RiskOn = 1,
+    RiskOff = 2,
+}
+
+impl PortfolioSignal {
+    pub fn label(self) -> &'static str {
+        match self {
+            PortfolioSignal::Neutral => "NEUTRAL",
+            PortfolioSignal::RiskOn => "RISK_ON",
+            PortfolioSignal::RiskOff => "RISK_OFF",
+        }
+    }
+}
+
+impl TryFrom<u32> for PortfolioSignal {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(PortfolioSignal::Neutral),
+            1 => Ok(PortfolioSignal::RiskOn),
+            2 => Ok(PortfolioSignal::RiskOff),
+            _ => Err(value),
+        }
+    }
+}
+
+// Vote over the per-asset signals: RiskOn when the bullish (Buy,
+// CoverShort) total is larger than the bearish (Sell, OpenShort) total,
+// RiskOff for the reverse. Each asset is one vote, or as many as its weight
+// when `weights` is given (the input's TAG_WEIGHTS). A tie is always
+// Neutral, the portfolio's HOLD: the result depends only on the two totals,
+// never on signal order or randomness, so every run over the same signals
+// proves the same vote. HOLDs don't vote.
+pub fn aggregate(signals: &[Signal], weights: Option<&[u32]>) -> PortfolioSignal {
+    let (bullish, bearish) = vote_totals(signals, weights);
+    match bullish.cmp(&bearish) {
+        core::cmp::Ordering::Greater => PortfolioSignal::RiskOn,
+        core::cmp::Ordering::Less => PortfolioSignal::RiskOff,
+        core::cmp::Ordering::Equal => PortfolioSignal::Neutral,
+    }
+}
+
+// Whether `aggregate` broke a tie: equal bullish and bearish totals, above
+// zero. A basket with no votes at all isn't a tie.
+pub fn is_tie(signals: &[Signal], weights: Option<&[u32]>) -> bool {
+    let (bullish, bearish) = vote_totals(signals, weights);
+    bullish == bearish && bullish > 0
+}
+
+// The bullish and bearish totals `aggregate` compares: each side's count,
+// or the sum of its assets' weights.
+pub fn vote_totals(signals: &[Signal], weights: Option<&[u32]>) -> (u64, u64) {
+    let weight = |i: usize| weights.map_or(1, |w| w.get(i).map_or(0, |&w| w as u64));
+    let total = |side: fn(Signal) -> bool| {
+        signals.iter().enumerate().filter(|&(_, &s)| side(s)).map(|(i, _)| weight(i)).sum()
+    };
+    (total(Signal::is_bullish), total(Signal::is_bearish))
+}
+
+// Without enough price history for realized volatility, a 24h move larger
+// than this (either way) is HIGH risk and anything else LOW.
+pub const HIGH_RISK_CHANGE: ReturnBps = ReturnBps(1000);
+
+// Realized volatility (stddev of per-close returns, basis points) at or
+// above which risk is MEDIUM and HIGH.
+pub const MEDIUM_RISK_VOLATILITY: u64 = 200;
+pub const HIGH_RISK_VOLATILITY: u64 = 500;
+
+// Why a record got Signal::Hold, published next to the signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldReason {
+    // The indicator is inside its neutral band (or, for a crossover, didn't
+    // cross)
+    NeutralBand = 0,
+    // The mode needs a price history that is missing or too short
+    MissingData = 1,
+    // The record is too old to act on
+    StaleTimestamp = 2,
+    // Indicators disagree; reserved for strategies that combine them
+    ConflictingIndicators = 3,
+    // The 24h volume is below the liquidity floor
+    LowLiquidity = 4,
+    // The change crossed the opposite threshold but not by the hysteresis
+    // margin, so the previous signal wasn't reversed
+    Hysteresis = 5,
+    // A value computed for the record didn't fit its type and saturated, so
+    // the decision can't be trusted. Takes precedence over every other reason
+    Overflow = 6,
+    // The 24h change is beyond Rules::max_change_bps, so the feed value is
+    // taken to be a glitch rather than a move to trade on
+    DataSanity = 7,
+    // A BUY or SELL came inside Rules::min_hold's cooldown after the last
+    // trade
+    MinHold = 8,
+}
+
+impl HoldReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            HoldReason::NeutralBand => "neutral_band",
+            HoldReason::MissingData => "missing_data",
+            HoldReason::StaleTimestamp => "stale_timestamp",
+            HoldReason::ConflictingIndicators => "conflicting_indicators",
+            HoldReason::LowLiquidity => "low_liquidity",
+            HoldReason::Hysteresis => "hysteresis",
+            HoldReason::Overflow => "overflow",
+            HoldReason::DataSanity => "data_sanity",
+            HoldReason::MinHold => "min_hold",
+        }
+    }
+}
+
+// Decodes a published HOLD reason; the error is the unrecognised value.
+impl TryFrom<u32> for HoldReason {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, u32> {
+        match value {
+            0 => Ok(HoldReason::NeutralBand),
+            1 => Ok(HoldReason::MissingData),
+            2 => Ok(HoldReason::StaleTimestamp),
+            3 => Ok(HoldReason::ConflictingIndicators),
+            4 => Ok(HoldReason::LowLiquidity),
+            5 => Ok(HoldReason::Hysteresis),
+            6 => Ok(HoldReason::Overflow),
+            7 => Ok(HoldReason::DataSanity),
+            8 => Ok(HoldReason::MinHold),
+            _ => Err(value),
+        }
+    }
+}
+
+// How far past the crossed threshold (basis points) a change has to be for
+// full confidence.
+pub const FULL_CONFIDENCE_BPS: i64 = 1000;
+pub const MAX_CONFIDENCE: u32 = 100;
+
+// Published in the confidence slot for a signal from a mode that isn't
+// scored (anything but Threshold).
+pub const CONFIDENCE_UNAVAILABLE: u32 = 0xFF;
+
+// 0 for a `change` inside the neutral band; at or past a threshold,
+// proportional to how far past it is: MAX_CONFIDENCE at FULL_CONFIDENCE_BPS
+// and beyond, rounded up, and at least 1 so that a change right on the
+// threshold, which signals too, scores.
+pub fn confidence(change: ReturnBps, thresholds: &Thresholds) -> u32 {
+    let excess = if change <= thresholds.buy {
+        thresholds.buy.bps() as i128 - change.bps() as i128
+    } else if change >= thresholds.sell {
+        change.bps() as i128 - thresholds.sell.bps() as i128
+    } else {
+        return 0;
+    };
+    let full = FULL_CONFIDENCE_BPS as i128;
+    let score = (excess * MAX_CONFIDENCE as i128 + full - 1) / full;
+    score.clamp(1, MAX_CONFIDENCE as i128) as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub signal: Signal,
+    // Some exactly when `signal` is Signal::Hold.
+    pub hold_reason: Option<HoldReason>,
+    // On a HoldReason::MissingData HOLD, how many closes the record had (0
+    // without a history), so a short feed can be told from a missing one.
+    pub closes_available: Option<usize>,
+    // 0 on a HOLD. Otherwise `confidence` of the change in Threshold mode,
+    // and None in the modes that aren't scored.
+    pub confidence: Option<u32>,
+    pub risk: RiskLevel,
+    // The record's band (1..=RiskBands::count) when the input has risk
+    // bands. Published beside `risk`; it doesn't change the sizing.
+    pub risk_band: Option<u32>,
+    // Seconds since the record's timestamp (0 for a future one), when the
+    // input has a current time. Telemetry only: it never changes the signal.
+    pub age_secs: Option<u64>,
+    // Indicators over `closes`, when given and long enough, whatever the mode.
+    pub rsi_x100: Option<u32>,
+    pub sma_short: Option<u64>,
+    pub sma_long: Option<u64>,
+    pub band_width_bps: Option<u64>,
+    // Band width below Rules::squeeze_bps; None without the bands.
+    pub squeeze: Option<bool>,
+    // MACD - signal, in hundredths of a cent.
+    pub macd_histogram: Option<i32>,
+    // Price EMA (PriceBasis::Ema) or WMA (PriceBasis::Wma) in cents, when the
+    // history is long enough.
+    pub smoothed_price: Option<u64>,
+    // Realized volatility in basis points, which set `risk` when present.
+    pub volatility_bps: Option<u64>,
+    // Suggested notional in cents; 0 on HOLD or without Rules::position.
+    pub position_size: u64,
+    // Suggested exit prices for a position in Rules::direction, in cents;
+    // 0 without Rules::levels.
+    pub stop_loss: u64,
+    pub take_profit: u64,
+    // Derived from the record for publishing.
+    pub price_lo: u32,
+    pub price_hi: u32,
+    pub timestamp: u32,
+    pub volume_lo: u32,
+    pub volume_hi: u32,
+}
+
+impl Decision {
+    // The published values, indexed by the output_layout RUN_* constants.
+    pub fn outputs(&self) -> [u32; VALUES_PER_RECORD] {
+        let (position_lo, position_hi) = market::split_u64(self.position_size);
+        let (stop_lo, stop_hi) = market::split_u64(self.stop_loss);
+        let (target_lo, target_hi) = market::split_u64(self.take_profit);
+        let mut out = [0; VALUES_PER_RECORD];
+        out[layout::RUN_SIGNAL] = self.signal as u32;
+        out[layout::RUN_RISK] = layout::risk_and_band(self.risk as u32, self.risk_band);
+        out[layout::RUN_SMOOTHED_PRICE] = self.smoothed_price
+            .and_then(|p| u32::try_from(p).ok())
+            .unwrap_or(indicators::EMA_UNAVAILABLE);
+        out[layout::RUN_TIMESTAMP] = self.timestamp;
+        out[layout::RUN_RSI] = self.rsi_x100.unwrap_or(indicators::RSI_UNAVAILABLE);
+        out[layout::RUN_PRICE_LO] = self.price_lo;
+        out[layout::RUN_PRICE_HI] = self.price_hi;
+        out[layout::RUN_SMA_SHORT] = sma_output(self.sma_short);
+        out[layout::RUN_SMA_LONG] = sma_output(self.sma_long);
+        out[layout::RUN_HOLD_REASON] = layout::reason_confidence_closes(
+            self.hold_reason.map_or(0, |reason| reason as u32),
+            self.confidence.unwrap_or(CONFIDENCE_UNAVAILABLE),
+            self.closes_available.unwrap_or(0),
+        );
+        out[layout::RUN_VOLUME_LO] = self.volume_lo;
+        out[layout::RUN_VOLUME_HI] = self.volume_hi;
+        out[layout::RUN_BAND_WIDTH] = self.band_width_bps
+            .and_then(|w| u32::try_from(w).ok())
+            .unwrap_or(indicators::BANDS_UNAVAILABLE);
+        out[layout::RUN_SQUEEZE] = self.squeeze.map_or(indicators::BANDS_UNAVAILABLE, u32::from);
+        out[layout::RUN_MACD_HISTOGRAM] = self.macd_histogram.map_or(indicators::MACD_UNAVAILABLE, |h| h as u32);
+        out[layout::RUN_VOLATILITY] = self.volatility_bps
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(indicators::VOLATILITY_UNAVAILABLE);
+        out[layout::RUN_POSITION_LO] = position_lo;
+        out[layout::RUN_POSITION_HI] = position_hi;
+        out[layout::RUN_STOP_LO] = stop_lo;
+        out[layout::RUN_STOP_HI] = stop_hi;
+        out[layout::RUN_TARGET_LO] = target_lo;
+        out[layout::RUN_TARGET_HI] = target_hi;
+        out[layout::RUN_AGE] = self.age_secs.and_then(|a| u32::try_from(a).ok()).unwrap_or(u32::MAX);
+        out
+    }
+}
+
+// Suggested notional: balance x risk_fraction, halved at MEDIUM risk and
+// quartered at HIGH, and nothing for a HOLD. Rounds down to the cent; None
+// when it doesn't fit a u64.
+pub fn position_size(position: &Position, signal: Signal, risk: RiskLevel) -> Option<u64> {
+    if signal == Signal::Hold {
+        return Some(0);
+    }
+    let divisor = match risk {
+        RiskLevel::Low => 1,
+        RiskLevel::Medium => 2,
+        RiskLevel::High => 4,
+    };
+    let scale = 10u128.pow(market::RISK_FRACTION_DECIMALS);
+    let size = (position.balance as u128).checked_mul(position.risk_fraction as u128)? / scale / divisor;
+    u64::try_from(size).ok()
+}
+
+// Stop-loss and take-profit prices for `price`, with the stop widening
+// with the risk level. A long stops below the price and takes profit above
+// it; a short is the mirror image. Levels below the price floor at 0 and
+// both round down to the cent; None when one above doesn't fit a u64.
+pub fn exit_levels(levels: &Levels, price: u64, risk: RiskLevel, direction: Direction) -> Option<(u64, u64)> {
+    let stop_bps = levels.stop_bps as u128 * risk as u128;
+    let target_bps = levels.target_bps as u128;
+    let below = |bps: u128| (price as u128 * (10_000 - bps.min(10_000)) / 10_000) as u64;
+    let above = |bps: u128| u64::try_from((price as u128).checked_mul(10_000 + bps)? / 10_000).ok();
+    Some(match direction {
+        Direction::Long => (below(stop_bps), above(target_bps)?),
+        Direction::Short => (above(stop_bps)?, below(target_bps)),
+    })
+}
+
+// Reversing `prev` takes a change at least `margin` bps past the opposite
+// threshold: after a bullish signal SELL needs change >= sell + margin,
+// after a bearish one BUY needs change <= buy - margin. Anything in between
+// is a HOLD. Repeating the previous direction isn't affected.
+// The bounds are compared in i128, where even a u64::MAX margin fits, so
+// none of this can overflow.
+fn hysteresis(
+    signal: Result<Signal, HoldReason>,
+    change: ReturnBps,
+    thresholds: &Thresholds,
+    margin: u64,
+    prev: Signal,
+) -> Result<Signal, HoldReason> {
+    let change = change.bps() as i128;
+    let sell = thresholds.sell.bps() as i128 + margin as i128;
+    let buy = thresholds.buy.bps() as i128 - margin as i128;
+    match signal {
+        Ok(Signal::Sell) if prev.is_bullish() && change < sell => Err(HoldReason::Hysteresis),
+        Ok(Signal::Buy) if prev.is_bearish() && change > buy => Err(HoldReason::Hysteresis),
+        _ => signal,
+    }
+}
+
+fn sma_output(sma: Option<u64>) -> u32 {
+    sma.and_then(|v| u32::try_from(v).ok()).unwrap_or(indicators::SMA_UNAVAILABLE)
+}
+
+// `closes` is the record's price history, if any. The history-based modes
+// HOLD when it's missing or too short, and every mode HOLDs on a record that
+// is stale, below the volume floor or has an implausible 24h change
+// (Rules::max_change_bps; that check comes first). `prev` is the record's
+// signal from the previous run, which Rules::hysteresis_bps uses in
+// Threshold mode.
+// Any BUY or SELL left inside Rules::min_hold's cooldown then HOLDs.
+// Under PriceBasis::Ema (or Wma), Threshold mode (hysteresis included) reads
+// the EMA's (or WMA's) change instead of the 24h change, and HOLDs without
+// enough history.
+// Under Raw it HOLDs on a record whose change is CHANGE_UNAVAILABLE.
+// Threshold mode's bounds are Rules::active_thresholds, i.e. a symmetric
+// neutral band when the input sets one.
+// Whenever a value saturates (HoldReason::Overflow) the record HOLDs with
+// no position, and a level that overflowed is published as u64::MAX. The
+// band width and MACD histogram only count in their own modes, the only
+// ones that decide on them and publish them.
+pub fn decide(record: &Record, rules: &Rules, closes: Option<&[u64]>, prev: Option<Signal>) -> Decision {
+    let thresholds = &rules.active_thresholds();
+    let mut overflow = false;
+    let rsi = closes.and_then(indicators::rsi_x100);
+    let sma_short = closes.and_then(|c| indicators::sma(c, indicators::SMA_SHORT_PERIOD));
+    let sma_long = closes.and_then(|c| indicators::sma(c, indicators::SMA_LONG_PERIOD));
+    let enough_for_cross = closes.is_some_and(|c| c.len() > indicators::SMA_LONG_PERIOD);
+    let bands = closes.and_then(indicators::bollinger);
+    let band_width_bps = bands.map(|b| b.width_bps());
+    overflow |= rules.mode == SignalMode::Bollinger && band_width_bps == Some(u64::MAX);
+    let macd = closes.and_then(indicators::macd);
+    overflow |= rules.mode == SignalMode::Macd && macd.is_some_and(|m| m.checked_histogram_x100().is_none());
+    let smoothed = match rules.price_basis {
+        PriceBasis::Raw => None,
+        PriceBasis::Ema => closes.and_then(|c| indicators::price_ema(c, rules.ema_period as usize)),
+        PriceBasis::Wma => closes.and_then(|c| indicators::price_wma(c, rules.ema_period as usize)),
+    };
+    let change = match rules.price_basis {
+        PriceBasis::Raw => Some(record.change_24h).filter(|&c| c != market::CHANGE_UNAVAILABLE),
+        PriceBasis::Ema | PriceBasis::Wma => smoothed.map(|e| {
+            let change = e.change_bps.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+            overflow |= change as i128 != e.change_bps;
+            ReturnBps(change)
+        }),
+    };
+    let implausible = rules.max_change_bps.is_some_and(|bound| {
+        record.change_24h != market::CHANGE_UNAVAILABLE && record.change_24h.bps().unsigned_abs() > bound
+    });
+    let signal = match rules.mode {
+        _ if implausible => Err(HoldReason::DataSanity),
+        _ if rules.freshness.is_some_and(|f| f.is_stale(record.timestamp)) => Err(HoldReason::StaleTimestamp),
+        _ if rules.min_volume.is_some_and(|floor| record.volume < floor) => Err(HoldReason::LowLiquidity),
+        SignalMode::Threshold => match change {
+            Some(c) if c <= thresholds.buy => Ok(Signal::Buy),  // default: down 5% or more
+            Some(c) if c >= thresholds.sell => Ok(Signal::Sell),  // default: up 3% or more
+            Some(_) => Err(HoldReason::NeutralBand),
+            None => Err(HoldReason::MissingData),
+        },
+        SignalMode::Rsi => match rsi {
+            Some(rsi) if rsi < indicators::RSI_OVERSOLD_X100 => Ok(Signal::Buy),
+            Some(rsi) if rsi > indicators::RSI_OVERBOUGHT_X100 => Ok(Signal::Sell),
+            Some(_) => Err(HoldReason::NeutralBand),
+            None => Err(HoldReason::MissingData),
+        },
+        SignalMode::MaCrossover => match closes.and_then(indicators::sma_cross) {
+            Some(Cross::Above) => Ok(Signal::Buy),
+            Some(Cross::Below) => Ok(Signal::Sell),
+            None if enough_for_cross => Err(HoldReason::NeutralBand),
+            None => Err(HoldReason::MissingData),
+        },
+        // Trades the breakout: BUY above the upper band, SELL below the lower
+        SignalMode::Bollinger => match (bands, closes.and_then(|c| c.last())) {
+            (Some(b), Some(&close)) => match b.breakout(close) {
+                Some(Cross::Above) => Ok(Signal::Buy),
+                Some(Cross::Below) => Ok(Signal::Sell),
+                None => Err(HoldReason::NeutralBand),
+            },
+            _ => Err(HoldReason::MissingData),
+        },
+        SignalMode::Macd => match macd {
+            Some(m) if m.cross == Some(Cross::Above) => Ok(Signal::Buy),
+            Some(m) if m.cross == Some(Cross::Below) => Ok(Signal::Sell),
+            Some(_) if closes.is_some_and(|c| c.len() > indicators::MACD_MIN_CLOSES) => Err(HoldReason::NeutralBand),
+            _ => Err(HoldReason::MissingData),
+        },
+    };
+    let signal = match (rules.mode, rules.hysteresis_bps, prev, change) {
+        (SignalMode::Threshold, Some(margin), Some(prev), Some(change)) => {
+            hysteresis(signal, change, thresholds, margin, prev)
+        }
+        _ => signal,
+    };
+    let signal = match signal {
+        Ok(_) if rules.min_hold.is_some_and(|m| m.is_cooling_down(rules.now())) => Err(HoldReason::MinHold),
+        _ => signal,
+    };
+
+    // Risk level from realized volatility, or the 24h move without history
+    let volatility_bps = closes.and_then(indicators::volatility_bps);
+    overflow |= volatility_bps == Some(u64::MAX);
+    let risk = match volatility_bps {
+        Some(v) if v >= HIGH_RISK_VOLATILITY => RiskLevel::High,
+        Some(v) if v >= MEDIUM_RISK_VOLATILITY => RiskLevel::Medium,
+        Some(_) => RiskLevel::Low,
+        // An unknown move is HIGH
+        None if record.change_24h == market::CHANGE_UNAVAILABLE => RiskLevel::High,
+        None if record.change_24h.bps().unsigned_abs() > HIGH_RISK_CHANGE.bps().unsigned_abs() => RiskLevel::High,
+        None => RiskLevel::Low,
+    };
+    // The same metric for the configured bands; an unknown move is off the top
+    let risk_metric = volatility_bps.unwrap_or(match record.change_24h {
+        market::CHANGE_UNAVAILABLE => u64::MAX,
+        change => change.bps().unsigned_abs(),
+    });
+    let risk_band = rules.risk_bands.map(|bands| bands.band(risk_metric));
+
+    // No exit levels without Rules::levels; their runs aren't published then
+    let levels = rules.levels.map_or(Some((0, 0)), |l| exit_levels(&l, record.price, risk, rules.direction));
+    overflow |= levels.is_none();
+    let (stop_loss, take_profit) = levels.unwrap_or((u64::MAX, u64::MAX));
+    let timestamp = u32::try_from(record.timestamp).ok();
+    overflow |= timestamp.is_none();
+    let age_secs = rules.now().map(|now| market::age_secs(now, record.timestamp));
+    overflow |= age_secs.is_some_and(|a| u32::try_from(a).is_err());
+    let proposed = signal.unwrap_or(Signal::Hold).for_direction(rules.direction);
+    let size = rules.position.map_or(Some(0), |p| position_size(&p, proposed, risk));
+    overflow |= size.is_none();
+
+    let signal = if overflow { Err(HoldReason::Overflow) } else { signal };
+    let signal_or_hold = signal.unwrap_or(Signal::Hold).for_direction(rules.direction);
+    let position_size = size.filter(|_| !overflow).unwrap_or(0);
+    let confidence = match (signal, rules.mode, change) {
+        (Err(_), _, _) => Some(0),
+        (Ok(_), SignalMode::Threshold, Some(change)) => Some(confidence(change, thresholds)),
+        _ => None,
+    };
+
+    let (price_lo, price_hi) = market::split_u64(record.price);
+    let (volume_lo, volume_hi) = market::split_u64(record.volume);
+    Decision {
+        signal: signal_or_hold,
+        hold_reason: signal.err(),
+        closes_available: (signal == Err(HoldReason::MissingData)).then_some(closes.map_or(0, <[u64]>::len)),
+        confidence,
+        risk,
+        risk_band,
+        age_secs,
+        rsi_x100: rsi,
+        sma_short,
+        sma_long,
+        band_width_bps,
+        squeeze: band_width_bps.map(|w| w < rules.squeeze_bps),
+        macd_histogram: macd.map(|m| m.histogram_x100()),
+        smoothed_price: smoothed.map(|e| e.price),
+        volatility_bps,
+        position_size,
+        stop_loss,
+        take_profit,
+        price_lo,
+        price_hi,
+        timestamp: timestamp.unwrap_or(u32::MAX),
+        volume_lo,
+        volume_hi,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::{Freshness, RiskBands, DEFAULT_LEVELS, DEFAULT_SQUEEZE_BPS, DEFAULT_THRESHOLDS};
+
+    fn record(change_24h: i64) -> Record {
+        Record { price: 250_012, change_24h: ReturnBps(change_24h), timestamp: 1_700_000_000, volume: 5_000_000 }
+    }
+
+    fn rules(mode: SignalMode) -> Rules {
+        Rules {
+            thresholds: DEFAULT_THRESHOLDS,
+            mode,
+            freshness: None,
+            min_volume: None,
+            squeeze_bps: DEFAULT_SQUEEZE_BPS,
+            position: None,
+            levels: Some(DEFAULT_LEVELS),
+            direction: Direction::Long,
+            hysteresis_bps: None,
+            price_basis: PriceBasis::Raw,
+            ema_period: market::DEFAULT_EMA_PERIOD,
+            neutral_band_bps: None,
+            current_time: None,
+            max_change_bps: None,
+            min_hold: None,
+            risk_bands: None,
+        }
+    }
+
+    // The percent conversions are std (percent.rs)
+    #[cfg(feature = "std")]
+    #[test]
+    fn returns_are_basis_points() {
+        assert_eq!(ReturnBps::from_percent(-5.0), Ok(market::DEFAULT_BUY_BELOW));
+        assert_eq!(ReturnBps::from_percent(3.0), Ok(market::DEFAULT_SELL_ABOVE));
+        assert_eq!(ReturnBps::from_percent(-6.2), Ok(ReturnBps(-620)));
+        assert_eq!(ReturnBps::from_percent(0.006), Ok(ReturnBps(1)));  // rounds to the nearest bp
+        assert!(ReturnBps::from_percent(f64::NAN).is_err());
+        assert_eq!(ReturnBps(-620).to_percent(), -6.2);
+        assert_eq!(HIGH_RISK_CHANGE.to_percent(), 10.0);
+    }
+
+    #[test]
+    fn signals_from_labels() {
+        for code in 0..=4 {
+            let signal = Signal::try_from(code).unwrap();
+            assert_eq!(Signal::from_label(signal.label()), Some(signal));
+        }
+        assert_eq!(Signal::from_label("buy"), Some(Signal::Buy));
+        assert_eq!(Signal::from_label("Open_Short"), Some(Signal::OpenShort));
+        assert_eq!(Signal::from_label("BUY "), None);
+        assert_eq!(Signal::from_label("long"), None);
+    }
+
+    #[test]
+    fn hold_reasons_round_trip() {
+        for code in 0..=8 {
+            assert_eq!(HoldReason::try_from(code).map(|reason| reason as u32), Ok(code));
+        }
+        assert_eq!(HoldReason::try_from(9), Err(9));
+        assert_eq!(HoldReason::MinHold.name(), "min_hold");
+    }
+
+    #[test]
+    fn signal_boundaries() {
+        let cases = [
+            (-501, Signal::Buy),
+            (-500, Signal::Buy),
+            (-499, Signal::Hold),
+            (0, Signal::Hold),
+            (299, Signal::Hold),
+            (300, Signal::Sell),
+        ];
+        for (change, signal) in cases {
+            let decision = decide(&record(change), &rules(SignalMode::Threshold), None, None);
+            assert_eq!(decision.signal, signal, "change {}", change);
+            assert_eq!(decision.hold_reason.is_some(), signal == Signal::Hold, "change {}", change);
+        }
+    }
+
+    #[test]
+    fn confidence_boundaries() {
+        let cases = [
+            (-499, 0),
+            (-500, 1),
+            (-501, 1),
+            (-510, 1),
+            (-511, 2),
+            (-1_490, 99),
+            (-1_491, 100),
+            (-1_500, 100),
+            (i64::MIN, 100),
+            (0, 0),
+            (299, 0),
+            (300, 1),
+            (301, 1),
+            (1_300, 100),
+            (i64::MAX, 100),
+        ];
+        for (change, expected) in cases {
+            assert_eq!(confidence(ReturnBps(change), &DEFAULT_THRESHOLDS), expected, "change {}", change);
+        }
+
+        // Published next to the hold reason, in bits of its own
+        let rules = rules(SignalMode::Threshold);
+        let buy = decide(&record(-600), &rules, None, None);
+        assert_eq!(buy.confidence, Some(10));
+        assert_eq!(layout::split_reason_confidence_closes(buy.outputs()[layout::RUN_CONFIDENCE]), (0, 10, 0));
+        let hold = decide(&record(-100), &rules, None, None);
+        assert_eq!(hold.confidence, Some(0));
+        assert_eq!(hold.outputs()[layout::RUN_HOLD_REASON], HoldReason::NeutralBand as u32);
+    }
+
+    #[test]
+    fn short_direction() {
+        let mut rules = rules(SignalMode::Threshold);
+        rules.direction = Direction::Short;
+        let cases = [
+            (-501, Signal::CoverShort),
+            (0, Signal::Hold),
+            (301, Signal::OpenShort),
+        ];
+        for (change, signal) in cases {
+            assert_eq!(decide(&record(change), &rules, None, None).signal, signal, "change {}", change);
+        }
+    }
+
+    #[test]
+    fn hysteresis_holds_narrow_reversals() {
+        let rules = Rules { hysteresis_bps: Some(100), ..rules(SignalMode::Threshold) };
+        let cases = [
+            (Signal::Buy, 399, Signal::Hold),
+            (Signal::Buy, 400, Signal::Sell),
+            (Signal::Sell, -599, Signal::Hold),
+            (Signal::Sell, -600, Signal::Buy),
+            (Signal::Buy, -501, Signal::Buy),
+            (Signal::Hold, 301, Signal::Sell),
+        ];
+        for (prev, change, signal) in cases {
+            let decision = decide(&record(change), &rules, None, Some(prev));
+            assert_eq!(decision.signal, signal, "{:?} then change {}", prev, change);
+        }
+        let held = decide(&record(399), &rules, None, Some(Signal::Buy));
+        assert_eq!(held.hold_reason, Some(HoldReason::Hysteresis));
+        assert_eq!(decide(&record(399), &rules, None, None).signal, Signal::Sell);
+    }
+
+    #[test]
+    fn ema_basis_signals_on_the_smoothed_change() {
+        let rules = Rules { price_basis: PriceBasis::Ema, ema_period: 3, ..rules(SignalMode::Threshold) };
+        // EMA 10000 then (10000 * 2 + 2 * 8000) / 4 = 9000: a 10% drop
+        let closes = [10_000, 10_000, 10_000, 8_000];
+        let decision = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(decision.signal, Signal::Buy);
+        assert_eq!(decision.smoothed_price, Some(9_000));
+        assert_eq!(decision.outputs()[layout::RUN_SMOOTHED_PRICE], 9_000);
+
+        let short = decide(&record(-1_000), &rules, Some(&closes[..3]), None);
+        assert_eq!(short.hold_reason, Some(HoldReason::MissingData));
+        assert_eq!(short.outputs()[layout::RUN_SMOOTHED_PRICE], indicators::EMA_UNAVAILABLE);
+    }
+
+    #[test]
+    fn wma_basis_signals_on_the_weighted_change() {
+        let rules = Rules { price_basis: PriceBasis::Wma, ema_period: 3, ..rules(SignalMode::Threshold) };
+        // WMA (10000 + 2 * 10000 + 3 * 8000) / 6 = 9000, from 10000: a 10% drop
+        let closes = [10_000, 10_000, 10_000, 8_000];
+        let decision = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(decision.signal, Signal::Buy);
+        assert_eq!(decision.smoothed_price, Some(9_000));
+        assert_eq!(decision.outputs()[layout::RUN_SMOOTHED_PRICE], 9_000);
+
+        // (2 + 2 * 3 + 3 * 5) / 6 = 3.8333 from (1 + 2 * 2 + 3 * 3) / 6 = 2.3333:
+        // both kept to 10^-6 of a cent, then +6428.57 bps truncated
+        let wma = indicators::price_wma(&[1, 2, 3, 5], 3).unwrap();
+        assert_eq!((wma.price, wma.change_bps), (3, 6428));
+
+        let short = decide(&record(-1_000), &rules, Some(&closes[..3]), None);
+        assert_eq!(short.hold_reason, Some(HoldReason::MissingData));
+        assert_eq!(short.outputs()[layout::RUN_SMOOTHED_PRICE], indicators::EMA_UNAVAILABLE);
+    }
+
+    #[test]
+    fn short_history_holds_with_closes_available() {
+        let closes: Vec<u64> = (0..indicators::MACD_MIN_CLOSES as u64 + 1).map(|i| 10_000 + 37 * i).collect();
+        let modes = [SignalMode::Rsi, SignalMode::MaCrossover, SignalMode::Bollinger, SignalMode::Macd];
+        let ema = Rules { price_basis: PriceBasis::Ema, ..rules(SignalMode::Threshold) };
+        let wma = Rules { price_basis: PriceBasis::Wma, ..rules(SignalMode::Threshold) };
+        for rules in modes.map(rules).into_iter().chain([ema, wma]) {
+            // Every length short of the window, down to an empty series
+            for len in 0..=4 {
+                let decision = decide(&record(-1_000), &rules, Some(&closes[..len]), None);
+                assert_eq!(decision.hold_reason, Some(HoldReason::MissingData), "{:?} with {}", rules.mode, len);
+                assert_eq!(decision.closes_available, Some(len));
+                let slot = decision.outputs()[layout::RUN_HOLD_REASON];
+                let missing = (HoldReason::MissingData as u32, 0, len as u32);
+                assert_eq!(layout::split_reason_confidence_closes(slot), missing);
+            }
+            // And no panic at any length up to the longest window
+            for len in 0..closes.len() {
+                decide(&record(-1_000), &rules, Some(&closes[..len]), None);
+            }
+        }
+
+        let none = decide(&record(0), &rules(SignalMode::Rsi), None, None);
+        assert_eq!(none.closes_available, Some(0));
+        let neutral = decide(&record(0), &rules(SignalMode::Threshold), Some(&closes[..3]), None);
+        assert_eq!((neutral.hold_reason, neutral.closes_available), (Some(HoldReason::NeutralBand), None));
+    }
+
+    #[test]
+    fn missing_change_holds() {
+        let decision = decide(&record(market::CHANGE_UNAVAILABLE.bps()), &rules(SignalMode::Threshold), None, None);
+        assert_eq!(decision.signal, Signal::Hold);
+        assert_eq!(decision.hold_reason, Some(HoldReason::MissingData));
+        assert_eq!(decision.risk, RiskLevel::High);
+
+        // Hysteresis has nothing to compare either
+        let rules = Rules { hysteresis_bps: Some(100), ..rules(SignalMode::Threshold) };
+        let decision = decide(&record(market::CHANGE_UNAVAILABLE.bps()), &rules, None, Some(Signal::Buy));
+        assert_eq!(decision.hold_reason, Some(HoldReason::MissingData));
+    }
+
+    #[test]
+    fn portfolio_majority() {
+        use Signal::*;
+        let cases = [
+            (vec![], PortfolioSignal::Neutral),
+            (vec![Hold, Hold], PortfolioSignal::Neutral),
+            (vec![Buy, Hold], PortfolioSignal::RiskOn),
+            (vec![Buy, Sell], PortfolioSignal::Neutral),
+            (vec![Sell, OpenShort, Buy], PortfolioSignal::RiskOff),
+            (vec![CoverShort, Buy, Sell], PortfolioSignal::RiskOn),
+        ];
+        for (signals, expected) in cases {
+            assert_eq!(aggregate(&signals, None), expected, "{:?}", signals);
+        }
+    }
+
+    #[test]
+    fn portfolio_tie_is_neutral() {
+        use Signal::*;
+        // Two bullish, two bearish, and HOLDs that don't vote, in every order
+        let tie = [Buy, Sell, Hold, CoverShort, OpenShort, Hold];
+        let mut order = tie;
+        for _ in 0..tie.len() {
+            order.rotate_left(1);
+            assert_eq!(aggregate(&order, None), PortfolioSignal::Neutral, "{:?}", order);
+            assert!(is_tie(&order, None));
+            let mut reversed = order;
+            reversed.reverse();
+            assert_eq!(aggregate(&reversed, None), PortfolioSignal::Neutral);
+        }
+
+        assert!(!is_tie(&[], None));
+        assert!(!is_tie(&[Hold, Hold], None));
+        assert!(!is_tie(&[Buy, Sell, Sell], None));
+        let slot = layout::portfolio_vote(aggregate(&tie, None) as u32, is_tie(&tie, None), vote_totals(&tie, None));
+        assert_eq!(layout::split_portfolio_vote(slot), (PortfolioSignal::Neutral as u32, true, (2, 2)));
+    }
+
+    #[test]
+    fn weighted_portfolio() {
+        use Signal::*;
+        let signals = [Buy, Sell, Sell, Hold];
+        assert_eq!(aggregate(&signals, None), PortfolioSignal::RiskOff);
+
+        // One heavy BUY outweighs two light SELLs; the HOLD's weight never votes
+        let weights = [5, 2, 2, 100];
+        assert_eq!(vote_totals(&signals, Some(&weights)), (5, 4));
+        assert_eq!(aggregate(&signals, Some(&weights)), PortfolioSignal::RiskOn);
+
+        let even = [4, 2, 2, 0];
+        assert_eq!(aggregate(&signals, Some(&even)), PortfolioSignal::Neutral);
+        assert!(is_tie(&signals, Some(&even)));
+        // Weight 0 doesn't vote, so an all-zero book has no votes and no tie
+        assert!(!is_tie(&signals, Some(&[0; 4])));
+    }
+
+    #[test]
+    fn risk_boundaries() {
+        let cases = [
+            (-1001, RiskLevel::High),
+            (-1000, RiskLevel::Low),
+            (1000, RiskLevel::Low),
+            (1001, RiskLevel::High),
+        ];
+        for (change, risk) in cases {
+            let decision = decide(&record(change), &rules(SignalMode::Threshold), None, None);
+            assert_eq!(decision.risk, risk, "change {}", change);
+        }
+    }
+
+    #[test]
+    fn risk_bands() {
+        let bands = RiskBands::new(&[200, 500, 1000]).unwrap();
+        let rules = Rules { risk_bands: Some(bands), ..rules(SignalMode::Threshold) };
+        // |24h change| without a history, either sign
+        for (change, band) in [(0, 1), (-199, 1), (200, 2), (-500, 3), (999, 3), (-1000, 4), (5000, 4)] {
+            let decision = decide(&record(change), &rules, None, None);
+            assert_eq!(decision.risk_band, Some(band), "change {}", change);
+        }
+        let unknown = decide(&record(market::CHANGE_UNAVAILABLE.bps()), &rules, None, None);
+        assert_eq!(unknown.risk_band, Some(bands.count()));
+
+        // Volatility when the history has enough closes, as for `risk`
+        let closes: Vec<u64> =
+            (0..=indicators::VOLATILITY_PERIOD as u64).map(|i| if i % 2 == 0 { 10_000 } else { 10_250 }).collect();
+        let decision = decide(&record(2000), &rules, Some(&closes), None);
+        assert_eq!((decision.risk, decision.risk_band), (RiskLevel::Medium, Some(2)));
+
+        // Published in the risk byte, beside the unchanged risk level
+        let risk = decision.outputs()[layout::RUN_RISK];
+        assert_eq!(layout::split_risk_band(risk), (RiskLevel::Medium as u32, Some(2)));
+        let unbanded = decide(&record(2000), &Rules { risk_bands: None, ..rules }, Some(&closes), None);
+        assert_eq!(unbanded.risk_band, None);
+        assert_eq!(unbanded.outputs()[layout::RUN_RISK], RiskLevel::Medium as u32);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for signal in [Signal::Hold, Signal::Buy, Signal::Sell, Signal::OpenShort, Signal::CoverShort] {
+            assert_eq!(Signal::try_from(signal as u32), Ok(signal));
+        }
+        for risk in [RiskLevel::Low, RiskLevel::Medium, RiskLevel::High] {
+            assert_eq!(RiskLevel::try_from(risk as u32), Ok(risk));
+        }
+        assert_eq!(Signal::try_from(5), Err(5));
+        assert_eq!(RiskLevel::try_from(4), Err(4));
+    }
+
+    #[test]
+    fn volatility_risk() {
+        // Closes alternating by +/-x bps of 10000 have returns of about +/-x bps
+        let alternating = |x: u64| -> Vec<u64> {
+            (0..=indicators::VOLATILITY_PERIOD as u64).map(|i| if i % 2 == 0 { 10_000 } else { 10_000 + x }).collect()
+        };
+        let rules = rules(SignalMode::Threshold);
+        let cases = [
+            (0, RiskLevel::Low),
+            (150, RiskLevel::Low),
+            (250, RiskLevel::Medium),
+            (800, RiskLevel::High),
+        ];
+        for (x, risk) in cases {
+            // A 24h move that alone would be HIGH: volatility takes precedence
+            let decision = decide(&record(2000), &rules, Some(&alternating(x)), None);
+            assert_eq!(decision.risk, risk, "swing {}", x);
+        }
+
+        let flat = decide(&record(0), &rules, Some(&alternating(0)), None);
+        assert_eq!(flat.volatility_bps, Some(0));
+        let short = decide(&record(2000), &rules, Some(&[10_000; 3]), None);
+        assert_eq!((short.risk, short.volatility_bps), (RiskLevel::High, None));
+    }
+
+    #[test]
+    fn ma_crossover() {
+        // Flat, then a jump on the last close pulls the short SMA above the
+        // long one
+        let mut closes = vec![10_000; indicators::SMA_LONG_PERIOD];
+        closes.push(20_000);
+        let rules = rules(SignalMode::MaCrossover);
+        let buy = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(buy.signal, Signal::Buy);
+        assert_eq!(buy.sma_short, Some(12_000));
+        assert_eq!(buy.sma_long, Some(10_500));
+        let (_, confidence, _) = layout::split_reason_confidence_closes(buy.outputs()[layout::RUN_CONFIDENCE]);
+        assert_eq!(confidence, CONFIDENCE_UNAVAILABLE);
+
+        *closes.last_mut().unwrap() = 5_000;
+        let sell = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(sell.signal, Signal::Sell);
+
+        let short = decide(&record(0), &rules, Some(&closes[1..]), None);
+        assert_eq!(short.signal, Signal::Hold);
+        assert_eq!(short.hold_reason, Some(HoldReason::MissingData));
+    }
+
+    #[test]
+    fn symmetric_neutral_band() {
+        let rules = Rules { neutral_band_bps: Some(400), ..rules(SignalMode::Threshold) };
+        let cases = [
+            (-401, Signal::Buy),
+            (-400, Signal::Buy),
+            (-399, Signal::Hold),
+            (0, Signal::Hold),
+            (399, Signal::Hold),
+            (400, Signal::Sell),
+        ];
+        for (change, signal) in cases {
+            let decision = decide(&record(change), &rules, None, None);
+            assert_eq!(decision.signal, signal, "change {}", change);
+        }
+        // The band replaces the thresholds, and scores from its edge
+        assert_eq!(decide(&record(-450), &rules, None, None).signal, Signal::Buy);
+        assert_eq!(decide(&record(-400), &rules, None, None).confidence, Some(1));
+        assert_eq!(decide(&record(350), &rules, None, None).signal, Signal::Hold);
+
+        // The tightest band only holds a change of exactly 0
+        let tight = Rules { neutral_band_bps: Some(1), ..rules };
+        assert_eq!(decide(&record(0), &tight, None, None).signal, Signal::Hold);
+        assert_eq!(decide(&record(-1), &tight, None, None).signal, Signal::Buy);
+        assert_eq!(decide(&record(1), &tight, None, None).signal, Signal::Sell);
+        assert_eq!(Thresholds::symmetric(u64::MAX), Thresholds::symmetric(i64::MAX as u64));
+    }
+
+    #[test]
+    fn stale_records_hold() {
+        let rules = Rules {
+            freshness: Some(Freshness { current_time: 1_700_000_600, max_age_secs: 600 }),
+            ..rules(SignalMode::Threshold)
+        };
+        let cases = [
+            (1_700_000_000, Signal::Buy),    // exactly max_age_secs old
+            (1_699_999_999, Signal::Hold),   // one second too old
+            (1_700_009_999, Signal::Buy),    // in the future, clamped to age 0
+        ];
+        for (timestamp, signal) in cases {
+            let r = Record { timestamp, ..record(-1000) };
+            let decision = decide(&r, &rules, None, None);
+            assert_eq!(decision.signal, signal, "timestamp {}", timestamp);
+        }
+        let stale = Record { timestamp: 0, ..record(-1000) };
+        assert_eq!(decide(&stale, &rules, None, None).hold_reason, Some(HoldReason::StaleTimestamp));
+    }
+
+    #[test]
+    fn age_is_published_without_the_gate() {
+        let old = Record { timestamp: 1_699_000_000, ..record(-1000) };
+        let gated = Rules {
+            freshness: Some(Freshness { current_time: 1_700_000_000, max_age_secs: 600 }),
+            ..rules(SignalMode::Threshold)
+        };
+        assert_eq!(decide(&old, &gated, None, None).age_secs, Some(1_000_000));
+
+        // The current time alone publishes the age and never forces a HOLD
+        let ungated = Rules { current_time: Some(1_700_000_000), ..rules(SignalMode::Threshold) };
+        let decision = decide(&old, &ungated, None, None);
+        assert_eq!((decision.signal, decision.age_secs), (Signal::Buy, Some(1_000_000)));
+        assert_eq!(decision.outputs()[layout::RUN_AGE], 1_000_000);
+        assert_eq!(decision.outputs()[layout::RUN_RISK], decision.risk as u32);
+
+        // A timestamp of 0 is over 50 years old, and published in full
+        let ancient = Record { timestamp: 0, ..record(-1000) };
+        let decision = decide(&ancient, &ungated, None, None);
+        assert_eq!((decision.signal, decision.outputs()[layout::RUN_AGE]), (Signal::Buy, 1_700_000_000));
+        // ...until it no longer fits the run
+        let far = Rules { current_time: Some(u32::MAX as u64 + 1), ..ungated };
+        assert_eq!(decide(&ancient, &far, None, None).hold_reason, Some(HoldReason::Overflow));
+
+        // A future timestamp is age 0; no current time, no age
+        let future = Record { timestamp: 1_800_000_000, ..record(-1000) };
+        assert_eq!(decide(&future, &ungated, None, None).age_secs, Some(0));
+        let decision = decide(&old, &rules(SignalMode::Threshold), None, None);
+        assert_eq!(decision.age_secs, None);
+    }
+
+    #[test]
+    fn volume_floor() {
+        let rules = Rules { min_volume: Some(5_000_000), ..rules(SignalMode::Threshold) };
+        let at_floor = decide(&record(-1000), &rules, None, None);
+        assert_eq!(at_floor.signal, Signal::Buy);
+
+        let thin = Record { volume: 4_999_999, ..record(-1000) };
+        let decision = decide(&thin, &rules, None, None);
+        assert_eq!(decision.signal, Signal::Hold);
+        assert_eq!(decision.hold_reason, Some(HoldReason::LowLiquidity));
+    }
+
+    #[test]
+    fn min_hold_cools_down_after_a_trade() {
+        let now = 1_700_000_000;
+        let hold = |last_trade_time| Rules {
+            current_time: Some(now),
+            min_hold: Some(market::MinHold { last_trade_time, min_hold_secs: 3600 }),
+            ..rules(SignalMode::Threshold)
+        };
+        let cooling = decide(&record(-1000), &hold(now - 3599), None, None);
+        assert_eq!((cooling.signal, cooling.hold_reason), (Signal::Hold, Some(HoldReason::MinHold)));
+        assert_eq!(cooling.position_size, 0);
+        // Exactly min_hold_secs later trades again, as does a first trade
+        assert_eq!(decide(&record(-1000), &hold(now - 3600), None, None).signal, Signal::Buy);
+        assert_eq!(decide(&record(400), &hold(market::NO_LAST_TRADE), None, None).signal, Signal::Sell);
+        // A HOLD stays what it was, and a last trade in the future still cools down
+        assert_eq!(decide(&record(0), &hold(now - 60), None, None).hold_reason, Some(HoldReason::NeutralBand));
+        assert_eq!(decide(&record(-1000), &hold(now + 60), None, None).hold_reason, Some(HoldReason::MinHold));
+        // No current time: the cooldown can't be shown to be over
+        let unclocked = Rules { current_time: None, ..hold(now - 7200) };
+        assert_eq!(decide(&record(-1000), &unclocked, None, None).hold_reason, Some(HoldReason::MinHold));
+    }
+
+    #[test]
+    fn implausible_change_holds() {
+        let unbounded = rules(SignalMode::Threshold);
+        let rules = Rules { max_change_bps: Some(50_000), ..unbounded };
+        // +1000% is a SELL without the bound, a data-sanity HOLD beyond it
+        assert_eq!(decide(&record(100_000), &unbounded, None, None).signal, Signal::Sell);
+        let glitch = decide(&record(100_000), &rules, None, None);
+        assert_eq!((glitch.signal, glitch.hold_reason), (Signal::Hold, Some(HoldReason::DataSanity)));
+        // Either sign, and the bound itself is still plausible
+        assert_eq!(decide(&record(-50_001), &rules, None, None).hold_reason, Some(HoldReason::DataSanity));
+        assert_eq!(decide(&record(50_000), &rules, None, None).signal, Signal::Sell);
+        // A missing change has nothing to check
+        let missing = Record { change_24h: market::CHANGE_UNAVAILABLE, ..record(0) };
+        assert_eq!(decide(&missing, &rules, None, None).hold_reason, Some(HoldReason::MissingData));
+    }
+
+    #[test]
+    fn bollinger_breakout() {
+        // A flat window is a squeeze with zero width, and the close sits on
+        // the bands
+        let mut closes = vec![10_000; indicators::BOLLINGER_PERIOD];
+        let rules = rules(SignalMode::Bollinger);
+        let flat = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(flat.hold_reason, Some(HoldReason::NeutralBand));
+        assert_eq!((flat.band_width_bps, flat.squeeze), (Some(0), Some(true)));
+
+        // Middle 10005, stddev isqrt(475) = 21, so the bands are 10005 +/- 42
+        *closes.last_mut().unwrap() = 10_100;
+        let up = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(up.signal, Signal::Buy);
+        assert_eq!(up.band_width_bps, Some(83));
+
+        *closes.last_mut().unwrap() = 9_900;
+        assert_eq!(decide(&record(0), &rules, Some(&closes), None).signal, Signal::Sell);
+
+        let short = decide(&record(0), &rules, Some(&closes[1..]), None);
+        assert_eq!(short.hold_reason, Some(HoldReason::MissingData));
+        assert_eq!(short.outputs()[layout::RUN_SQUEEZE], indicators::BANDS_UNAVAILABLE);
+    }
+
+    #[test]
+    fn macd_crossover() {
+        // A long decline keeps MACD below its signal line; a jump on the last
+        // close pushes it above
+        let mut closes: Vec<u64> = (0..40).map(|i| 20_000 - i * 100).collect();
+        let rules = rules(SignalMode::Macd);
+        let falling = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(falling.hold_reason, Some(HoldReason::NeutralBand));
+
+        *closes.last_mut().unwrap() = 25_000;
+        let buy = decide(&record(0), &rules, Some(&closes), None);
+        assert_eq!(buy.signal, Signal::Buy);
+        assert!(buy.macd_histogram.unwrap() > 0);
+
+        let short = decide(&record(0), &rules, Some(&closes[..indicators::MACD_MIN_CLOSES]), None);
+        assert_eq!(short.hold_reason, Some(HoldReason::MissingData));
+        assert!(short.macd_histogram.is_some());
+    }
+
+    #[test]
+    fn position_sizing() {
+        // $10,000.00 at 2%: $200 at LOW risk, $100 at MEDIUM, $50 at HIGH
+        let position = Position { balance: 1_000_000, risk_fraction: 200 };
+        assert_eq!(position_size(&position, Signal::Buy, RiskLevel::Low), Some(20_000));
+        assert_eq!(position_size(&position, Signal::Sell, RiskLevel::Medium), Some(10_000));
+        assert_eq!(position_size(&position, Signal::Buy, RiskLevel::High), Some(5_000));
+        assert_eq!(position_size(&position, Signal::Hold, RiskLevel::Low), Some(0));
+
+        let mut rules = rules(SignalMode::Threshold);
+        assert_eq!(decide(&record(-600), &rules, None, None).position_size, 0);
+        rules.position = Some(position);
+        let decision = decide(&record(-600), &rules, None, None);
+        assert_eq!(decision.position_size, 20_000);
+        assert_eq!(decision.outputs()[layout::RUN_POSITION_LO..=layout::RUN_POSITION_HI], [20_000, 0]);
+    }
+
+    #[test]
+    fn exit_levels_widen_with_risk() {
+        let levels = Levels { stop_bps: 200, target_bps: 600 };
+        assert_eq!(exit_levels(&levels, 10_000, RiskLevel::Low, Direction::Long), Some((9_800, 10_600)));
+        assert_eq!(exit_levels(&levels, 10_000, RiskLevel::Medium, Direction::Long), Some((9_600, 10_600)));
+        assert_eq!(exit_levels(&levels, 10_000, RiskLevel::High, Direction::Long), Some((9_400, 10_600)));
+        assert_eq!(exit_levels(&levels, 10_000, RiskLevel::High, Direction::Short), Some((10_600, 9_400)));
+
+        // A stop wider than the price floors at zero
+        let wide = Levels { stop_bps: 5_000, target_bps: 0 };
+        assert_eq!(exit_levels(&wide, 10_000, RiskLevel::High, Direction::Long), Some((0, 10_000)));
+
+        let decision = decide(&record(0), &rules(SignalMode::Threshold), None, None);
+        assert_eq!((decision.stop_loss, decision.take_profit), (245_011, 265_012));
+        let unset = Rules { levels: None, ..rules(SignalMode::Threshold) };
+        let decision = decide(&record(0), &unset, None, None);
+        assert_eq!((decision.stop_loss, decision.take_profit), (0, 0));
+    }
+
+    #[test]
+    fn u64_boundaries() {
+        // 100% of the largest balance fits; anything more doesn't
+        let whole = 10u64.pow(market::RISK_FRACTION_DECIMALS);
+        let all_in = Position { balance: u64::MAX, risk_fraction: whole };
+        assert_eq!(position_size(&all_in, Signal::Buy, RiskLevel::Low), Some(u64::MAX));
+        let over = Position { risk_fraction: whole + 1, ..all_in };
+        assert_eq!(position_size(&over, Signal::Buy, RiskLevel::Low), None);
+        // ...unless the risk divisor brings it back down
+        assert_eq!(position_size(&over, Signal::Buy, RiskLevel::High), Some(4_612_147_187_029_230_642));
+
+        let flat = Levels { stop_bps: 0, target_bps: 0 };
+        assert_eq!(exit_levels(&flat, u64::MAX, RiskLevel::High, Direction::Long), Some((u64::MAX, u64::MAX)));
+        let above = Levels { stop_bps: 0, target_bps: 1 };
+        assert_eq!(exit_levels(&above, u64::MAX, RiskLevel::Low, Direction::Long), None);
+        let huge = Levels { stop_bps: u64::MAX, target_bps: u64::MAX };
+        assert_eq!(exit_levels(&huge, 10_000, RiskLevel::High, Direction::Short), None);
+
+        // A BUY whose take-profit can't be represented HOLDs instead
+        let top = Record { price: u64::MAX, ..record(-600) };
+        let decision = decide(&top, &rules(SignalMode::Threshold), None, None);
+        assert_eq!(decision.hold_reason, Some(HoldReason::Overflow));
+        assert_eq!((decision.stop_loss, decision.take_profit), (u64::MAX, u64::MAX));
+
+        let sized = Rules { position: Some(over), ..rules(SignalMode::Threshold) };
+        let decision = decide(&record(-600), &sized, None, None);
+        assert_eq!((decision.hold_reason, decision.position_size), (Some(HoldReason::Overflow), 0));
+
+        let late = Record { timestamp: u32::MAX as u64 + 1, ..record(-600) };
+        let decision = decide(&late, &rules(SignalMode::Threshold), None, None);
+        assert_eq!(decision.hold_reason, Some(HoldReason::Overflow));
+        assert_eq!(decision.outputs()[layout::RUN_TIMESTAMP], u32::MAX);
+
+        // Returns between 1 cent and u64::MAX cents overflow the variance sums
+        let wild: Vec<u64> = (0..=indicators::VOLATILITY_PERIOD).map(|i| if i % 2 == 0 { 1 } else { u64::MAX }).collect();
+        let decision = decide(&record(0), &rules(SignalMode::Threshold), Some(&wild), None);
+        assert_eq!(decision.volatility_bps, Some(u64::MAX));
+        assert_eq!(decision.hold_reason, Some(HoldReason::Overflow));
+
+        // BTC-sized closes at 8 decimals put the MACD histogram past an i32,
+        // which only the MACD mode decides on
+        let btc: Vec<u64> = (0..40u64).map(|i| 6_000_000_000_000 + i * i * 10_000_000_000).collect();
+        assert!(indicators::macd(&btc).is_some_and(|m| m.checked_histogram_x100().is_none()));
+        let decision = decide(&record(-800), &rules(SignalMode::Threshold), Some(&btc), None);
+        assert_eq!((decision.signal, decision.hold_reason), (Signal::Buy, None));
+        let decision = decide(&record(-800), &rules(SignalMode::Rsi), Some(&btc), None);
+        assert_ne!(decision.hold_reason, Some(HoldReason::Overflow));
+        let decision = decide(&record(-800), &rules(SignalMode::Macd), Some(&btc), None);
+        assert_eq!(decision.hold_reason, Some(HoldReason::Overflow));
+    }
+
+    #[test]
+    fn i64_boundaries() {
+        let rules = rules(SignalMode::Threshold);
+        let top = decide(&record(i64::MAX), &rules, None, None);
+        assert_eq!((top.signal, top.risk), (Signal::Sell, RiskLevel::High));
+        let bottom = decide(&record(i64::MIN + 1), &rules, None, None);
+        assert_eq!((bottom.signal, bottom.risk), (Signal::Buy, RiskLevel::High));
+
+        // No change is past the widest margin, so it holds every reversal
+        let wide = Rules { hysteresis_bps: Some(u64::MAX), ..rules };
+        let held = decide(&record(i64::MAX), &wide, None, Some(Signal::Buy));
+        assert_eq!(held.hold_reason, Some(HoldReason::Hysteresis));
+        let held = decide(&record(i64::MIN + 1), &wide, None, Some(Signal::Sell));
+        assert_eq!(held.hold_reason, Some(HoldReason::Hysteresis));
+
+        // An EMA jump from 1 cent to u64::MAX is beyond an i64 of bps
+        let ema = Rules { price_basis: PriceBasis::Ema, ema_period: 3, ..rules };
+        let decision = decide(&record(0), &ema, Some(&[1, 1, 1, u64::MAX]), None);
+        assert_eq!(decision.hold_reason, Some(HoldReason::Overflow));
+        assert!(decision.smoothed_price.is_some());
+    }
+
+    #[test]
+    fn derived_fields() {
+        let decision = decide(&record(0), &rules(SignalMode::Threshold), None, None);
+        assert_eq!(decision.outputs()[layout::RUN_SMOOTHED_PRICE], indicators::EMA_UNAVAILABLE);
+        assert_eq!(market::join_u64(decision.price_lo, decision.price_hi), 250_012);
+        assert_eq!(market::join_u64(decision.volume_lo, decision.volume_hi), 5_000_000);
+        assert_eq!(decision.outputs()[layout::RUN_RSI], indicators::RSI_UNAVAILABLE);
+    }
+}
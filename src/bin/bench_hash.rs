@@ -0,0 +1,49 @@
+// Host-side benchmark: runs the guest's hash chain natively for a range of
+// n, to estimate how a large n will scale before proving it.
+//
+// cargo run --release --bin bench_hash -- [--n 1000,10000,100000] [--algorithm sha256]
+//
+// Native timings are far faster than proving; use the ratios between rows,
+// not the absolute numbers.
+
+use std::error::Error;
+use std::time::Instant;
+
+use clap::Parser;
+use sha_hasher::hasher::{hash_chain, Algorithm};
+
+#[derive(Parser)]
+#[command(about = "Time the guest's hash chain natively for several n")]
+struct Args {
+    /// Round counts to time, comma-separated
+    #[arg(long, value_delimiter = ',', default_values_t = [1_000, 10_000, 100_000, 1_000_000])]
+    n: Vec<u64>,
+    /// Hash algorithm: sha256 or keccak256
+    #[arg(long, default_value = "sha256")]
+    algorithm: String,
+    /// Length of the all-0xff secret, as in build.rs
+    #[arg(long, default_value_t = 32)]
+    secret_len: usize,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let algorithm = Algorithm::from_name(&args.algorithm)
+        .ok_or_else(|| format!("unknown algorithm {:?}", args.algorithm))?;
+    let hasher = algorithm.hasher();
+    let secret = vec![0xffu8; args.secret_len];
+
+    println!("{} with a {}-byte secret", algorithm.name(), args.secret_len);
+    println!("{:>12} {:>14} {:>14}", "n", "elapsed (ms)", "hashes/sec");
+    for &n in &args.n {
+        let start = Instant::now();
+        let hash = hash_chain(hasher, &secret, n, |_, _| {});
+        let elapsed = start.elapsed();
+        // Keep the result observable so the loop isn't optimized away
+        std::hint::black_box(hash);
+
+        let rate = n as f64 / elapsed.as_secs_f64();
+        println!("{:>12} {:>14.1} {:>14.0}", n, elapsed.as_secs_f64() * 1000.0, rate);
+    }
+    Ok(())
+}
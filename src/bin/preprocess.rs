@@ -0,0 +1,375 @@
+// Host-side tool: turns input.json into build/input.bin and public.json.
+//
+// cargo run --bin preprocess -- [--input input.json] [--bin build/input.bin] [--public public.json]
+// cargo run --bin preprocess -- --check [--input input.json]   (validate only)
+// cat input.json | cargo run --bin preprocess -- -          (or --stdin)
+// cargo run --bin preprocess -- --format hex [--stdout-only]   (also print input.bin)
+// cargo run --bin preprocess -- -v   (log each step to stderr; or RUST_LOG=debug)
+// cargo run --bin preprocess -- --pad-byte [0x20|pkcs7]   (pad short secrets to 32 bytes)
+// cargo run --bin preprocess -- --compress   (write build/input.bin.gz instead)
+// cargo run --bin preprocess -- --secret-file secret.txt   (or ZKMD_SECRET=...; overrides private.secret)
+//
+// input.json holds one Input, or an array of them; an array produces
+// numbered files (build/input_0.bin, public_0.json, ...).
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use log::{debug, trace};
+use serde_json::Value;
+use sha_hasher::secret::{self, Padding};
+use sha_hasher::{
+    codec, crc32, header, input_gen, parse_input, serialize_input, validate_input, Input, ValidationError,
+};
+
+#[derive(Parser)]
+#[command(about = "Serialize input.json into the guest's input.bin")]
+struct Args {
+    /// Input file, or - to read stdin (same as --input)
+    #[arg(value_name = "INPUT", conflicts_with = "input")]
+    source: Option<PathBuf>,
+    /// JSON file with the public and private inputs, or - for stdin
+    #[arg(long, default_value = "input.json")]
+    input: PathBuf,
+    /// Read the JSON from stdin, ignoring --input
+    #[arg(long)]
+    stdin: bool,
+    /// Where to write the serialized guest input
+    #[arg(long, default_value = "build/input.bin")]
+    bin: PathBuf,
+    /// Where to write the public inputs
+    #[arg(long, default_value = "public.json")]
+    public: PathBuf,
+    /// Validate the input and print the layout, without writing anything
+    #[arg(long)]
+    check: bool,
+    /// Largest n to accept
+    #[arg(long, default_value_t = sha_hasher::DEFAULT_MAX_N)]
+    max_n: u64,
+    /// Also print input.bin to stdout in this encoding (raw: don't print)
+    #[arg(long, value_enum, default_value_t = Format::Raw)]
+    format: Format,
+    /// Print input.bin to stdout instead of writing any file
+    #[arg(long)]
+    stdout_only: bool,
+    /// Pad secrets shorter than 32 bytes with this byte (decimal or 0x hex), or
+    /// pkcs7; a bare --pad-byte fills with 0. Overrides public.secret_padding
+    #[arg(long, value_name = "BYTE", num_args = 0..=1, default_missing_value = "0", value_parser = parse_padding)]
+    pad_byte: Option<Padding>,
+    /// Gzip input.bin and write it with a .gz suffix (build/input.bin.gz)
+    #[arg(long)]
+    compress: bool,
+    /// Read the secret from this file instead of private.secret (one trailing
+    /// newline is dropped); takes precedence over ZKMD_SECRET
+    #[arg(long, value_name = "PATH")]
+    secret_file: Option<PathBuf>,
+    /// Log parsed fields, byte offsets and each step to stderr (-vv: also the bytes)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Raw,
+    Hex,
+    Base64,
+}
+
+// Exit status when input.json fails validation (1 is any other error).
+const EXIT_INVALID: u8 = 2;
+
+// Every validation failure, reported together; in a batch each one is
+// tagged with the index of its input.
+#[derive(Debug)]
+struct Invalid(Vec<(Option<usize>, ValidationError)>);
+
+impl std::fmt::Display for Invalid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} validation error(s)", self.0.len())?;
+        for (index, e) in &self.0 {
+            match index {
+                Some(i) => write!(f, "\n  - input {}: {}", i, e)?,
+                None => write!(f, "\n  - {}", e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for Invalid {}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    init_logging(args.verbose);
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("preprocess: {}", e);
+            if e.is::<Invalid>() {
+                ExitCode::from(EXIT_INVALID)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+// The log goes to stderr, so stdout only ever carries results. RUST_LOG
+// sets the filter (warnings only by default) unless -v overrides it.
+fn init_logging(verbose: u8) {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"));
+    match verbose {
+        0 => {}
+        1 => {
+            builder.filter_level(log::LevelFilter::Debug);
+        }
+        _ => {
+            builder.filter_level(log::LevelFilter::Trace);
+        }
+    }
+    builder.init();
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let path = args.source.as_ref().unwrap_or(&args.input);
+    let (name, contents) = if args.stdin || path == Path::new("-") {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents).map_err(|e| format!("cannot read stdin: {}", e))?;
+        ("stdin".to_string(), contents)
+    } else {
+        if !path.is_file() {
+            return Err(format!("input file {} does not exist", path.display()).into());
+        }
+        let contents = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        (path.display().to_string(), contents)
+    };
+    debug!("read {} bytes from {}", contents.len(), name);
+    let json: Value = serde_json::from_str(&contents).map_err(|e| format!("{} is not valid JSON: {}", name, e))?;
+    let mut inputs: Vec<(Option<usize>, Input)> = match json {
+        Value::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let input = serde_json::from_value(item)
+                    .map_err(|e| format!("{} element {} is not a valid input: {}", name, i, e))?;
+                Ok((Some(i), input))
+            })
+            .collect::<Result<_, String>>()?,
+        single => {
+            let input = serde_json::from_value(single).map_err(|e| format!("{} is not a valid input: {}", name, e))?;
+            vec![(None, input)]
+        }
+    };
+    if inputs.is_empty() {
+        return Err(format!("{} is an empty array", name).into());
+    }
+    debug!("{}: {} input(s)", name, inputs.len());
+    if let Some(padding) = args.pad_byte {
+        for (_, input) in &mut inputs {
+            input.public.secret_padding = padding;
+        }
+    }
+    if let Some(secret) = secret_override(args)? {
+        for (_, input) in &mut inputs {
+            input.private.secret = secret.clone();
+        }
+    }
+
+    // Nothing is written unless every input in the batch is valid
+    let errors: Vec<_> = inputs
+        .iter()
+        .flat_map(|(index, input)| validate_input(input, args.max_n).into_iter().map(move |e| (*index, e)))
+        .collect();
+    if !errors.is_empty() {
+        return Err(Box::new(Invalid(errors)));
+    }
+
+    for (index, input) in &inputs {
+        let mut bin_path = numbered(&args.bin, *index);
+        if args.compress {
+            bin_path.as_mut_os_string().push(input_gen::GZIP_SUFFIX);
+        }
+        let public_path = numbered(&args.public, *index);
+        write_input(args, input, &bin_path, &public_path)?;
+    }
+    Ok(())
+}
+
+// The secret from --secret-file or ZKMD_SECRET, which then replaces
+// private.secret in every input (and is validated like it), so a real
+// secret never has to sit in a version-controlled input.json. Only where it
+// came from is logged, never the value.
+fn secret_override(args: &Args) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(path) = &args.secret_file {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read secret file {}: {}", path.display(), e))?;
+        // The newline an editor or `echo` ends the file with isn't part of it
+        let secret = match contents.strip_suffix('\n') {
+            Some(line) => line.strip_suffix('\r').unwrap_or(line),
+            None => &contents,
+        };
+        debug!("secret: {} bytes from {}", secret.len(), path.display());
+        return Ok(Some(secret.to_string()));
+    }
+    match env::var("ZKMD_SECRET") {
+        Ok(secret) => {
+            debug!("secret: {} bytes from ZKMD_SECRET", secret.len());
+            Ok(Some(secret))
+        }
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err("ZKMD_SECRET is not valid UTF-8".into()),
+    }
+}
+
+// `path` with `_<index>` before its extension: build/input.bin ->
+// build/input_3.bin. Unchanged for a single input.
+fn numbered(path: &Path, index: Option<usize>) -> PathBuf {
+    let Some(i) = index else {
+        return path.to_path_buf();
+    };
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, i, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, i),
+    };
+    path.with_file_name(name)
+}
+
+fn write_input(args: &Args, input: &Input, bin_path: &Path, public_path: &Path) -> Result<(), Box<dyn Error>> {
+    debug!(
+        "{}: n = {}, algorithm = {}, checkpoint interval = {}, secret {} bytes, padding {}",
+        bin_path.display(),
+        input.public.n,
+        input.public.algorithm.name(),
+        input.public.checkpoint_interval,
+        input.private.secret.len(),
+        input.public.secret_padding.name()
+    );
+    let bin = serialize_input(input)?;
+    for (start, end, what) in layout(input, &bin) {
+        debug!("{:<12} {}", format!("[{}..{}]", start, end), what);
+    }
+    trace!("input.bin: {}", redacted_hex(input, &bin));
+
+    if args.check {
+        // Read it back the way the guest will, so a bad layout fails here
+        parse_input(&bin).map_err(|e| format!("serialized input does not parse: {}", e))?;
+        print_layout(input, &bin);
+        println!("OK: would write {} and {}", bin_path.display(), public_path.display());
+        return Ok(());
+    }
+
+    debug!("format {:?}, stdout only: {}", args.format, args.stdout_only);
+    match args.format {
+        Format::Raw if args.stdout_only => io::stdout().write_all(&bin)?,
+        Format::Raw => {}
+        Format::Hex => println!("{}", codec::hex(&bin)),
+        Format::Base64 => println!("{}", base64(&bin)),
+    }
+    if args.stdout_only {
+        return Ok(());
+    }
+
+    let contents = if args.compress { input_gen::gzip(&bin)? } else { bin.clone() };
+    write_file(bin_path, &contents)?;
+
+    // Only the public half is published; the secret stays in input.bin
+    write_file(public_path, serde_json::to_string_pretty(&input.public)?.as_bytes())?;
+
+    // Keep stdout to the encoded input when there is one
+    let report = format!(
+        "Wrote {} ({} bytes) and {} (n = {}, {})",
+        bin_path.display(),
+        contents.len(),
+        public_path.display(),
+        input.public.n,
+        input.public.algorithm.name()
+    );
+    if args.format == Format::Raw {
+        println!("{}", report);
+    } else {
+        eprintln!("{}", report);
+    }
+    Ok(())
+}
+
+fn parse_padding(name: &str) -> Result<Padding, String> {
+    Padding::try_from(name.to_string())
+}
+
+// `bin` in hex with the secret's bytes shown as `**`, for the log.
+fn redacted_hex(input: &Input, bin: &[u8]) -> String {
+    let secret = secret_range(input);
+    bin.iter()
+        .enumerate()
+        .map(|(i, b)| if secret.contains(&i) { "**".to_string() } else { codec::hex(&[*b]) })
+        .collect()
+}
+
+// Standard base64 (RFC 4648) with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn print_layout(input: &Input, bin: &[u8]) {
+    for (start, end, what) in layout(input, bin) {
+        println!("{:<12} {}", format!("[{}..{}]", start, end), what);
+    }
+    println!("total {} bytes", bin.len());
+}
+
+// Where the (padded) secret's bytes are in input.bin.
+fn secret_range(input: &Input) -> Range<usize> {
+    let secret_len = secret::pad(input.private.secret.as_bytes(), input.public.secret_padding).len();
+    let secret_at = header::HEADER_LEN + sha_hasher::PUBLIC_LEN + 2;
+    secret_at..secret_at + secret_len
+}
+
+// Byte ranges of `bin` with what each holds, in order.
+fn layout(input: &Input, bin: &[u8]) -> Vec<(usize, usize, String)> {
+    let n_at = header::HEADER_LEN;
+    let algorithm_at = n_at + 8;
+    let checkpoint_at = algorithm_at + 1;
+    let Range { start: secret_at, end: crc_at } = secret_range(input);
+    let secret_len = crc_at - secret_at;
+    let crc = codec::read_u32_le(&bin[crc_at..]);
+    vec![
+        (0, n_at, format!("header (magic ZKMD, version {})", header::FORMAT_VERSION)),
+        (n_at, n_at + 8, format!("n = {}", input.public.n)),
+        (algorithm_at, algorithm_at + 1, format!("algorithm = {}", input.public.algorithm.name())),
+        (checkpoint_at, checkpoint_at + 8, format!("checkpoint interval = {}", input.public.checkpoint_interval)),
+        (checkpoint_at + 8, secret_at, format!("secret length = {}", secret_len)),
+        (secret_at, crc_at, format!("secret (private), padding {}", input.public.secret_padding.name())),
+        (crc_at, crc_at + crc32::CRC_LEN, format!("CRC-32 = {:#010x}", crc)),
+    ]
+}
+
+// Writes `contents` to `path` atomically, creating its parent directory if
+// needed.
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    input_gen::write_atomic(path, contents).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    debug!("wrote {} bytes to {}", contents.len(), path.display());
+    Ok(())
+}
@@ -0,0 +1,154 @@
+// Byte order, in one place. Everything in input.bin (header, fields,
+// records, CRC trailer) is little-endian. The 32-byte hashes the guests
+// publish are the exception: they are split into big-endian u32 chunks, so
+// the output slots read in order as the usual hex digest. Encodings meant
+// for other chains and tools (write_u64_be) are big-endian as well.
+//
+// The readers take their value from the start of `bytes` and panic when it
+// is shorter than that; callers check lengths first, as they must anyway to
+// reject a malformed input with its own error.
+//
+// Digests, secrets and messages outside the binary formats are lowercase
+// hex, two digits per byte (hex, unhex, digest_from_hex).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub fn read_u16_le(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes(first(bytes))
+}
+
+pub fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(first(bytes))
+}
+
+pub fn read_u64_le(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(first(bytes))
+}
+
+pub fn read_i64_le(bytes: &[u8]) -> i64 {
+    i64::from_le_bytes(first(bytes))
+}
+
+pub fn write_u16_le(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u32_le(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u64_le(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_i64_le(out: &mut Vec<u8>, value: i64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u64_be(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+// Passes `hash` to `write` as eight big-endian u32 chunks, with their index
+// (0..8), e.g. straight to ziskos::set_output.
+pub fn write_u32_be_chunks(hash: &[u8; 32], mut write: impl FnMut(usize, u32)) {
+    for (i, chunk) in hash.chunks_exact(4).enumerate() {
+        write(i, u32::from_be_bytes(first(chunk)));
+    }
+}
+
+// The hash back from its eight chunks, as write_u32_be_chunks wrote them.
+pub fn read_u32_be_chunks(words: &[u32; 8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(words) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    hash
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    bytes.iter().flat_map(|b| [DIGITS[(b >> 4) as usize] as char, DIGITS[(b & 0xf) as usize] as char]).collect()
+}
+
+// The bytes `text` spells in hex, in either case and with an optional 0x
+// prefix; None unless it is an even number of hex digits.
+pub fn unhex(text: &str) -> Option<Vec<u8>> {
+    from_digits(text.strip_prefix("0x").unwrap_or(text))
+}
+
+// A 32-byte digest from exactly 64 hex digits, as hex writes it (no prefix).
+pub fn digest_from_hex(text: &str) -> Option<[u8; 32]> {
+    from_digits(text)?.try_into().ok()
+}
+
+fn from_digits(digits: &str) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((0..digits.len()).step_by(2).map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap()).collect())
+}
+
+fn first<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes[..N].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn little_endian_round_trips() {
+        let mut out = Vec::new();
+        write_u16_le(&mut out, 0xBEEF);
+        write_u32_le(&mut out, 0xDEAD_BEEF);
+        write_u64_le(&mut out, u64::MAX - 1);
+        write_i64_le(&mut out, i64::MIN);
+        assert_eq!(&out[..6], [0xEF, 0xBE, 0xEF, 0xBE, 0xAD, 0xDE]);
+        assert_eq!(out.len(), 2 + 4 + 8 + 8);
+
+        assert_eq!(read_u16_le(&out), 0xBEEF);
+        assert_eq!(read_u32_le(&out[2..]), 0xDEAD_BEEF);
+        assert_eq!(read_u64_le(&out[6..]), u64::MAX - 1);
+        assert_eq!(read_i64_le(&out[14..]), i64::MIN);
+        // Only the first bytes count
+        assert_eq!(read_u16_le(&[1, 0, 0xFF]), 1);
+    }
+
+    #[test]
+    fn write_u64_be_is_big_endian() {
+        let mut out = vec![0xAA];
+        write_u64_be(&mut out, 0x0102_0304_0506_0708);
+        assert_eq!(out, [0xAA, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn chunks_read_in_hash_order() {
+        let hash: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let mut words = [0u32; 8];
+        write_u32_be_chunks(&hash, |i, word| words[i] = word);
+        assert_eq!(words[0], 0x0001_0203);
+        assert_eq!(words[7], 0x1C1D_1E1F);
+        assert_eq!(read_u32_be_chunks(&words), hash);
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let bytes = [0x00, 0x0F, 0xA5, 0xFF];
+        assert_eq!(hex(&bytes), "000fa5ff");
+        assert_eq!(hex(&[]), "");
+        assert_eq!(unhex("000fa5ff").as_deref(), Some(&bytes[..]));
+        assert_eq!(unhex("0x000FA5FF").as_deref(), Some(&bytes[..]));
+        assert_eq!(unhex(""), Some(Vec::new()));
+        for bad in ["0", "0x0", "zz", "00 11", "+1"] {
+            assert_eq!(unhex(bad), None, "{:?}", bad);
+        }
+
+        let digest: [u8; 32] = core::array::from_fn(|i| (i * 7) as u8);
+        assert_eq!(digest_from_hex(&hex(&digest)), Some(digest));
+        // Exactly 32 bytes, and no prefix
+        assert_eq!(digest_from_hex(&hex(&digest[..31])), None);
+        assert_eq!(digest_from_hex(&format!("0x{}", hex(&digest))), None);
+    }
+}
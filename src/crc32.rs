@@ -0,0 +1,81 @@
+// CRC-32 (IEEE 802.3, reflected, poly 0xEDB88320) trailer for input.bin.
+//
+// The last 4 bytes of input.bin are the CRC of every preceding byte,
+// header included, stored little-endian. Bitwise implementation so the
+// guest doesn't need a table or an external crate.
+
+use alloc::vec::Vec;
+
+use super::codec;
+
+pub const CRC_LEN: usize = 4;
+
+// Value published in output slot 0 when the trailer doesn't match.
+pub const ERR_BAD_CRC: u32 = 0xE000_0004;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrcError {
+    Truncated(usize),
+    Mismatch { expected: u32, actual: u32 },
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+pub fn append_crc(out: &mut Vec<u8>) {
+    let crc = crc32(out);
+    codec::write_u32_le(out, crc);
+}
+
+// Verifies the trailer and returns everything before it.
+pub fn strip_crc(input: &[u8]) -> Result<&[u8], CrcError> {
+    if input.len() < CRC_LEN {
+        return Err(CrcError::Truncated(input.len()));
+    }
+    let (body, trailer) = input.split_at(input.len() - CRC_LEN);
+    let expected = codec::read_u32_le(trailer);
+    let actual = crc32(body);
+    if expected != actual {
+        return Err(CrcError::Mismatch { expected, actual });
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_answer() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn strip_crc_checks_the_trailer() {
+        let mut framed = b"payload".to_vec();
+        append_crc(&mut framed);
+        assert_eq!(framed.len(), 7 + CRC_LEN);
+        assert_eq!(&framed[7..], crc32(b"payload").to_le_bytes());
+        assert_eq!(strip_crc(&framed), Ok(&b"payload"[..]));
+        // Just a trailer frames an empty body
+        assert_eq!(strip_crc(&crc32(b"").to_le_bytes()), Ok(&[][..]));
+
+        let mut flipped = framed.clone();
+        flipped[0] ^= 1;
+        let actual = crc32(&flipped[..7]);
+        assert_eq!(strip_crc(&flipped), Err(CrcError::Mismatch { expected: crc32(b"payload"), actual }));
+        for len in 0..CRC_LEN {
+            assert_eq!(strip_crc(&framed[..len]), Err(CrcError::Truncated(len)));
+        }
+    }
+}
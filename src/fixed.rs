@@ -0,0 +1,111 @@
+// Decimal fixed-point conversions for values written into input.bin.
+//
+// A value with `decimals` places is stored as round(value * 10^decimals),
+// e.g. $1234.567 with 2 decimals is 123457 (cents). Conversions reject
+// NaN/infinite input and values that don't fit the target integer.
+
+pub const MAX_DECIMALS: u32 = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixedError {
+    NotFinite(f64),
+    Negative(f64),
+    Overflow(f64),
+    TooManyDecimals(u32),
+}
+
+impl std::fmt::Display for FixedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixedError::NotFinite(v) => write!(f, "{} is not a finite number", v),
+            FixedError::Negative(v) => write!(f, "{} is negative", v),
+            FixedError::Overflow(v) => write!(f, "{} does not fit in 64 bits", v),
+            FixedError::TooManyDecimals(d) => {
+                write!(f, "{} decimals requested, maximum is {}", d, MAX_DECIMALS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixedError {}
+
+fn scale(value: f64, decimals: u32) -> Result<f64, FixedError> {
+    if decimals > MAX_DECIMALS {
+        return Err(FixedError::TooManyDecimals(decimals));
+    }
+    if !value.is_finite() {
+        return Err(FixedError::NotFinite(value));
+    }
+    Ok((value * 10f64.powi(decimals as i32)).round())
+}
+
+pub fn to_fixed(value: f64, decimals: u32) -> Result<u64, FixedError> {
+    let scaled = scale(value, decimals)?;
+    if scaled < 0.0 {
+        return Err(FixedError::Negative(value));
+    }
+    // u64::MAX as f64 rounds up to 2^64, so anything >= it overflows
+    if scaled >= u64::MAX as f64 {
+        return Err(FixedError::Overflow(value));
+    }
+    Ok(scaled as u64)
+}
+
+pub fn to_fixed_signed(value: f64, decimals: u32) -> Result<i64, FixedError> {
+    let scaled = scale(value, decimals)?;
+    if scaled < i64::MIN as f64 || scaled >= i64::MAX as f64 {
+        return Err(FixedError::Overflow(value));
+    }
+    Ok(scaled as i64)
+}
+
+pub fn from_fixed(value: u64, decimals: u32) -> f64 {
+    value as f64 / 10f64.powi(decimals as i32)
+}
+
+pub fn from_fixed_signed(value: i64, decimals: u32) -> f64 {
+    value as f64 / 10f64.powi(decimals as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_nearest_away_from_zero() {
+        assert_eq!(to_fixed(1234.567, 2), Ok(123457));
+        assert_eq!(to_fixed(0.125, 2), Ok(13));
+        assert_eq!(to_fixed(2.5, 0), Ok(3));
+        assert_eq!(to_fixed_signed(-0.125, 2), Ok(-13));
+        assert_eq!(to_fixed_signed(-2.5, 0), Ok(-3));
+        assert_eq!(to_fixed(7.0, MAX_DECIMALS), Ok(7_000_000_000_000_000_000));
+        assert_eq!(from_fixed(123457, 2), 1234.57);
+        assert_eq!(from_fixed_signed(-13, 2), -0.13);
+    }
+
+    #[test]
+    fn negative_values() {
+        assert_eq!(to_fixed(-0.01, 2), Err(FixedError::Negative(-0.01)));
+        // Rounding to zero isn't negative
+        assert_eq!(to_fixed(-0.004, 2), Ok(0));
+        assert_eq!(to_fixed_signed(-1234.567, 2), Ok(-123457));
+    }
+
+    #[test]
+    fn overflow_boundaries() {
+        // 2^63 fits a u64 but not an i64
+        let two_63 = 2f64.powi(63);
+        assert_eq!(to_fixed(two_63, 0), Ok(1 << 63));
+        assert_eq!(to_fixed_signed(two_63, 0), Err(FixedError::Overflow(two_63)));
+        assert_eq!(to_fixed_signed(-two_63, 0), Ok(i64::MIN));
+        assert_eq!(to_fixed_signed(-2.0 * two_63, 0), Err(FixedError::Overflow(-2.0 * two_63)));
+        // u64::MAX as f64 is 2^64, one past the largest u64
+        let two_64 = u64::MAX as f64;
+        assert_eq!(to_fixed(two_64, 0), Err(FixedError::Overflow(two_64)));
+        assert_eq!(to_fixed(1e18, 2), Err(FixedError::Overflow(1e18)));
+
+        assert_eq!(to_fixed(1.0, MAX_DECIMALS + 1), Err(FixedError::TooManyDecimals(MAX_DECIMALS + 1)));
+        assert!(matches!(to_fixed(f64::NAN, 2), Err(FixedError::NotFinite(_))));
+        assert_eq!(to_fixed_signed(f64::NEG_INFINITY, 2), Err(FixedError::NotFinite(f64::NEG_INFINITY)));
+    }
+}
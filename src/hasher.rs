@@ -0,0 +1,145 @@
+// Hash functions the guest can iterate, selected by the algorithm byte in
+// input.bin.
+//
+// Both produce 32 bytes, so the round loop and the 8 output words are the
+// same whichever one is chosen.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+// Value published in output slot 0 when the algorithm byte is unknown.
+pub const ERR_BAD_ALGORITHM: u32 = 0xE000_0008;
+
+pub trait Hasher {
+    fn hash(&self, input: &[u8]) -> [u8; 32];
+}
+
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn hash(&self, input: &[u8]) -> [u8; 32] {
+        Sha256::digest(input).into()
+    }
+}
+
+// Keccak-256 as used by Ethereum (the original padding, not FIPS SHA3-256).
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    fn hash(&self, input: &[u8]) -> [u8; 32] {
+        Keccak256::digest(input).into()
+    }
+}
+
+// Algorithm byte in input.bin; SHA-256 unless input.json says otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    #[default]
+    Sha256 = 0,
+    Keccak256 = 1,
+}
+
+impl Algorithm {
+    pub fn from_byte(byte: u8) -> Option<Algorithm> {
+        match byte {
+            0 => Some(Algorithm::Sha256),
+            1 => Some(Algorithm::Keccak256),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "sha256" => Some(Algorithm::Sha256),
+            "keccak256" => Some(Algorithm::Keccak256),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Keccak256 => "keccak256",
+        }
+    }
+
+    pub fn hasher(self) -> &'static dyn Hasher {
+        match self {
+            Algorithm::Sha256 => &Sha256Hasher,
+            Algorithm::Keccak256 => &Keccak256Hasher,
+        }
+    }
+}
+
+// The guest's hash chain: the first round absorbs the variable-length
+// secret, each later round hashes the previous 32-byte digest. n == 0
+// returns the secret itself, zero-padded (or truncated) to 32 bytes.
+//
+// `on_round(done, hash)` runs after every round, for progress and
+// checkpoint output. The guest and the native benchmark both call this, so
+// they do exactly the same work per round.
+pub fn hash_chain(
+    hasher: &dyn Hasher,
+    secret: &[u8],
+    n: u64,
+    mut on_round: impl FnMut(u64, &[u8; 32]),
+) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    if n == 0 {
+        let len = secret.len().min(32);
+        hash[..len].copy_from_slice(&secret[..len]);
+        return hash;
+    }
+    hash = hasher.hash(secret);
+    on_round(1, &hash);
+    resume_chain(hasher, hash, 1, n, on_round)
+}
+
+// The rest of hash_chain from `hash`, the digest after round `done` (at
+// least 1), through round n; `on_round` sees rounds done + 1 onwards. A
+// resumed native run (resume.rs) continues through here, so it ends on the
+// same hash as an uninterrupted one.
+pub fn resume_chain(
+    hasher: &dyn Hasher,
+    mut hash: [u8; 32],
+    done: u64,
+    n: u64,
+    mut on_round: impl FnMut(u64, &[u8; 32]),
+) -> [u8; 32] {
+    for done in done + 1..=n {
+        hash = hasher.hash(&hash);
+        on_round(done, &hash);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::hex;
+
+    #[test]
+    fn known_answers() {
+        assert_eq!(
+            hex(&Keccak256Hasher.hash(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(hex(&Sha256Hasher.hash(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn chain_of_two_hashes_the_digest() {
+        for algorithm in [Algorithm::Sha256, Algorithm::Keccak256] {
+            let hasher = algorithm.hasher();
+            let mut rounds = Vec::new();
+            let hash = hash_chain(hasher, b"secret", 2, |done, hash| rounds.push((done, *hash)));
+            let first = hasher.hash(b"secret");
+            assert_eq!(hash, hasher.hash(&first), "{}", algorithm.name());
+            assert_eq!(rounds, [(1, first), (2, hash)]);
+            // Resuming after round 1 ends on the same hash
+            assert_eq!(resume_chain(hasher, first, 1, 2, |_, _| {}), hash);
+        }
+    }
+}
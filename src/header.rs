@@ -0,0 +1,90 @@
+// Versioned header shared by every producer and consumer of input.bin.
+//
+// Layout (8 bytes, prepended to the payload):
+//   [0..4] magic b"ZKMD"
+//   [4..6] format version (u16, little-endian)
+//   [6..8] reserved, always zero
+
+use alloc::vec::Vec;
+
+use super::codec;
+
+pub const MAGIC: [u8; 4] = *b"ZKMD";
+pub const FORMAT_VERSION: u16 = 8;
+pub const HEADER_LEN: usize = 8;
+
+// Values published in output slot 0 when the header check fails.
+pub const ERR_BAD_MAGIC: u32 = 0xE000_0001;
+pub const ERR_BAD_VERSION: u32 = 0xE000_0002;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    BadMagic,
+    BadVersion(u16),
+}
+
+impl HeaderError {
+    pub fn code(&self) -> u32 {
+        match self {
+            HeaderError::BadMagic => ERR_BAD_MAGIC,
+            HeaderError::BadVersion(_) => ERR_BAD_VERSION,
+        }
+    }
+}
+
+pub fn write_header(out: &mut Vec<u8>) {
+    out.extend_from_slice(&MAGIC);
+    codec::write_u16_le(out, FORMAT_VERSION);
+    out.extend_from_slice(&[0u8; 2]);
+}
+
+// Checks the header and returns the payload that follows it.
+pub fn read_header(input: &[u8]) -> Result<&[u8], HeaderError> {
+    if input.len() < HEADER_LEN || input[0..4] != MAGIC {
+        return Err(HeaderError::BadMagic);
+    }
+    let version = codec::read_u16_le(&input[4..]);
+    if version != FORMAT_VERSION {
+        return Err(HeaderError::BadVersion(version));
+    }
+    Ok(&input[HEADER_LEN..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Vec<u8> {
+        let mut out = Vec::new();
+        write_header(&mut out);
+        out
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut input = header();
+        assert_eq!(input.len(), HEADER_LEN);
+        assert_eq!(read_header(&input), Ok(&[][..]));
+        input.extend_from_slice(b"payload");
+        assert_eq!(read_header(&input), Ok(&b"payload"[..]));
+    }
+
+    #[test]
+    fn each_failure_has_its_code() {
+        let mut bad_magic = header();
+        bad_magic[0] = b'X';
+        assert_eq!(read_header(&bad_magic), Err(HeaderError::BadMagic));
+        assert_eq!(HeaderError::BadMagic.code(), ERR_BAD_MAGIC);
+
+        let mut bad_version = header();
+        bad_version[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let err = read_header(&bad_version).unwrap_err();
+        assert_eq!(err, HeaderError::BadVersion(FORMAT_VERSION + 1));
+        assert_eq!(err.code(), ERR_BAD_VERSION);
+
+        // Too short to hold a header reads as a bad magic
+        for len in 0..HEADER_LEN {
+            assert_eq!(read_header(&header()[..len]).map_err(|e| e.code()), Err(ERR_BAD_MAGIC), "{} bytes", len);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// The input.bin envelope both build scripts write, so the framing can't
+// drift between the SHA example and the market guest:
+//
+//   header (header.rs) | payload | CRC-32 (crc32.rs)
+//
+// Each guest owns its payload layout (lib.rs for the hash chain,
+// market.rs for market data) and unwraps the envelope with
+// `crc32::strip_crc` then `header::read_header`.
+//
+// A generator may also gzip the framed file (GZIP_SUFFIX, e.g.
+// build/input.bin.gz) to keep large price histories manageable. Host tools
+// read either through read_input_file; guests only ever see the raw bytes.
+//
+// Build scripts can't link a library, so the market build.rs includes this
+// file by path; it only refers to its siblings through `super`.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use super::{crc32, header};
+
+// A path ending in this holds a gzip-compressed input.bin.
+pub const GZIP_SUFFIX: &str = ".gz";
+
+// header | payload | CRC, ready to be written out.
+pub fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(header::HEADER_LEN + payload.len() + crc32::CRC_LEN);
+    header::write_header(&mut out);
+    out.extend_from_slice(payload);
+    crc32::append_crc(&mut out);
+    out
+}
+
+// Frame `payload` and write it to `path` atomically.
+pub fn write_input_bin(path: &str, payload: &[u8]) -> io::Result<()> {
+    write_atomic(path, &frame(payload))
+}
+
+// Frame `payload`, gzip it and write it to `path` (which should end in
+// GZIP_SUFFIX) atomically.
+pub fn write_input_bin_gz(path: &str, payload: &[u8]) -> io::Result<()> {
+    write_atomic(path, &gzip(&frame(payload))?)
+}
+
+pub fn gzip(contents: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contents)?;
+    encoder.finish()
+}
+
+// The input.bin at `path`, decompressed when the path ends in GZIP_SUFFIX.
+pub fn read_input_file(path: &str) -> io::Result<Vec<u8>> {
+    let contents = fs::read(path)?;
+    if !path.ends_with(GZIP_SUFFIX) {
+        return Ok(contents);
+    }
+    let mut raw = Vec::new();
+    GzDecoder::new(contents.as_slice()).read_to_end(&mut raw)?;
+    Ok(raw)
+}
+
+// Write to `<path>.tmp` and rename it into place, so a cancelled build
+// leaves the previous file rather than a truncated one.
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
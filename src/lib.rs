@@ -0,0 +1,351 @@
+// Shared definition of the input.bin format.
+//
+// input.bin = header | n (u64 LE) | algorithm (u8) | checkpoint interval (u64 LE)
+//             | secret length (u16 LE) | secret | CRC-32
+//
+// Everything that writes or reads input.bin goes through `serialize_input`
+// and `parse_input` so the layout lives in exactly one place.
+
+// The modules shared with the market crate import from alloc, since it
+// builds them no_std
+extern crate alloc;
+
+pub mod codec;
+pub mod crc32;
+pub mod fixed;
+pub mod hasher;
+pub mod header;
+pub mod input_gen;
+pub mod resume;
+pub mod secret;
+
+use serde::{Deserialize, Serialize};
+
+use crc32::CrcError;
+use hasher::Algorithm;
+use header::HeaderError;
+use secret::{Padding, SecretError};
+
+// Value published in output slot 0 when the payload is too short for `n`.
+pub const ERR_TOO_SHORT: u32 = 0xE000_0005;
+
+// Value published in output slot 0 when `n` is above the guest's ceiling.
+pub const ERR_TOO_MANY_ITERATIONS: u32 = 0xE000_0007;
+
+// Value published in output slot 0 when `n` is above MAX_ROUNDS.
+pub const ERR_ROUNDS_OVERFLOW: u32 = 0xE000_0009;
+
+// Bytes between the header and the private section.
+pub const PUBLIC_LEN: usize = 8 + 1 + 8;
+
+// Smallest valid input.bin: header, public fields, an empty secret and the CRC.
+pub const MIN_INPUT_LEN: usize = header::HEADER_LEN + PUBLIC_LEN + 2 + crc32::CRC_LEN;
+
+// Largest `n` that `validate_input` accepts unless told otherwise, and the
+// guest's default ceiling, so a typo doesn't turn into a multi-hour
+// proving run.
+pub const DEFAULT_MAX_N: u64 = 1_000_000;
+
+// Largest `n` any build of the guest runs, whatever ZKMD_MAX_ITERATIONS
+// says: the round counter has to fit a usize on 32-bit targets too, so the
+// same input.bin proves the same number of rounds everywhere.
+pub const MAX_ROUNDS: u64 = u32::MAX as u64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Public {
+    pub n: u64,
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    // Print the hash every this many rounds; 0 disables checkpoints
+    #[serde(default)]
+    pub checkpoint_interval: u64,
+    // Applied to the secret by `serialize_input`; recorded here (and so in
+    // public.json) so the same input.bin can be rebuilt
+    #[serde(default)]
+    pub secret_padding: Padding,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Private {
+    // May be left out of input.json when preprocess gets the secret from
+    // --secret-file or ZKMD_SECRET instead
+    #[serde(default)]
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub public: Public,
+    pub private: Private,
+}
+
+// Borrowed view of a parsed input.bin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedInput<'a> {
+    pub n: u64,
+    pub algorithm: Algorithm,
+    pub checkpoint_interval: u64,
+    pub secret: &'a [u8],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    Crc(CrcError),
+    Header(HeaderError),
+    TooShort { expected: usize, actual: usize },
+    BadAlgorithm(u8),
+    Secret(SecretError),
+}
+
+impl InputError {
+    // Error code the guest publishes in output slot 0.
+    pub fn code(&self) -> u32 {
+        match self {
+            InputError::Crc(_) => crc32::ERR_BAD_CRC,
+            InputError::Header(e) => e.code(),
+            InputError::TooShort { .. } => ERR_TOO_SHORT,
+            InputError::BadAlgorithm(_) => hasher::ERR_BAD_ALGORITHM,
+            InputError::Secret(_) => secret::ERR_BAD_SECRET,
+        }
+    }
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Crc(CrcError::Truncated(len)) => {
+                write!(f, "input.bin too short for CRC trailer: {} bytes", len)
+            }
+            InputError::Crc(CrcError::Mismatch { expected, actual }) => {
+                write!(f, "CRC mismatch: trailer {:#010x}, computed {:#010x}", expected, actual)
+            }
+            InputError::Header(HeaderError::BadMagic) => write!(f, "bad magic, expected ZKMD"),
+            InputError::Header(HeaderError::BadVersion(v)) => write!(
+                f,
+                "unsupported format version {} (expected {})",
+                v,
+                header::FORMAT_VERSION
+            ),
+            InputError::TooShort { expected, actual } => {
+                write!(f, "payload too short: expected >= {} bytes, got {}", expected, actual)
+            }
+            InputError::BadAlgorithm(b) => write!(f, "unknown hash algorithm {}", b),
+            InputError::Secret(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+// `n` doesn't fit the round counter (see MAX_ROUNDS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundsOverflow(pub u64);
+
+impl std::fmt::Display for RoundsOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "n = {} does not fit the round counter (maximum {})", self.0, MAX_ROUNDS)
+    }
+}
+
+impl std::error::Error for RoundsOverflow {}
+
+// The number of rounds for `n`, or an error when it is above MAX_ROUNDS.
+pub fn round_count(n: u64) -> Result<usize, RoundsOverflow> {
+    if n > MAX_ROUNDS {
+        return Err(RoundsOverflow(n));
+    }
+    usize::try_from(n).map_err(|_| RoundsOverflow(n))
+}
+
+// Problems `validate_input` reports about an input.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    EmptySecret,
+    SecretTooLong(usize),
+    ZeroRounds,
+    TooManyRounds { n: u64, max: u64 },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::EmptySecret => write!(f, "private.secret is empty"),
+            ValidationError::SecretTooLong(len) => write!(
+                f,
+                "private.secret is {} bytes, maximum is {}",
+                len,
+                secret::MAX_SECRET_LEN
+            ),
+            ValidationError::ZeroRounds => write!(f, "public.n is 0, which proves nothing"),
+            ValidationError::TooManyRounds { n, max } => {
+                write!(f, "public.n is {}, maximum is {}", n, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+// Every problem with `input`, not just the first; empty when it's valid.
+pub fn validate_input(input: &Input, max_n: u64) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let secret_len = input.private.secret.len();
+    if secret_len == 0 {
+        errors.push(ValidationError::EmptySecret);
+    } else if secret_len > secret::MAX_SECRET_LEN {
+        errors.push(ValidationError::SecretTooLong(secret_len));
+    }
+    if input.public.n == 0 {
+        errors.push(ValidationError::ZeroRounds);
+    } else if input.public.n > max_n {
+        errors.push(ValidationError::TooManyRounds { n: input.public.n, max: max_n });
+    }
+    errors
+}
+
+pub fn serialize_input(input: &Input) -> Result<Vec<u8>, SecretError> {
+    let secret = secret::pad(input.private.secret.as_bytes(), input.public.secret_padding);
+    serialize_raw(&input.public, &secret)
+}
+
+// Like `serialize_input`, for secrets that aren't valid UTF-8. `secret` is
+// written as given: `public.secret_padding` isn't applied.
+pub fn serialize_raw(public: &Public, secret: &[u8]) -> Result<Vec<u8>, SecretError> {
+    Ok(input_gen::frame(&serialize_payload(public, secret)?))
+}
+
+// The part of input.bin between the header and the CRC, for
+// `input_gen::write_input_bin`.
+pub fn serialize_payload(public: &Public, secret: &[u8]) -> Result<Vec<u8>, SecretError> {
+    let mut out = Vec::with_capacity(PUBLIC_LEN + 2 + secret.len());
+    codec::write_u64_le(&mut out, public.n);
+    out.push(public.algorithm as u8);
+    codec::write_u64_le(&mut out, public.checkpoint_interval);
+    secret::write_secret(&mut out, secret)?;
+    Ok(out)
+}
+
+pub fn parse_input(input: &[u8]) -> Result<ParsedInput<'_>, InputError> {
+    let body = crc32::strip_crc(input).map_err(InputError::Crc)?;
+    let payload = header::read_header(body).map_err(InputError::Header)?;
+    if payload.len() < PUBLIC_LEN {
+        return Err(InputError::TooShort { expected: PUBLIC_LEN, actual: payload.len() });
+    }
+    let (public, rest) = payload.split_at(PUBLIC_LEN);
+    let n = codec::read_u64_le(&public[0..]);
+    let algorithm = Algorithm::from_byte(public[8]).ok_or(InputError::BadAlgorithm(public[8]))?;
+    let checkpoint_interval = codec::read_u64_le(&public[9..]);
+    let secret = secret::read_secret(rest).map_err(InputError::Secret)?;
+    Ok(ParsedInput { n, algorithm, checkpoint_interval, secret })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use secret::Padding;
+
+    fn algorithms() -> impl Strategy<Value = Algorithm> {
+        prop_oneof![Just(Algorithm::Sha256), Just(Algorithm::Keccak256)]
+    }
+
+    fn public(n: u64, algorithm: Algorithm, checkpoint_interval: u64) -> Public {
+        Public { n, algorithm, checkpoint_interval, secret_padding: Padding::None }
+    }
+
+    // Serializes, parses back and checks every field survived.
+    fn assert_round_trip(public: &Public, secret: &[u8]) {
+        let bin = serialize_raw(public, secret).unwrap();
+        assert_eq!(bin.len(), MIN_INPUT_LEN + secret.len());
+        let parsed = parse_input(&bin).unwrap();
+        assert_eq!(parsed.n, public.n);
+        assert_eq!(parsed.algorithm, public.algorithm);
+        assert_eq!(parsed.checkpoint_interval, public.checkpoint_interval);
+        assert_eq!(parsed.secret, secret);
+    }
+
+    proptest! {
+        #[test]
+        fn raw_round_trip(
+            n in any::<u64>(),
+            algorithm in algorithms(),
+            checkpoint_interval in any::<u64>(),
+            secret in proptest::collection::vec(any::<u8>(), 0..=secret::MAX_SECRET_LEN),
+        ) {
+            assert_round_trip(&public(n, algorithm, checkpoint_interval), &secret);
+        }
+
+        // What preprocess writes: an Input from JSON, through serialize_input
+        #[test]
+        fn input_round_trip(n in 1..=DEFAULT_MAX_N, algorithm in algorithms(), secret in "\\PC{1,256}") {
+            let input = Input { public: public(n, algorithm, 0), private: Private { secret } };
+            let bin = serialize_input(&input).unwrap();
+            let parsed = parse_input(&bin).unwrap();
+            prop_assert_eq!(parsed.n, input.public.n);
+            prop_assert_eq!(parsed.algorithm, input.public.algorithm);
+            prop_assert_eq!(parsed.secret, input.private.secret.as_bytes());
+        }
+
+        // Dropping bytes (or flipping one) never parses as some other input
+        #[test]
+        fn damage_is_rejected(
+            secret in proptest::collection::vec(any::<u8>(), 0..64),
+            cut in 1usize..8,
+            flip in any::<usize>(),
+        ) {
+            let bin = serialize_raw(&public(5, Algorithm::Sha256, 0), &secret).unwrap();
+            prop_assert!(parse_input(&bin[..bin.len() - cut]).is_err());
+            let mut flipped = bin.clone();
+            flipped[flip % bin.len()] ^= 0x01;
+            prop_assert!(parse_input(&flipped).is_err());
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let max_n = public(u64::MAX, Algorithm::Keccak256, u64::MAX);
+        assert_round_trip(&max_n, &[]);
+        assert_round_trip(&max_n, &[0xff; 32]);
+        assert_round_trip(&public(DEFAULT_MAX_N, Algorithm::Sha256, 1), &[0; 32]);
+        assert_round_trip(&public(0, Algorithm::Sha256, 0), &[7; secret::MAX_SECRET_LEN]);
+
+        let too_long = [0; secret::MAX_SECRET_LEN + 1];
+        let err = serialize_raw(&max_n, &too_long).unwrap_err();
+        assert_eq!(err, SecretError::TooLong(secret::MAX_SECRET_LEN + 1));
+    }
+
+    #[test]
+    fn rounds_fit_the_counter() {
+        for (n, ok) in [(0, true), (MAX_ROUNDS, true), (MAX_ROUNDS + 1, false), (u64::MAX, false)] {
+            let bin = serialize_raw(&public(n, Algorithm::Sha256, 0), &[1; 32]).unwrap();
+            let parsed = parse_input(&bin).unwrap();
+            match round_count(parsed.n) {
+                Ok(rounds) => assert!(ok && rounds as u64 == n, "n = {}", n),
+                Err(e) => assert!(!ok && e == RoundsOverflow(n), "n = {}", n),
+            }
+        }
+    }
+
+    #[test]
+    fn padding_is_applied_before_serializing() {
+        let mut input = Input {
+            public: public(5, Algorithm::Sha256, 0),
+            private: Private { secret: "abc".to_string() },
+        };
+        let cases = [
+            (Padding::None, b"abc".to_vec()),
+            (Padding::Fill(0), [&b"abc"[..], &[0; 29]].concat()),
+            (Padding::Pkcs7, [&b"abc"[..], &[29; 29]].concat()),
+        ];
+        for (padding, secret) in cases {
+            input.public.secret_padding = padding;
+            let bin = serialize_input(&input).unwrap();
+            assert_eq!(parse_input(&bin).unwrap().secret, secret.as_slice(), "{:?}", padding);
+        }
+
+        // A 32-byte secret is left as it is
+        input.private.secret = "k".repeat(secret::PADDED_LEN);
+        let bin = serialize_input(&input).unwrap();
+        assert_eq!(parse_input(&bin).unwrap().secret, input.private.secret.as_bytes());
+    }
+}
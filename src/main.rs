@@ -1,28 +1,357 @@
 #![no_main]
 ziskos::entrypoint!(main);
 
-use ziskos::{read_input, set_output};
-use sha2::{Digest, Sha256};
-use std::convert::TryInto;
+use log::{debug, error, info, warn};
+use ziskos::set_output;
+use sha_hasher::codec::{hex, write_u32_be_chunks};
+use sha_hasher::hasher::{hash_chain, resume_chain};
+use sha_hasher::{
+    parse_input, round_count, InputError, RoundsOverflow, DEFAULT_MAX_N, ERR_ROUNDS_OVERFLOW,
+    ERR_TOO_MANY_ITERATIONS, ERR_TOO_SHORT, MIN_INPUT_LEN,
+};
+use std::fmt;
+
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+use sha_hasher::resume::{self, Checkpoint};
+
+// Where a native run (`cargo run`) reads input.bin from unless
+// ZKMD_INPUT_BIN names another file. Inside the zkVM the input comes from
+// the emulator's or prover's `-i` instead.
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+const DEFAULT_INPUT_BIN: &str = "build/input.bin";
+
+// Where a native run saves its place (resume.rs) unless
+// ZKMD_CHECKPOINT_FILE names another file.
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+const DEFAULT_CHECKPOINT_FILE: &str = "build/checkpoint.txt";
+
+#[derive(Debug)]
+enum MainError {
+    // A zero-byte input.bin, most often left by a build step that failed
+    Empty,
+    TooShort(usize),
+    TooManyIterations { n: u64, max: u64 },
+    RoundsOverflow(RoundsOverflow),
+    Input(InputError),
+}
+
+impl MainError {
+    fn code(&self) -> u32 {
+        match self {
+            MainError::Empty | MainError::TooShort(_) => ERR_TOO_SHORT,
+            MainError::TooManyIterations { .. } => ERR_TOO_MANY_ITERATIONS,
+            MainError::RoundsOverflow(_) => ERR_ROUNDS_OVERFLOW,
+            MainError::Input(e) => e.code(),
+        }
+    }
+}
+
+impl fmt::Display for MainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MainError::Empty => write!(f, "input.bin is empty — did the build step fail?"),
+            MainError::TooShort(len) => write!(
+                f,
+                "input.bin too short: expected >= {} bytes, got {}",
+                MIN_INPUT_LEN, len
+            ),
+            MainError::TooManyIterations { n, max } => {
+                write!(f, "n = {} exceeds the iteration ceiling of {}", n, max)
+            }
+            MainError::RoundsOverflow(e) => write!(f, "{}", e),
+            MainError::Input(e) => write!(f, "invalid input.bin: {}", e),
+        }
+    }
+}
+
+impl From<RoundsOverflow> for MainError {
+    fn from(e: RoundsOverflow) -> Self {
+        MainError::RoundsOverflow(e)
+    }
+}
+
+impl From<InputError> for MainError {
+    fn from(e: InputError) -> Self {
+        MainError::Input(e)
+    }
+}
+
+// Ceiling on n, set at build time with ZKMD_MAX_ITERATIONS. Parsed in a
+// const, so a value that isn't a number fails the build instead of every run.
+const MAX_ITERATIONS: u64 = match option_env!("ZKMD_MAX_ITERATIONS") {
+    Some(v) => match u64::from_str_radix(v, 10) {
+        Ok(max) => max,
+        Err(_) => panic!("ZKMD_MAX_ITERATIONS must be a number"),
+    },
+    None => DEFAULT_MAX_N,
+};
+
+// Diagnostics go through `log`; stdout carries only the results (the
+// checkpoint hashes input.bin asks for and `--hex`'s line). Info messages
+// read as they are and the rest take their level ("error: ..."), on either
+// route below.
+fn log_line(record: &log::Record) -> String {
+    match record.level() {
+        log::Level::Info => record.args().to_string(),
+        level => format!("{}: {}", level.as_str().to_lowercase(), record.args()),
+    }
+}
+
+// A native run logs to stderr. RUST_LOG sets the filter (info and above by
+// default, which includes progress) unless -v (debug: the parsed input) or
+// -vv (trace) overrides it.
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+fn init_logging() {
+    use std::io::Write;
+
+    let verbose: usize = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.strip_prefix('-').filter(|v| !v.is_empty() && v.bytes().all(|b| b == b'v')).map(str::len))
+        .sum();
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    match verbose {
+        0 => {}
+        1 => {
+            builder.filter_level(log::LevelFilter::Debug);
+        }
+        _ => {
+            builder.filter_level(log::LevelFilter::Trace);
+        }
+    }
+    builder.format(|buf, record| writeln!(buf, "{}", log_line(record))).init();
+}
+
+// The zkVM has no stderr and no environment to read RUST_LOG from, so a
+// guest logs info and above to stdout, the emulator's console.
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+struct Console;
+
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+impl log::Log for Console {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            println!("{}", log_line(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+fn init_logging() {
+    static CONSOLE: Console = Console;
+    if log::set_logger(&CONSOLE).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+}
+
+// The input buffer. ziskos's native read_input always opens
+// build/input.bin, so a native run reads the file itself to honor
+// ZKMD_INPUT_BIN (gunzipping a .gz path, as preprocess --compress writes),
+// and stops with the path if it is missing rather than publishing an error
+// code for an input that was never read, or was left empty.
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+fn load_input() -> Vec<u8> {
+    ziskos::read_input()
+}
+
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+fn load_input() -> Vec<u8> {
+    let path = std::env::var("ZKMD_INPUT_BIN").unwrap_or_else(|_| DEFAULT_INPUT_BIN.to_string());
+    match sha_hasher::input_gen::read_input_file(&path) {
+        Ok(input) if input.is_empty() => {
+            error!("input file {} is empty — did the build step fail? (run `cargo build` to regenerate it)", path);
+            std::process::exit(1);
+        }
+        Ok(input) => input,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            error!("input file {} does not exist (set ZKMD_INPUT_BIN or run `cargo build` to generate it)", path);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("cannot read input file {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Whether to also print the final hash as one hex line. A native run takes
+// `--hex` on its command line; the zkVM passes no arguments, so a guest
+// build sets ZKMD_HEX_OUTPUT at build time instead.
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+fn hex_output() -> bool {
+    option_env!("ZKMD_HEX_OUTPUT").is_some()
+}
+
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+fn hex_output() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--hex")
+}
+
+// A native run saves the hash to a checkpoint file every
+// ZKMD_CHECKPOINT_EVERY rounds (unset or 0: never), and with `--resume`
+// continues from the file's round instead of round 1. The zkVM has no disk,
+// so a guest always runs the whole chain.
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+struct Resume;
+
+#[cfg(all(target_os = "zkvm", target_vendor = "zisk"))]
+impl Resume {
+    fn from_env(_input: &[u8]) -> Resume {
+        Resume
+    }
+
+    fn start(&self, _n: u64) -> Option<(u64, [u8; 32])> {
+        None
+    }
+
+    fn after_round(&self, _done: u64, _hash: &[u8; 32]) {}
+}
+
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+struct Resume {
+    path: String,
+    every: u64,
+    resume: bool,
+    input_digest: [u8; 32],
+}
+
+#[cfg(not(all(target_os = "zkvm", target_vendor = "zisk")))]
+impl Resume {
+    fn from_env(input: &[u8]) -> Resume {
+        let every = match std::env::var("ZKMD_CHECKPOINT_EVERY") {
+            Ok(v) => v.parse().unwrap_or_else(|_| {
+                error!("ZKMD_CHECKPOINT_EVERY must be a number of rounds, got {:?}", v);
+                std::process::exit(1);
+            }),
+            Err(_) => 0,
+        };
+        Resume {
+            path: std::env::var("ZKMD_CHECKPOINT_FILE").unwrap_or_else(|_| DEFAULT_CHECKPOINT_FILE.to_string()),
+            every,
+            resume: std::env::args().skip(1).any(|arg| arg == "--resume"),
+            input_digest: resume::input_digest(input),
+        }
+    }
+
+    // The round and hash to continue from, or None to start at round 1:
+    // without --resume, or with no checkpoint file yet. One saved from a
+    // different input.bin stops the run rather than being ignored.
+    fn start(&self, n: u64) -> Option<(u64, [u8; 32])> {
+        if !self.resume {
+            return None;
+        }
+        let saved = match Checkpoint::load(&self.path) {
+            Ok(saved) => saved,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("no checkpoint at {}, starting from round 1", self.path);
+                return None;
+            }
+            Err(e) => {
+                error!("cannot resume from {}: {}", self.path, e);
+                std::process::exit(1);
+            }
+        };
+        if saved.input_digest != self.input_digest {
+            error!("{} was saved from a different input.bin; delete it to start over", self.path);
+            std::process::exit(1);
+        }
+        if saved.done > n {
+            error!("{} is at round {}, past n = {}", self.path, saved.done, n);
+            std::process::exit(1);
+        }
+        info!("resuming from round {}/{} ({})", saved.done, n, self.path);
+        Some((saved.done, saved.hash))
+    }
+
+    // A failed save is reported but doesn't stop the run: the previous
+    // checkpoint is still there to resume from.
+    fn after_round(&self, done: u64, hash: &[u8; 32]) {
+        if self.every == 0 || !done.is_multiple_of(self.every) {
+            return;
+        }
+        let checkpoint = Checkpoint { input_digest: self.input_digest, done, hash: *hash };
+        if let Err(e) = checkpoint.save(&self.path) {
+            warn!("cannot save checkpoint to {}: {}", self.path, e);
+        }
+    }
+}
 
 fn main() {
+    init_logging();
+    // The entrypoint can't return a Result, so report failures here
+    if let Err(e) = run() {
+        error!("{}", e);
+        set_output(0, e.code());
+    }
+}
+
+// Prints the hash after round `done` when it falls on the checkpoint
+// interval, so host and guest runs can be compared round by round.
+fn checkpoint(interval: u64, done: u64, hash: &[u8; 32]) {
+    if interval == 0 || !done.is_multiple_of(interval) {
+        return;
+    }
+    println!("checkpoint {}: {}", done, hex(hash));
+}
+
+fn run() -> Result<(), MainError> {
     // Read the input data as a byte array from ZisK
-    let input: Vec<u8> = read_input();
-    let n = u64::from_le_bytes(input[0..8].try_into().unwrap());
-    let mut hash = [0u8; 32];
-    hash.copy_from_slice(&input[8..40]);
+    let input: Vec<u8> = load_input();
+    if input.is_empty() {
+        return Err(MainError::Empty);
+    }
+    if input.len() < MIN_INPUT_LEN {
+        return Err(MainError::TooShort(input.len()));
+    }
 
-    // Compute SHA-256 hashing 'n' times
-    for _ in 0..n {
-        let mut hasher = Sha256::new();
-        hasher.update(hash);
-        let digest = &hasher.finalize();
-        hash = Into::<[u8; 32]>::into(*digest);
+    // CRC, header and layout checks all live in the shared parser
+    let parsed = parse_input(&input)?;
+    let n = parsed.n;
+    let secret = parsed.secret;
+    let hasher = parsed.algorithm.hasher();
+    let checkpoint_interval = parsed.checkpoint_interval;
+    // Never the secret itself, only its length
+    debug!(
+        "input.bin: {} bytes, n = {}, algorithm = {}, checkpoint interval = {}, secret length = {}",
+        input.len(),
+        n,
+        parsed.algorithm.name(),
+        checkpoint_interval,
+        secret.len()
+    );
+    // Checked before the ceiling, which a build can raise past it
+    round_count(n)?;
+    if n > MAX_ITERATIONS {
+        return Err(MainError::TooManyIterations { n, max: MAX_ITERATIONS });
     }
 
-    // Output the final hash in 8 chunks (public output)
-    for i in 0..8 {
-        let val = u32::from_be_bytes(hash[i * 4..i * 4 + 4].try_into().unwrap());
-        set_output(i, val);
+    // Report every tenth of n, plus any checkpoints input.bin asked for,
+    // and save the hash for --resume when asked to
+    let resume = Resume::from_env(&input);
+    let progress_step = (n / 10).max(1);
+    let mut on_round = |done: u64, hash: &[u8; 32]| {
+        checkpoint(checkpoint_interval, done, hash);
+        if done.is_multiple_of(progress_step) {
+            info!("progress: {}/{} rounds", done, n);
+        }
+        resume.after_round(done, hash);
+    };
+    let hash = match resume.start(n) {
+        Some((done, hash)) => resume_chain(hasher, hash, done, n, &mut on_round),
+        None => hash_chain(hasher, secret, n, &mut on_round),
+    };
+
+    // Output the final hash in 8 big-endian chunks (public output)
+    write_u32_be_chunks(&hash, set_output);
+    // The same 32 bytes in one line, in chunk order
+    if hex_output() {
+        println!("hash: {}", hex(&hash));
     }
-} 
\ No newline at end of file
+
+    Ok(())
+}
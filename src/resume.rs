@@ -0,0 +1,136 @@
+// Where a long native run got to, saved so a crashed run can pick up from
+// there instead of round 1 (`--resume` in main.rs). Unlike input.bin's
+// checkpoint interval, which only prints the hash, this writes it to a file:
+//
+//   zkmd-checkpoint 1
+//   input <SHA-256 of input.bin, hex>
+//   round <rounds done>
+//   hash <the hash after that round, hex>
+//
+// The input digest ties the file to the input.bin it was saved from, so a
+// resume against a different n, algorithm or secret is refused rather than
+// finishing someone else's chain. The hash is as sensitive as the output:
+// anyone holding it can compute every later round.
+
+use std::fmt;
+use std::io;
+
+use sha2::{Digest, Sha256};
+
+use super::codec::{digest_from_hex, hex};
+use super::input_gen;
+
+const FIRST_LINE: &str = "zkmd-checkpoint 1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub input_digest: [u8; 32],
+    // At least 1: hash_chain's first round hashes the secret itself
+    pub done: u64,
+    pub hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    BadFormat(String),
+    ZeroRounds,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::BadFormat(line) => write!(f, "not a checkpoint file: unexpected {:?}", line),
+            CheckpointError::ZeroRounds => write!(f, "checkpoint at round 0, which hash_chain never saves"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+pub fn input_digest(input: &[u8]) -> [u8; 32] {
+    Sha256::digest(input).into()
+}
+
+impl Checkpoint {
+    // Whether this was saved from `input` (the raw input.bin bytes).
+    pub fn is_for(&self, input: &[u8]) -> bool {
+        self.input_digest == input_digest(input)
+    }
+
+    pub fn to_text(self) -> String {
+        format!(
+            "{}\ninput {}\nround {}\nhash {}\n",
+            FIRST_LINE,
+            hex(&self.input_digest),
+            self.done,
+            hex(&self.hash)
+        )
+    }
+
+    pub fn parse(text: &str) -> Result<Checkpoint, CheckpointError> {
+        let mut lines = text.lines();
+        let first = lines.next().unwrap_or("");
+        if first != FIRST_LINE {
+            return Err(CheckpointError::BadFormat(first.to_string()));
+        }
+        // The value after `key `, on the next line
+        let mut next = |key: &str| {
+            let line = lines.next().unwrap_or("");
+            line.strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix(' '))
+                .ok_or_else(|| CheckpointError::BadFormat(line.to_string()))
+        };
+        let digest = |text: &str| digest_from_hex(text).ok_or_else(|| CheckpointError::BadFormat(text.to_string()));
+        let input_digest = next("input").and_then(digest)?;
+        let done = next("round")?;
+        let done = done.parse().map_err(|_| CheckpointError::BadFormat(done.to_string()))?;
+        let hash = next("hash").and_then(digest)?;
+        if done == 0 {
+            return Err(CheckpointError::ZeroRounds);
+        }
+        Ok(Checkpoint { input_digest, done, hash })
+    }
+
+    // Written atomically, so a crash mid-write leaves the previous one.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        input_gen::write_atomic(path, self.to_text().as_bytes())
+    }
+
+    pub fn load(path: &str) -> io::Result<Checkpoint> {
+        let text = std::fs::read_to_string(path)?;
+        Checkpoint::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{hash_chain, resume_chain, Algorithm};
+
+    #[test]
+    fn resuming_finishes_the_same_chain() {
+        let hasher = Algorithm::Sha256.hasher();
+        let secret = [0xffu8; 32];
+        let input = b"stand-in for input.bin";
+        let mut saved = None;
+        let full = hash_chain(hasher, &secret, 10, |done, hash| {
+            if done == 4 {
+                saved = Some(Checkpoint { input_digest: input_digest(input), done, hash: *hash });
+            }
+        });
+
+        // Through the file format and back
+        let saved = Checkpoint::parse(&saved.unwrap().to_text()).unwrap();
+        assert!(saved.is_for(input));
+        assert!(!saved.is_for(b"another input.bin"));
+        assert_eq!(resume_chain(hasher, saved.hash, saved.done, 10, |_, _| {}), full);
+        // Resuming at the last round is the answer
+        assert_eq!(resume_chain(hasher, full, 10, 10, |_, _| {}), full);
+
+        let text = saved.to_text();
+        assert!(Checkpoint::parse(&text.replace("round 4", "round 0")).is_err());
+        assert!(Checkpoint::parse(&text.replace("round 4", "round four")).is_err());
+        assert!(Checkpoint::parse(&text[..text.len() - 3]).is_err());
+        assert!(Checkpoint::parse("").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+// Length-prefixed private section of input.bin.
+//
+// Layout (follows the public u64 `n`):
+//   [0..2]     secret length L (u16, little-endian)
+//   [2..2 + L] secret bytes, after any Padding
+//
+// The padding is applied when input.bin is written, so the guest only
+// ever sees the padded bytes.
+
+use serde::{Deserialize, Serialize};
+
+use super::codec;
+
+pub const MAX_SECRET_LEN: usize = 1024;
+
+// Length `pad` brings a shorter secret up to: one hash-sized key.
+pub const PADDED_LEN: usize = 32;
+
+// Value published in output slot 0 when the private section is malformed.
+pub const ERR_BAD_SECRET: u32 = 0xE000_0003;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretError {
+    TooLong(usize),
+    Truncated { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::TooLong(len) => {
+                write!(f, "secret is {} bytes, maximum is {}", len, MAX_SECRET_LEN)
+            }
+            SecretError::Truncated { expected, actual } => {
+                write!(f, "secret section truncated: expected {} bytes, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+// How a secret shorter than PADDED_LEN is filled out before it goes into
+// input.bin. None (the default) stores it as given; Fill appends copies of
+// one byte, and Pkcs7 appends PADDED_LEN - len copies of that count, as
+// PKCS#7 does for a 32-byte block. Secrets of PADDED_LEN bytes or more are
+// never padded.
+//
+// Named "none", "pkcs7", or the fill byte in decimal or 0x-prefixed hex.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Padding {
+    #[default]
+    None,
+    Fill(u8),
+    Pkcs7,
+}
+
+impl Padding {
+    pub fn from_name(name: &str) -> Option<Padding> {
+        match name {
+            "none" => Some(Padding::None),
+            "pkcs7" => Some(Padding::Pkcs7),
+            _ => {
+                let byte = match name.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => name.parse(),
+                };
+                byte.ok().map(Padding::Fill)
+            }
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Padding::None => "none".to_string(),
+            Padding::Fill(byte) => format!("{:#04x}", byte),
+            Padding::Pkcs7 => "pkcs7".to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for Padding {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        Padding::from_name(&name).ok_or_else(|| format!("unknown padding {:?}, expected none, pkcs7 or a byte", name))
+    }
+}
+
+impl From<Padding> for String {
+    fn from(padding: Padding) -> String {
+        padding.name()
+    }
+}
+
+// `secret` padded to PADDED_LEN bytes with `padding`, or unchanged when it's
+// already that long or the padding is None.
+pub fn pad(secret: &[u8], padding: Padding) -> Vec<u8> {
+    let mut out = secret.to_vec();
+    let missing = PADDED_LEN.saturating_sub(secret.len());
+    match padding {
+        Padding::None => {}
+        Padding::Fill(byte) => out.resize(secret.len() + missing, byte),
+        Padding::Pkcs7 => out.resize(secret.len() + missing, missing as u8),
+    }
+    out
+}
+
+pub fn write_secret(out: &mut Vec<u8>, secret: &[u8]) -> Result<(), SecretError> {
+    if secret.len() > MAX_SECRET_LEN {
+        return Err(SecretError::TooLong(secret.len()));
+    }
+    codec::write_u16_le(out, secret.len() as u16);
+    out.extend_from_slice(secret);
+    Ok(())
+}
+
+// Returns the secret bytes from the start of the private section.
+pub fn read_secret(section: &[u8]) -> Result<&[u8], SecretError> {
+    if section.len() < 2 {
+        return Err(SecretError::Truncated { expected: 2, actual: section.len() });
+    }
+    let len = codec::read_u16_le(section) as usize;
+    if len > MAX_SECRET_LEN {
+        return Err(SecretError::TooLong(len));
+    }
+    let body = &section[2..];
+    if body.len() < len {
+        return Err(SecretError::Truncated { expected: len, actual: body.len() });
+    }
+    Ok(&body[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(secret: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_secret(&mut out, secret).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trips() {
+        assert_eq!(section(b"key"), [3, 0, b'k', b'e', b'y']);
+        assert_eq!(read_secret(&section(b"key")), Ok(&b"key"[..]));
+        assert_eq!(read_secret(&section(b"")), Ok(&[][..]));
+        let longest = vec![0xAB; MAX_SECRET_LEN];
+        assert_eq!(read_secret(&section(&longest)), Ok(&longest[..]));
+        // Whatever follows the secret isn't part of it
+        let mut section = section(b"key");
+        section.push(0xFF);
+        assert_eq!(read_secret(&section), Ok(&b"key"[..]));
+    }
+
+    #[test]
+    fn rejects_bad_sections() {
+        let too_long = vec![0; MAX_SECRET_LEN + 1];
+        assert_eq!(write_secret(&mut Vec::new(), &too_long), Err(SecretError::TooLong(MAX_SECRET_LEN + 1)));
+        let mut oversized = (MAX_SECRET_LEN as u16 + 1).to_le_bytes().to_vec();
+        oversized.extend_from_slice(&too_long);
+        assert_eq!(read_secret(&oversized), Err(SecretError::TooLong(MAX_SECRET_LEN + 1)));
+
+        assert_eq!(read_secret(&[3]), Err(SecretError::Truncated { expected: 2, actual: 1 }));
+        assert_eq!(read_secret(&section(b"key")[..4]), Err(SecretError::Truncated { expected: 3, actual: 2 }));
+    }
+
+    #[test]
+    fn padding() {
+        for padding in [Padding::None, Padding::Pkcs7, Padding::Fill(0), Padding::Fill(0xFF)] {
+            assert_eq!(Padding::from_name(&padding.name()), Some(padding));
+        }
+        assert_eq!(Padding::from_name("7"), Some(Padding::Fill(7)));
+        assert_eq!(Padding::from_name("256"), None);
+        assert!(Padding::try_from("zero".to_string()).is_err());
+
+        assert_eq!(pad(b"key", Padding::None), b"key");
+        let filled = pad(b"key", Padding::Fill(0));
+        assert_eq!((filled.len(), &filled[..3], filled[31]), (PADDED_LEN, &b"key"[..], 0));
+        let pkcs7 = pad(b"key", Padding::Pkcs7);
+        assert_eq!(pkcs7.len(), PADDED_LEN);
+        assert!(pkcs7[3..].iter().all(|&b| b as usize == PADDED_LEN - 3));
+        // Already long enough: unchanged
+        let long = [1u8; PADDED_LEN + 1];
+        assert_eq!(pad(&long, Padding::Pkcs7), long);
+    }
+}